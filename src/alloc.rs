@@ -0,0 +1,126 @@
+#[cfg(feature = "alloc-stats")]
+pub use counting::measure;
+
+/// Runs `func`, returning its heap usage when built with the `alloc-stats` feature.
+#[cfg(not(feature = "alloc-stats"))]
+pub fn measure<R>(func: impl FnOnce() -> R) -> (R, Option<Usage>) {
+    (func(), None)
+}
+
+/// Heap usage of a single measured call.
+#[derive(Copy, Clone, Debug)]
+pub struct Usage {
+    /// Amount of allocations (including reallocations) made.
+    pub allocations: usize,
+    /// Largest amount of bytes allocated on top of what was already allocated before the call.
+    pub peak: usize,
+}
+
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+        let mut peak = self.peak as f64;
+        let mut unit = 0;
+
+        while peak >= 1024.0 && unit < UNITS.len() - 1 {
+            peak /= 1024.0;
+            unit += 1;
+        }
+
+        write!(
+            f,
+            "allocations = {}, peak heap = {:.1} {}",
+            self.allocations, peak, UNITS[unit]
+        )
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use super::Usage;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    static CURRENT: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Runs `func`, returning how much it allocated.
+    pub fn measure<R>(func: impl FnOnce() -> R) -> (R, Option<Usage>) {
+        let baseline = CURRENT.load(Ordering::SeqCst);
+        let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+        PEAK.store(baseline, Ordering::SeqCst);
+
+        let result = func();
+
+        let usage = Usage {
+            allocations: ALLOCATIONS.load(Ordering::SeqCst) - allocations,
+            peak: PEAK.load(Ordering::SeqCst).saturating_sub(baseline),
+        };
+
+        (result, Some(usage))
+    }
+
+    /// Forwards to the system allocator, keeping track of the current and peak heap size.
+    pub struct Counting;
+
+    impl Counting {
+        fn grow(size: usize) {
+            let current = CURRENT.fetch_add(size, Ordering::SeqCst) + size;
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+
+        fn shrink(size: usize) {
+            CURRENT.fetch_sub(size, Ordering::SeqCst);
+        }
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc(layout);
+
+            if !pointer.is_null() {
+                ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+                Counting::grow(layout.size());
+            }
+
+            pointer
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let pointer = System.alloc_zeroed(layout);
+
+            if !pointer.is_null() {
+                ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+                Counting::grow(layout.size());
+            }
+
+            pointer
+        }
+
+        unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+            System.dealloc(pointer, layout);
+            Counting::shrink(layout.size());
+        }
+
+        unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_pointer = System.realloc(pointer, layout, new_size);
+
+            if !new_pointer.is_null() {
+                ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+
+                if new_size > layout.size() {
+                    Counting::grow(new_size - layout.size());
+                } else {
+                    Counting::shrink(layout.size() - new_size);
+                }
+            }
+
+            new_pointer
+        }
+    }
+}
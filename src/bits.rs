@@ -0,0 +1,215 @@
+use anyhow::anyhow;
+
+/// Reads numbers of arbitrary bit widths, most significant bit first, from packed bytes.
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    length: usize,
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a reader over the first `length` bits of `bytes`.
+    pub fn new(bytes: &'a [u8], length: usize) -> Self {
+        BitReader {
+            bytes,
+            length: length.min(bytes.len() * 8),
+            position: 0,
+        }
+    }
+
+    /// Amount of bits read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.length - self.position
+    }
+
+    /// Returns the next `count` (at most 64) bits without consuming them.
+    pub fn peek(&self, count: usize) -> anyhow::Result<u64> {
+        if count > 64 {
+            return Err(anyhow!("Cannot read {} bits at once", count));
+        }
+
+        if count > self.remaining() {
+            return Err(anyhow!(
+                "Unexpected end of input reading {} bits at bit {}, only {} left",
+                count,
+                self.position,
+                self.remaining()
+            ));
+        }
+
+        let mut value = 0u64;
+        let mut position = self.position;
+        let mut count = count;
+
+        while count > 0 {
+            let offset = position % 8;
+            let width = (8 - offset).min(count);
+            let byte = self.bytes[position / 8] as u64;
+            let bits = byte >> (8 - offset - width) & ((1 << width) - 1);
+
+            value = value << width | bits;
+            position += width;
+            count -= width;
+        }
+
+        Ok(value)
+    }
+
+    /// Consumes the next `count` (at most 64) bits.
+    pub fn read(&mut self, count: usize) -> anyhow::Result<u64> {
+        let value = self.peek(count)?;
+        self.position += count;
+        Ok(value)
+    }
+
+    pub fn read_bit(&mut self) -> anyhow::Result<bool> {
+        Ok(self.read(1)? == 1)
+    }
+}
+
+/// Packs numbers of arbitrary bit widths, most significant bit first, into bytes.
+#[derive(Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    length: usize,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter::default()
+    }
+
+    /// Amount of bits written so far.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Writes the lowest `count` (at most 64) bits of `value`.
+    pub fn write(&mut self, value: u64, count: usize) {
+        let mut count = count;
+
+        while count > 0 {
+            let offset = self.length % 8;
+
+            if offset == 0 {
+                self.bytes.push(0);
+            }
+
+            let width = (8 - offset).min(count);
+            let bits = (value >> (count - width)) as u8 & ((1u16 << width) - 1) as u8;
+
+            *self.bytes.last_mut().unwrap() |= bits << (8 - offset - width);
+            self.length += width;
+            count -= width;
+        }
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write(bit as u64, 1);
+    }
+
+    /// Replaces `count` already written bits, starting at bit `position`, with the lowest `count`
+    /// bits of `value`.
+    pub fn overwrite(&mut self, position: usize, value: u64, count: usize) {
+        assert!(
+            position + count <= self.length,
+            "Overwriting unwritten bits"
+        );
+
+        for index in 0..count {
+            let bit = position + index;
+            let mask = 0x80 >> (bit % 8);
+
+            if value >> (count - 1 - index) & 1 == 1 {
+                self.bytes[bit / 8] |= mask;
+            } else {
+                self.bytes[bit / 8] &= !mask;
+            }
+        }
+    }
+
+    /// Returns the written bits, padded with zeros to a whole amount of bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn fields() -> impl Strategy<Value = Vec<(u64, usize)>> {
+        let field = (any::<u64>(), 0..=64usize).prop_map(|(value, width)| {
+            (
+                value & u64::MAX.checked_shr(64 - width as u32).unwrap_or(0),
+                width,
+            )
+        });
+
+        prop::collection::vec(field, 0..32)
+    }
+
+    proptest! {
+        #[test]
+        fn reads_what_was_written(fields in fields()) {
+            let mut writer = BitWriter::new();
+
+            for &(value, width) in &fields {
+                writer.write(value, width);
+            }
+
+            let length = writer.len();
+            let bytes = writer.into_bytes();
+            let mut reader = BitReader::new(&bytes, length);
+
+            prop_assert_eq!(bytes.len(), length.div_ceil(8));
+
+            for &(value, width) in &fields {
+                prop_assert_eq!(reader.peek(width).unwrap(), value);
+                prop_assert_eq!(reader.read(width).unwrap(), value);
+            }
+
+            prop_assert_eq!(reader.remaining(), 0);
+            prop_assert!(reader.read_bit().is_err());
+        }
+
+        #[test]
+        fn overwrite_replaces_a_field(fields in fields(), index in any::<prop::sample::Index>(), value in any::<u64>()) {
+            prop_assume!(!fields.is_empty());
+            let index = index.index(fields.len());
+            let width = fields[index].1;
+            let value = value & u64::MAX.checked_shr(64 - width as u32).unwrap_or(0);
+
+            let mut overwritten = BitWriter::new();
+            let mut expected = BitWriter::new();
+            let mut position = 0;
+
+            for (i, &(field, width)) in fields.iter().enumerate() {
+                if i == index {
+                    position = overwritten.len();
+                }
+
+                overwritten.write(field, width);
+                expected.write(if i == index { value } else { field }, width);
+            }
+
+            overwritten.overwrite(position, value, width);
+            prop_assert_eq!(overwritten.into_bytes(), expected.into_bytes());
+        }
+
+        #[test]
+        fn reads_msb_first(bytes in prop::collection::vec(any::<u8>(), 1..16), position in 0..8usize) {
+            let mut reader = BitReader::new(&bytes, bytes.len() * 8);
+            reader.read(position).unwrap();
+
+            let expected = (bytes[0] >> (7 - position)) & 1 == 1;
+            prop_assert_eq!(reader.read_bit().unwrap(), expected);
+            prop_assert_eq!(reader.position(), position + 1);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use anyhow::{anyhow, Context};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Axis-aligned box in `N` dimensions, with inclusive bounds on every axis.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Cuboid<const N: usize> {
+    pub min: [i64; N],
+    pub max: [i64; N],
+}
+
+impl<const N: usize> Cuboid<N> {
+    /// Creates a box, unless it would be empty along some axis.
+    pub fn new(min: [i64; N], max: [i64; N]) -> Option<Self> {
+        (0..N)
+            .all(|axis| min[axis] <= max[axis])
+            .then_some(Cuboid { min, max })
+    }
+
+    /// Box spanning `min..=max` along every axis.
+    pub fn cube(min: i64, max: i64) -> Self {
+        Cuboid {
+            min: [min; N],
+            max: [max; N],
+        }
+    }
+
+    /// Amount of points in the box.
+    pub fn volume(&self) -> i128 {
+        (0..N)
+            .map(|axis| (self.max[axis] as i128) - (self.min[axis] as i128) + 1)
+            .product()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let min = std::array::from_fn(|axis| self.min[axis].max(other.min[axis]));
+        let max = std::array::from_fn(|axis| self.max[axis].min(other.max[axis]));
+
+        Cuboid::new(min, max)
+    }
+
+    /// Parses a box like `from_str` does and clips it to `region`.
+    ///
+    /// Gives up with `None` at the first range that misses the region, without looking at the
+    /// rest of the input, so boxes far outside of it are cheap to skip.
+    pub fn parse_within(s: &str, region: &Self) -> anyhow::Result<Option<Self>> {
+        for (axis, range) in s.split(',').take(N).enumerate() {
+            let (min, max) = parse_range(range)?;
+
+            if max < region.min[axis] || min > region.max[axis] {
+                return Ok(None);
+            }
+        }
+
+        Ok(s.parse::<Self>()?.intersection(region))
+    }
+}
+
+impl<const N: usize> std::fmt::Display for Cuboid<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for axis in 0..N {
+            if axis > 0 {
+                write!(f, ",")?;
+            }
+
+            write!(f, "{}..{}", self.min[axis], self.max[axis])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses one `min..max` range per axis separated by commas, each optionally named like `x=1..2`.
+impl<const N: usize> FromStr for Cuboid<N> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ranges = s.split(',').collect::<Vec<_>>();
+
+        if ranges.len() != N {
+            return Err(anyhow!(
+                "Expecting {} ranges, found {} in {}",
+                N,
+                ranges.len(),
+                s
+            ));
+        }
+
+        let mut min = [0; N];
+        let mut max = [0; N];
+
+        for (axis, range) in ranges.into_iter().enumerate() {
+            (min[axis], max[axis]) = parse_range(range)?;
+        }
+
+        Cuboid::new(min, max).with_context(|| format!("Empty range in {}", s))
+    }
+}
+
+/// Parses a range like `1..2`, optionally named like `x=1..2`, into its bounds.
+fn parse_range(range: &str) -> anyhow::Result<(i64, i64)> {
+    let range = range
+        .split_once('=')
+        .map_or(range, |(_, range)| range)
+        .trim();
+    let (from, to) = range
+        .split_once("..")
+        .with_context(|| format!("Expecting a range like 1..2, found {}", range))?;
+
+    let min = from
+        .parse()
+        .with_context(|| format!("Invalid bound {}", from))?;
+    let max = to
+        .parse()
+        .with_context(|| format!("Invalid bound {}", to))?;
+
+    Ok((min, max))
+}
+
+/// Set of points made up of boxes.
+///
+/// The set is kept as boxes whose volumes are added or subtracted, inclusion–exclusion style, so
+/// boxes never have to be cut up. Boxes that show up more than once are merged into one.
+#[derive(Clone, Debug, Default)]
+pub struct BoxSet<const N: usize> {
+    terms: Vec<(Cuboid<N>, i64)>,
+}
+
+impl<const N: usize> BoxSet<N> {
+    pub fn new() -> Self {
+        BoxSet { terms: Vec::new() }
+    }
+
+    /// Turns on all points of the box.
+    pub fn insert(&mut self, cuboid: Cuboid<N>) {
+        self.cut(&cuboid);
+        self.terms.push((cuboid, 1));
+    }
+
+    /// Turns off all points of the box.
+    pub fn remove(&mut self, cuboid: Cuboid<N>) {
+        self.cut(&cuboid);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let overlap = self
+            .product(other)
+            .map(|(cuboid, weight)| (cuboid, -weight));
+        let terms = self.terms.iter().chain(&other.terms).copied();
+
+        Self::from_terms(terms.chain(overlap))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::from_terms(self.product(other))
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let overlap = self
+            .product(other)
+            .map(|(cuboid, weight)| (cuboid, -weight));
+        Self::from_terms(self.terms.iter().copied().chain(overlap))
+    }
+
+    /// Amount of points in the set.
+    pub fn volume(&self) -> i128 {
+        self.terms
+            .iter()
+            .map(|(cuboid, weight)| cuboid.volume() * *weight as i128)
+            .sum()
+    }
+
+    /// Amount of points of the set within `region`.
+    pub fn volume_within(&self, region: &Cuboid<N>) -> i128 {
+        self.intersection(&(*region).into()).volume()
+    }
+
+    /// Subtracts the overlap of every box with `cuboid`, which cancels out boxes that lie inside of
+    /// it rather than adding a negative copy.
+    fn cut(&mut self, cuboid: &Cuboid<N>) {
+        let mut overlaps = Vec::new();
+
+        self.terms
+            .retain(|(existing, weight)| match existing.intersection(cuboid) {
+                Some(overlap) if overlap == *existing => false,
+                Some(overlap) => {
+                    overlaps.push((overlap, -weight));
+                    true
+                }
+                None => true,
+            });
+
+        self.terms.extend(overlaps);
+    }
+
+    /// Overlap of every box of this set with every box of the other one.
+    fn product<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (Cuboid<N>, i64)> + 'a {
+        self.terms.iter().flat_map(move |(a, v)| {
+            other
+                .terms
+                .iter()
+                .filter_map(move |(b, w)| a.intersection(b).map(|cuboid| (cuboid, v * w)))
+        })
+    }
+
+    /// Merges equal boxes, dropping those that cancel out, while keeping the boxes in order.
+    fn from_terms(terms: impl Iterator<Item = (Cuboid<N>, i64)>) -> Self {
+        let mut merged = Vec::<(Cuboid<N>, i64)>::new();
+        let mut indices = HashMap::<Cuboid<N>, usize>::new();
+
+        for (cuboid, weight) in terms {
+            match indices.entry(cuboid) {
+                Entry::Occupied(entry) => merged[*entry.get()].1 += weight,
+                Entry::Vacant(entry) => {
+                    entry.insert(merged.len());
+                    merged.push((cuboid, weight));
+                }
+            }
+        }
+
+        merged.retain(|(_, weight)| *weight != 0);
+        BoxSet { terms: merged }
+    }
+}
+
+impl<const N: usize> From<Cuboid<N>> for BoxSet<N> {
+    fn from(cuboid: Cuboid<N>) -> Self {
+        BoxSet {
+            terms: vec![(cuboid, 1)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    type Points = HashSet<[i64; 2]>;
+
+    fn cuboid<const N: usize>() -> impl Strategy<Value = Cuboid<N>> {
+        let range = (-10..=10i64, 0..=10i64).prop_map(|(min, length)| (min, min + length));
+
+        prop::array::uniform(range).prop_map(|ranges| Cuboid {
+            min: ranges.map(|(min, _)| min),
+            max: ranges.map(|(_, max)| max),
+        })
+    }
+
+    fn point<const N: usize>() -> impl Strategy<Value = [i64; N]> {
+        prop::array::uniform(-10..=20i64)
+    }
+
+    /// Switches boxes on and off, both in a set and point by point.
+    fn steps() -> impl Strategy<Value = (BoxSet<2>, Points)> {
+        prop::collection::vec((any::<bool>(), cuboid::<2>()), 0..12).prop_map(|steps| {
+            let mut set = BoxSet::new();
+            let mut points = Points::new();
+
+            for (on, cuboid) in steps {
+                let inside = (cuboid.min[0]..=cuboid.max[0])
+                    .flat_map(|x| (cuboid.min[1]..=cuboid.max[1]).map(move |y| [x, y]));
+
+                if on {
+                    set.insert(cuboid);
+                    points.extend(inside);
+                } else {
+                    set.remove(cuboid);
+                    inside.for_each(|point| {
+                        points.remove(&point);
+                    });
+                }
+            }
+
+            (set, points)
+        })
+    }
+
+    fn contains<const N: usize>(cuboid: &Cuboid<N>, point: &[i64; N]) -> bool {
+        (0..N).all(|axis| (cuboid.min[axis]..=cuboid.max[axis]).contains(&point[axis]))
+    }
+
+    fn agrees(set: &BoxSet<2>, points: &Points) -> bool {
+        let mut grid = (-10..=20).flat_map(|x| (-10..=20).map(move |y| [x, y]));
+        let contains = |point| set.volume_within(&Cuboid::new(point, point).unwrap()) == 1;
+
+        set.volume() == points.len() as i128
+            && grid.all(|point| contains(point) == points.contains(&point))
+    }
+
+    #[test]
+    fn parse_accepts_named_ranges() {
+        let cuboid = "x=-5..47,y=-31..22,z=-19..33".parse::<Cuboid<3>>().unwrap();
+        assert_eq!(cuboid, Cuboid::new([-5, -31, -19], [47, 22, 33]).unwrap());
+        assert_eq!(cuboid.to_string().parse::<Cuboid<3>>().unwrap(), cuboid);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_ranges() {
+        assert!("1..2,3..4".parse::<Cuboid<3>>().is_err());
+        assert!("x=1..2,y=3,z=4..5".parse::<Cuboid<3>>().is_err());
+        assert!("x=2..1".parse::<Cuboid<1>>().is_err());
+    }
+
+    #[test]
+    fn parse_within_clips_and_skips() {
+        let region = Cuboid::cube(-50, 50);
+        let clipped = Cuboid::parse_within("x=-60..10,y=0..0,z=40..70", &region).unwrap();
+
+        assert_eq!(clipped, Cuboid::new([-50, 0, 40], [10, 0, 50]));
+        assert_eq!(
+            Cuboid::parse_within("x=60..70,y=oops", &region).unwrap(),
+            None
+        );
+        assert!(Cuboid::parse_within("x=0..1,y=oops", &region).is_err());
+        assert!(Cuboid::parse_within("x=0..1,y=0..1", &region).is_err());
+    }
+
+    #[test]
+    fn volume_fits_large_boxes() {
+        let cuboid = Cuboid::<3>::cube(-1_000_000_000, 1_000_000_000);
+        assert_eq!(cuboid.volume(), 2_000_000_001i128.pow(3));
+    }
+
+    proptest! {
+        #[test]
+        fn intersection_is_commutative(a in cuboid::<3>(), b in cuboid::<3>()) {
+            prop_assert_eq!(a.intersection(&b), b.intersection(&a));
+        }
+
+        #[test]
+        fn intersection_contains_shared_points(
+            a in cuboid::<3>(),
+            b in cuboid::<3>(),
+            p in point::<3>(),
+        ) {
+            let shared = contains(&a, &p) && contains(&b, &p);
+            let intersection = a.intersection(&b);
+            prop_assert_eq!(intersection.is_some_and(|cuboid| contains(&cuboid, &p)), shared);
+        }
+
+        #[test]
+        fn intersection_is_no_larger(a in cuboid::<3>(), b in cuboid::<3>()) {
+            if let Some(intersection) = a.intersection(&b) {
+                prop_assert!(intersection.volume() <= a.volume().min(b.volume()));
+            }
+        }
+
+        #[test]
+        fn insert_and_remove_match_points((set, points) in steps()) {
+            prop_assert!(agrees(&set, &points));
+        }
+
+        #[test]
+        fn set_operations_match_points((a, p) in steps(), (b, q) in steps()) {
+            prop_assert!(agrees(&a.union(&b), &(&p | &q)));
+            prop_assert!(agrees(&a.intersection(&b), &(&p & &q)));
+            prop_assert!(agrees(&a.difference(&b), &(&p - &q)));
+        }
+
+        #[test]
+        fn volume_within_counts_points_in_region((set, points) in steps(), region in cuboid::<2>()) {
+            let inside = points.iter().filter(|point| contains(&region, point)).count();
+            prop_assert_eq!(set.volume_within(&region), inside as i128);
+        }
+    }
+}
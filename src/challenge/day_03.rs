@@ -1,8 +1,7 @@
 use std::num::ParseIntError;
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let bit_count = input[0].as_bytes().len();
-    let majority_count = input.len() / 2;
+    let bit_count = input[0].len();
 
     let max = 2u32.pow(bit_count as u32) - 1;
     let mut counters = vec![0; bit_count];
@@ -18,7 +17,7 @@ pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
     }
 
     let gamma = counters.iter().fold(0u32, |acc, count| {
-        if *count < majority_count {
+        if *count * 2 < input.len() {
             acc << 1
         } else {
             acc << 1 | 1
@@ -69,6 +68,11 @@ fn find_rating(input: &[&str], index: usize, rating: Rating) -> Result<usize, Pa
             .filter(|line| line.as_bytes()[index] == b'1')
             .count();
 
+        if count == 0 || count == length {
+            // every number shares the bit, there's nothing to filter out
+            return find_rating(input, index + 1, rating);
+        }
+
         let bit = if count >= length - count {
             rating.majority()
         } else {
@@ -84,3 +88,22 @@ fn find_rating(input: &[&str], index: usize, rating: Rating) -> Result<usize, Pa
         find_rating(&filtered, index + 1, rating)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamma_rate_takes_strict_majority() {
+        // two out of five is a minority, even though it's as much as half of five rounded down
+        let input = ["11", "10", "10", "01", "00"];
+        assert_eq!(part_a(&input).unwrap().to_string(), "2");
+    }
+
+    #[test]
+    fn ratings_skip_bits_every_number_shares() {
+        // no number starts with a one, which mustn't filter every number out of the CO2 rating
+        let input = ["011", "010", "001"];
+        assert_eq!(part_b(&input).unwrap().to_string(), "3");
+    }
+}
@@ -16,7 +16,7 @@ pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
         }
     }
 
-    return Err(anyhow!("Could not find winning board"));
+    Err(anyhow!("Could not find winning board"))
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
@@ -37,7 +37,7 @@ pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
         }
     }
 
-    return Err(anyhow!("Could not find last winning board"));
+    Err(anyhow!("Could not find last winning board"))
 }
 
 fn parse_boards(input: &[&str]) -> anyhow::Result<Vec<Board>> {
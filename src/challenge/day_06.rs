@@ -1,47 +1,266 @@
-const COUNTERS_LEN: usize = 9;
-const DAYS_TO_REPRODUCE: usize = 7;
+use crate::matrix::{Arithmetic, Exact, Matrix, Modulo};
+use anyhow::{anyhow, Context};
+use num_bigint::BigUint;
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    simulate(input[0], 80)
+    simulate(input[0], &Model::default(), 80)
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    simulate(input[0], 256)
+    simulate(input[0], &Model::default(), 256)
 }
 
-fn simulate(input: &str, days: usize) -> anyhow::Result<u64> {
-    let mut counters = Counters::try_from(input)?;
+/// Counts the fish after `tool 6 count <days> <modulus> [cycle] [delay]` days, modulo `modulus`,
+/// or exactly when the modulus is `exact`.
+///
+/// Raises the daily transition to the power of `days` by squaring, so `10^12` days take about 40
+/// matrix products. Exact counts grow by about 4% a day though, so they only stay practical up to
+/// millions of days.
+pub fn count(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let days = args
+        .first()
+        .context("Missing days, expecting `tool 6 count <days> <modulus>`")?;
+    let days = days
+        .parse::<u64>()
+        .with_context(|| format!("Invalid amount of days {}", days))?;
+    let modulus = args
+        .get(1)
+        .context("Missing modulus, expecting a number or `exact`")?;
+    let model = Model::parse(&args[2..])?;
+
+    match modulus.as_str() {
+        "exact" => println!("{}", project(input[0], &model, days, &Exact)?),
+        modulus => {
+            let modulus = modulus
+                .parse()
+                .with_context(|| format!("Invalid modulus {}", modulus))?;
+
+            println!(
+                "{}",
+                project(input[0], &model, days, &Modulo::new(modulus)?)?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the population at the start of each day, up to `tool 6 series <days> [cycle] [delay]`.
+pub fn series(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let days = args
+        .first()
+        .context("Missing days, expecting `tool 6 series <days>`")?;
+    let days = days
+        .parse::<usize>()
+        .with_context(|| format!("Invalid amount of days {}", days))?;
+    let model = Model::parse(&args[1..])?;
+    let mut counters = Counters::parse(input[0], &model, &Exact)?;
+
+    for day in 0..=days {
+        if day > 0 {
+            counters.advance(&Exact);
+        }
+
+        println!("{} {}", day, counters.count(&Exact));
+    }
+
+    Ok(())
+}
+
+fn simulate(input: &str, model: &Model, days: usize) -> anyhow::Result<BigUint> {
+    let mut counters = Counters::parse(input, model, &Exact)?;
 
     for _ in 0..days {
-        counters.advance();
+        counters.advance(&Exact);
     }
 
-    Ok(counters.count())
+    Ok(counters.count(&Exact))
 }
 
-struct Counters(usize, [u64; COUNTERS_LEN]);
+/// Counts the fish after `days` through powers of the daily transition, instead of day by day.
+fn project<A: Arithmetic>(
+    input: &str,
+    model: &Model,
+    days: u64,
+    arithmetic: &A,
+) -> anyhow::Result<A::Value> {
+    let counters = Counters::parse(input, model, arithmetic)?;
+    let counts = transition(model, arithmetic)
+        .pow(days, arithmetic)
+        .apply(&counters.in_order(), arithmetic);
 
-impl Counters {
-    fn advance(&mut self) {
-        self.1[(self.0 + DAYS_TO_REPRODUCE) % COUNTERS_LEN] += self.1[self.0];
-        self.0 = (self.0 + 1) % COUNTERS_LEN;
+    Ok(arithmetic.sum(&counts))
+}
+
+/// Life cycle of the fish: every `cycle` days a fish spawns a newborn, which takes `delay` days
+/// longer before spawning for the first time.
+struct Model {
+    cycle: usize,
+    delay: usize,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model { cycle: 7, delay: 2 }
     }
+}
+
+impl Model {
+    /// Reads `[cycle] [delay]`, falling back to the defaults for missing values.
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut model = Model::default();
 
-    fn count(&self) -> u64 {
-        self.1.iter().sum()
+        if let Some(cycle) = args.first() {
+            model.cycle = cycle
+                .parse()
+                .with_context(|| format!("Invalid spawn cycle {}", cycle))?;
+        }
+
+        if let Some(delay) = args.get(1) {
+            model.delay = delay
+                .parse()
+                .with_context(|| format!("Invalid newborn delay {}", delay))?;
+        }
+
+        if model.cycle == 0 {
+            return Err(anyhow!("The spawn cycle must last at least a day"));
+        }
+
+        Ok(model)
     }
+
+    /// Amount of timer values, from 0 up to the timer of a newborn.
+    fn timers(&self) -> usize {
+        self.cycle + self.delay
+    }
+}
+
+/// Amount of fish for every timer value, kept in a ring that turns by one every day so that only
+/// the spawning fish have to be moved.
+struct Counters<T> {
+    start: usize,
+    cycle: usize,
+    counts: Vec<T>,
 }
 
-impl TryFrom<&str> for Counters {
-    type Error = anyhow::Error;
+impl<T: Clone> Counters<T> {
+    fn parse<A: Arithmetic<Value = T>>(
+        input: &str,
+        model: &Model,
+        arithmetic: &A,
+    ) -> anyhow::Result<Self> {
+        let mut counts = vec![arithmetic.zero(); model.timers()];
 
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut counters = [0u64; COUNTERS_LEN];
+        for number in input.split(',') {
+            let timer = number
+                .trim()
+                .parse::<usize>()
+                .with_context(|| format!("Invalid timer {}", number))?;
+            let count = counts.get_mut(timer).with_context(|| {
+                format!(
+                    "Timer {} is above the newborn timer {}",
+                    timer,
+                    model.timers() - 1
+                )
+            })?;
 
-        for number in value.split(',') {
-            counters[number.parse::<usize>()?] += 1;
+            *count = arithmetic.add(count, &arithmetic.one());
         }
 
-        Ok(Counters(0, counters))
+        Ok(Counters {
+            start: 0,
+            cycle: model.cycle,
+            counts,
+        })
+    }
+
+    fn advance<A: Arithmetic<Value = T>>(&mut self, arithmetic: &A) {
+        // the spawning fish stay where they are as the newborns, once the ring has turned
+        let len = self.counts.len();
+        let reset = (self.start + self.cycle) % len;
+
+        self.counts[reset] = arithmetic.add(&self.counts[reset], &self.counts[self.start]);
+        self.start = (self.start + 1) % len;
+    }
+
+    fn count<A: Arithmetic<Value = T>>(&self, arithmetic: &A) -> T {
+        arithmetic.sum(&self.counts)
+    }
+
+    /// Counts by timer value, starting with the fish about to spawn.
+    fn in_order(&self) -> Vec<T> {
+        let (before, after) = self.counts.split_at(self.start);
+        after.iter().chain(before).cloned().collect()
+    }
+}
+
+/// Moves every fish one timer down, and the spawning ones back up to the end of the cycle along with
+/// their newborns.
+fn transition<A: Arithmetic>(model: &Model, arithmetic: &A) -> Matrix<A::Value> {
+    let size = model.timers();
+    let moves = (1..size).map(|timer| (timer - 1, timer));
+    let spawns = [(model.cycle - 1, 0), (size - 1, 0)];
+
+    Matrix::from_edges(size, moves.chain(spawns), arithmetic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const EXAMPLE: &str = "3,4,3,1,2";
+
+    #[test]
+    fn simulate_matches_example() {
+        let model = Model::default();
+        assert_eq!(simulate(EXAMPLE, &model, 18).unwrap(), BigUint::from(26u8));
+        assert_eq!(
+            simulate(EXAMPLE, &model, 80).unwrap(),
+            BigUint::from(5934u16)
+        );
+        assert_eq!(
+            simulate(EXAMPLE, &model, 256).unwrap(),
+            BigUint::from(26984457539u64)
+        );
+    }
+
+    #[test]
+    fn simulate_counts_past_u64() {
+        let count = simulate(EXAMPLE, &Model::default(), 1000).unwrap();
+        assert!(count > BigUint::from(u64::MAX));
+        assert_eq!(
+            project(EXAMPLE, &Model::default(), 1000, &Exact).unwrap(),
+            count
+        );
+    }
+
+    #[test]
+    fn parse_rejects_timers_past_newborns() {
+        assert!(Counters::parse("3,9", &Model::default(), &Exact).is_err());
+        assert!(Counters::parse("3,9", &Model { cycle: 7, delay: 3 }, &Exact).is_ok());
+    }
+
+    proptest! {
+        #[test]
+        fn project_matches_simulate(
+            timers in prop::collection::vec(0..5usize, 1..10),
+            cycle in 1..9usize,
+            delay in 0..4usize,
+            days in 0..300usize,
+            modulus in 1..1_000_000u64,
+        ) {
+            let model = Model { cycle, delay };
+            let input = timers
+                .iter()
+                .map(|timer| (timer % model.timers()).to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            let exact = simulate(&input, &model, days).unwrap();
+            let projected = project(&input, &model, days as u64, &Modulo::new(modulus).unwrap()).unwrap();
+
+            prop_assert_eq!(exact.clone() % modulus, BigUint::from(projected));
+            prop_assert_eq!(project(&input, &model, days as u64, &Exact).unwrap(), exact);
+        }
     }
 }
@@ -33,9 +33,5 @@ fn fuel_cost(x: usize, y: usize) -> usize {
 }
 
 fn abs_diff(x: usize, y: usize) -> usize {
-    if x < y {
-        y - x
-    } else {
-        x - y
-    }
+    x.abs_diff(y)
 }
@@ -116,7 +116,7 @@ impl FromStr for Problem {
     }
 }
 
-#[derive(Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
 struct Segments(u8);
 
 impl Segments {
@@ -156,3 +156,68 @@ impl Not for Segments {
         Segments(!self.0 & SEGMENT_MASK.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const DIGITS: [&str; 10] = [
+        "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+    ];
+
+    fn segments() -> impl Strategy<Value = Segments> {
+        (0..=SEGMENT_MASK.0).prop_map(Segments)
+    }
+
+    fn rewire(digit: usize, wiring: &[u8]) -> String {
+        DIGITS[digit]
+            .bytes()
+            .map(|segment| char::from(wiring[(segment - b'a') as usize]))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn de_morgan(a in segments(), b in segments()) {
+            prop_assert_eq!(!(a | b), !a & !b);
+            prop_assert_eq!(!(a & b), !a | !b);
+        }
+
+        #[test]
+        fn not_stays_within_mask(a in segments()) {
+            prop_assert_eq!(!!a, a);
+            prop_assert_eq!((!a).count(), 7 - a.count());
+            prop_assert_eq!(a | !a, SEGMENT_MASK);
+        }
+
+        #[test]
+        fn counts_add_up(a in segments(), b in segments()) {
+            prop_assert_eq!((a | b).count() + (a & b).count(), a.count() + b.count());
+        }
+
+        #[test]
+        fn parse_ignores_order(segments in proptest::sample::subsequence(b"abcdefg".to_vec(), 0..=7)) {
+            let forward = String::from_utf8(segments.clone()).unwrap();
+            let backward = forward.chars().rev().collect::<String>();
+            let parsed = Segments::parse(&forward);
+
+            prop_assert_eq!(parsed, Segments::parse(&backward));
+            prop_assert_eq!(parsed.count() as usize, segments.len());
+        }
+
+        #[test]
+        fn solves_any_wiring(
+            wiring in Just(b"abcdefg".to_vec()).prop_shuffle(),
+            patterns in Just((0..10).collect::<Vec<_>>()).prop_shuffle(),
+            digits in proptest::array::uniform4(0..10usize),
+        ) {
+            let patterns = patterns.iter().map(|&digit| rewire(digit, &wiring)).collect::<Vec<_>>();
+            let outputs = digits.iter().map(|&digit| rewire(digit, &wiring)).collect::<Vec<_>>();
+            let line = format!("{} | {}", patterns.join(" "), outputs.join(" "));
+            let expected = digits.iter().fold(0, |sum, digit| sum * 10 + digit);
+
+            prop_assert_eq!(line.parse::<Problem>().unwrap().solve(), expected);
+        }
+    }
+}
@@ -77,14 +77,14 @@ impl Map {
     }
 
     fn get(&self, x: usize, y: usize) -> u8 {
-        self.depths[(x + y * self.width)]
+        self.depths[x + y * self.width]
     }
 
     fn up(&self, x: usize, y: usize) -> u8 {
         if y == 0 {
             MAX_DEPTH
         } else {
-            self.depths[(x + (y - 1) * self.width)]
+            self.depths[x + (y - 1) * self.width]
         }
     }
 
@@ -94,7 +94,7 @@ impl Map {
         if y >= self.height {
             MAX_DEPTH
         } else {
-            self.depths[(x + y * self.width)]
+            self.depths[x + y * self.width]
         }
     }
 
@@ -102,7 +102,7 @@ impl Map {
         if x == 0 {
             MAX_DEPTH
         } else {
-            self.depths[((x - 1) + y * self.width)]
+            self.depths[(x - 1) + y * self.width]
         }
     }
 
@@ -112,7 +112,7 @@ impl Map {
         if x >= self.width {
             MAX_DEPTH
         } else {
-            self.depths[(x + y * self.width)]
+            self.depths[x + y * self.width]
         }
     }
 }
@@ -147,11 +147,10 @@ fn basin_sizes(map: &Map) -> impl Iterator<Item = usize> {
                 basin_sizes[top] += basin_sizes[left] + 1;
                 basin_sizes[left] = 0;
 
-                for prev in lookbehind[0..x].iter_mut().rev() {
+                // walls can split the left basin up, so its cells needn't be next to each other
+                for prev in lookbehind.iter_mut() {
                     if *prev == left {
                         *prev = top;
-                    } else {
-                        break;
                     }
                 }
             }
@@ -160,3 +159,16 @@ fn basin_sizes(map: &Map) -> impl Iterator<Item = usize> {
 
     basin_sizes.into_iter().filter(|size| *size > 0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basins_merge_across_the_row() {
+        // the big basin is first seen as three, and the last merge on the second row has to reach
+        // past the wall in the middle of it
+        let input = ["911191", "119111", "919911", "191191"];
+        assert_eq!(part_b(&input).unwrap().to_string(), "26");
+    }
+}
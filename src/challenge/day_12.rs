@@ -1,4 +1,4 @@
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use std::collections::HashMap;
 
 const LOWER_CASE_MASK: u8 = 0x20;
@@ -7,22 +7,53 @@ const START_NODE_ID: usize = 0;
 const END_NODE_ID: usize = 1;
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(Graph::parse(input)?.count_paths(false))
+    let graph = Graph::parse(input)?;
+    Ok(graph.count_paths(&Policy::new(&graph, 0)))
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(Graph::parse(input)?.count_paths(true))
+    let graph = Graph::parse(input)?;
+    Ok(graph.count_paths(&Policy::new(&graph, 1)))
+}
+
+/// Prints every path through the caves, one per line, followed by how many there are.
+///
+/// Takes the rules of `Policy::parse`, like `tool 12 paths 1 ab=2` to allow one extra visit to a
+/// small cave on top of visiting `ab` twice.
+pub fn paths(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let graph = Graph::parse(input)?;
+    let policy = Policy::parse(&graph, args)?;
+    let mut count = 0;
+
+    for path in graph.paths(&policy) {
+        println!("{}", path.join(","));
+        count += 1;
+    }
+
+    println!("{} paths", count);
+    Ok(())
+}
+
+/// Prints the graph the paths are counted on in Graphviz's DOT language, for `dot -Tsvg`.
+///
+/// Big caves are left out of it, since every link to one is replaced by links between the caves
+/// around it, labelled with how many ways lead from one cave to the other.
+pub fn dot(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    print!("{}", Graph::parse(input)?.to_dot());
+    Ok(())
 }
 
 type NodeId = usize;
 
 struct Node {
+    name: String,
+    small: bool,
     links: Vec<Link>,
 }
 
 impl Node {
-    fn new(links: Vec<Link>) -> Self {
-        Node { links }
+    fn new(name: String, small: bool, links: Vec<Link>) -> Self {
+        Node { name, small, links }
     }
 
     fn links(&self) -> &[Link] {
@@ -30,14 +61,17 @@ impl Node {
     }
 }
 
+/// Ways to go from a small cave to another, either directly or through one of the big caves next
+/// to both of them.
 struct Link {
     id: NodeId,
-    paths: usize,
+    /// Big cave of every way, or `None` for the tunnel between the caves themselves.
+    routes: Vec<Option<NodeId>>,
 }
 
 impl Link {
-    fn new(id: NodeId, paths: usize) -> Self {
-        Link { id, paths }
+    fn new(id: NodeId, routes: Vec<Option<NodeId>>) -> Self {
+        Link { id, routes }
     }
 
     fn id(&self) -> NodeId {
@@ -45,7 +79,64 @@ impl Link {
     }
 
     fn path_count(&self) -> usize {
-        self.paths
+        self.routes.len()
+    }
+}
+
+/// How often the caves may be visited along a path.
+struct Policy {
+    /// Visits allowed to every cave before it takes a revisit, which is once for small caves and
+    /// without limit for big ones.
+    limits: Vec<usize>,
+    /// Extra visits to small caves a path may make in total, past their limits.
+    revisits: usize,
+}
+
+impl Policy {
+    fn new(graph: &Graph, revisits: usize) -> Self {
+        let limits = graph
+            .nodes
+            .iter()
+            .map(|node| if node.small { 1 } else { usize::MAX })
+            .collect();
+
+        Policy { limits, revisits }
+    }
+
+    /// Reads `[revisits] [cave=limit]...`, so that `1` allows one small cave to be visited twice
+    /// like part B does, and `ab=0` closes off `ab`. Only small caves take limits, since big ones
+    /// are collapsed into the links between the small caves around them.
+    fn parse(graph: &Graph, args: &[String]) -> anyhow::Result<Self> {
+        let mut policy = Policy::new(graph, 0);
+
+        for (index, arg) in args.iter().enumerate() {
+            match arg.split_once('=') {
+                Some((name, limit)) => {
+                    let id = graph
+                        .id(name)
+                        .with_context(|| format!("Unknown cave {}", name))?;
+
+                    if !graph.nodes[id].small {
+                        return Err(anyhow!(
+                            "Big cave {} can't be limited, only small caves can",
+                            name
+                        ));
+                    }
+
+                    policy.limits[id] = limit
+                        .parse()
+                        .with_context(|| format!("Invalid limit {} for {}", limit, name))?;
+                }
+                None if index == 0 => {
+                    policy.revisits = arg
+                        .parse()
+                        .with_context(|| format!("Invalid amount of revisits {}", arg))?;
+                }
+                None => return Err(anyhow!("Expecting cave=limit, found {}", arg)),
+            }
+        }
+
+        Ok(policy)
     }
 }
 
@@ -58,58 +149,227 @@ impl Graph {
         Graph { nodes }
     }
 
+    #[tracing::instrument(name = "build graph", skip_all)]
     fn parse(input: &[&str]) -> anyhow::Result<Graph> {
         Ok(GraphBuilder::new().parse(input)?.build())
     }
 
-    fn count_paths(&self, mut twice: bool) -> usize {
-        let mut visited = vec![false; self.nodes.len()];
-        self.count_paths_from(START_NODE_ID, &mut visited, &mut twice)
+    fn id(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.name == name)
     }
 
-    fn count_paths_from(&self, id: NodeId, visited: &mut [bool], twice: &mut bool) -> usize {
+    #[tracing::instrument(name = "count paths", skip_all)]
+    fn count_paths(&self, policy: &Policy) -> usize {
+        let mut visits = vec![0; self.nodes.len()];
+        visits[START_NODE_ID] = 1;
+
+        self.count_paths_from(START_NODE_ID, policy, &mut visits, policy.revisits)
+    }
+
+    fn count_paths_from(
+        &self,
+        id: NodeId,
+        policy: &Policy,
+        visits: &mut [usize],
+        revisits: usize,
+    ) -> usize {
         if id == END_NODE_ID {
             return 1;
         }
 
-        let second_time = visited[id];
+        let mut count = 0;
 
-        if second_time {
-            if !*twice {
-                return 0;
-            } else {
-                *twice = false;
+        for link in self.nodes[id].links() {
+            let id = link.id();
+            let revisit = visits[id] >= policy.limits[id];
+
+            if revisit && revisits == 0 {
+                continue;
             }
+
+            visits[id] += 1;
+            let paths = self.count_paths_from(id, policy, visits, revisits - revisit as usize);
+            visits[id] -= 1;
+
+            count += link.path_count() * paths;
         }
 
-        let node = &self.nodes[id];
-        visited[id] = true;
+        count
+    }
+
+    /// Lists the paths one at a time, with the big caves they go through, in the same order as
+    /// `count_paths` counts them.
+    fn paths<'p>(&self, policy: &'p Policy) -> Paths<'_, 'p> {
+        let mut visits = vec![0; self.nodes.len()];
+        visits[START_NODE_ID] = 1;
+
+        Paths {
+            graph: self,
+            policy,
+            visits,
+            revisits: policy.revisits,
+            stack: vec![Step::new(START_NODE_ID, None, false)],
+        }
+    }
 
-        let count = node
-            .links()
+    fn to_dot(&self) -> String {
+        let mut output = String::from("digraph caves {\n");
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            if node.small {
+                let shape = match id {
+                    START_NODE_ID | END_NODE_ID => "doublecircle",
+                    _ => "circle",
+                };
+
+                output += &format!("    {} [shape={}];\n", quote(&node.name), shape);
+            }
+        }
+
+        // paths stop at the end, so the links out of it never get used
+        for (_, node) in self
+            .nodes
             .iter()
-            .map(|link| link.path_count() * self.count_paths_from(link.id(), visited, twice))
-            .sum();
+            .enumerate()
+            .filter(|(id, _)| *id != END_NODE_ID)
+        {
+            for link in node.links() {
+                let routes = link
+                    .routes
+                    .iter()
+                    .map(|route| match route {
+                        Some(big) => self.nodes[*big].name.as_str(),
+                        None => "direct",
+                    })
+                    .collect::<Vec<_>>();
+
+                output += &format!(
+                    "    {} -> {} [label=\"{}\", tooltip={}];\n",
+                    quote(&node.name),
+                    quote(&self.nodes[link.id()].name),
+                    link.path_count(),
+                    quote(&routes.join(", "))
+                );
+            }
+        }
+
+        output + "}\n"
+    }
+}
 
-        if second_time {
-            *twice = true;
-        } else {
-            visited[id] = false;
+/// Writes the text as a DOT string, escaping quotes and the backslashes that would escape them.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Cave along the path being explored, along with the way it was reached and the next one to try
+/// from it.
+struct Step {
+    id: NodeId,
+    /// Big cave the path went through to get here, if any.
+    via: Option<NodeId>,
+    /// Whether getting here took one of the revisits.
+    revisit: bool,
+    link: usize,
+    route: usize,
+}
+
+impl Step {
+    fn new(id: NodeId, via: Option<NodeId>, revisit: bool) -> Self {
+        Step {
+            id,
+            via,
+            revisit,
+            link: 0,
+            route: 0,
         }
+    }
+}
 
-        count
+/// Depth-first walk over the paths, keeping only the path being explored.
+struct Paths<'a, 'p> {
+    graph: &'a Graph,
+    policy: &'p Policy,
+    visits: Vec<usize>,
+    revisits: usize,
+    stack: Vec<Step>,
+}
+
+impl<'a> Paths<'a, '_> {
+    fn backtrack(&mut self) {
+        if let Some(step) = self.stack.pop() {
+            self.visits[step.id] -= 1;
+            self.revisits += step.revisit as usize;
+        }
+    }
+
+    fn path(&self) -> Vec<&'a str> {
+        let name = |id: NodeId| self.graph.nodes[id].name.as_str();
+
+        self.stack
+            .iter()
+            .flat_map(|step| step.via.map(name).into_iter().chain([name(step.id)]))
+            .collect()
     }
 }
 
-struct NodeBuilder {
+impl<'a> Iterator for Paths<'a, '_> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let graph = self.graph;
+
+        loop {
+            let step = self.stack.last_mut()?;
+
+            if step.id == END_NODE_ID {
+                let path = self.path();
+                self.backtrack();
+                return Some(path);
+            }
+
+            let links = graph.nodes[step.id].links();
+
+            let link = match links.get(step.link) {
+                Some(link) => link,
+                None => {
+                    self.backtrack();
+                    continue;
+                }
+            };
+
+            let via = link.routes[step.route];
+            step.route += 1;
+
+            if step.route == link.path_count() {
+                step.link += 1;
+                step.route = 0;
+            }
+
+            let id = link.id();
+            let revisit = self.visits[id] >= self.policy.limits[id];
+
+            if revisit && self.revisits == 0 {
+                continue;
+            }
+
+            self.visits[id] += 1;
+            self.revisits -= revisit as usize;
+            self.stack.push(Step::new(id, via, revisit));
+        }
+    }
+}
+
+struct NodeBuilder<'a> {
+    name: &'a str,
     small: bool,
     links: Vec<NodeId>,
 }
 
-impl NodeBuilder {
-    fn new(small: bool) -> Self {
+impl<'a> NodeBuilder<'a> {
+    fn new(name: &'a str, small: bool) -> Self {
         let links = Vec::new();
-        NodeBuilder { small, links }
+        NodeBuilder { name, small, links }
     }
 
     fn is_small(&self) -> bool {
@@ -129,7 +389,7 @@ impl NodeBuilder {
 
 struct GraphBuilder<'a> {
     ids: HashMap<&'a str, NodeId>,
-    nodes: Vec<NodeBuilder>,
+    nodes: Vec<NodeBuilder<'a>>,
 }
 
 impl<'a> GraphBuilder<'a> {
@@ -146,14 +406,28 @@ impl<'a> GraphBuilder<'a> {
     }
 
     fn parse(mut self, input: &[&'a str]) -> anyhow::Result<Self> {
-        for line in input {
+        for (index, line) in input.iter().enumerate() {
             let (left, right) = line
                 .split_once('-')
                 .context("Unexpected end of line, expecting `-`")?;
 
+            if left.is_empty() || right.is_empty() {
+                return Err(anyhow!("Missing cave name on line {}", index + 1));
+            }
+
             let left = self.create_node(left);
             let right = self.create_node(right);
 
+            // the path could go back and forth between them forever
+            if !self.nodes[left].is_small() && !self.nodes[right].is_small() {
+                return Err(anyhow!(
+                    "Big caves {} and {} are connected on line {}, so there are infinitely many paths",
+                    self.nodes[left].name,
+                    self.nodes[right].name,
+                    index + 1
+                ));
+            }
+
             self.nodes[left].connect(right);
             self.nodes[right].connect(left);
         }
@@ -175,35 +449,187 @@ impl<'a> GraphBuilder<'a> {
         *self.ids.entry(name).or_insert_with(|| {
             let id = self.nodes.len();
             let small = name.as_bytes()[0] & LOWER_CASE_MASK != 0;
-            self.nodes.push(NodeBuilder::new(small));
+            self.nodes.push(NodeBuilder::new(name, small));
             id
         })
     }
 
+    /// Links a small cave to the small caves next to it, and to those next to the big caves next
+    /// to it, which can't lead anywhere else since big caves are never next to each other.
     fn build_node(&self, node: &NodeBuilder) -> Node {
+        let name = node.name.to_string();
+
         if !node.is_small() {
-            return Node::new(Vec::new());
+            return Node::new(name, false, Vec::new());
         }
 
-        let mut links = HashMap::<NodeId, usize>::new();
+        let mut ids = Vec::new();
+        let mut links = HashMap::<NodeId, Vec<Option<NodeId>>>::new();
+
+        let mut add = |id: NodeId, via: Option<NodeId>| {
+            links
+                .entry(id)
+                .or_insert_with(|| {
+                    ids.push(id);
+                    Vec::new()
+                })
+                .push(via)
+        };
 
         for &id in node.links() {
             let link = &self.nodes[id];
 
             if link.is_small() {
-                *links.entry(id).or_default() += 1;
+                add(id, None);
             } else {
-                for &id in link.links() {
-                    *links.entry(id).or_default() += 1;
+                for &next in link.links() {
+                    add(next, Some(id));
                 }
             }
         }
 
-        let links = links
+        let links = ids
             .into_iter()
-            .map(|(id, count)| Link::new(id, count))
+            .map(|id| Link::new(id, links.remove(&id).unwrap()))
             .collect();
 
-        Node::new(links)
+        Node::new(name, true, links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const EXAMPLE: &[&str] = &["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"];
+
+    #[test]
+    fn count_paths_matches_example() {
+        let graph = Graph::parse(EXAMPLE).unwrap();
+        assert_eq!(graph.count_paths(&Policy::new(&graph, 0)), 10);
+        assert_eq!(graph.count_paths(&Policy::new(&graph, 1)), 36);
+    }
+
+    #[test]
+    fn paths_go_through_big_caves() {
+        let graph = Graph::parse(EXAMPLE).unwrap();
+        let paths = graph.paths(&Policy::new(&graph, 0)).collect::<Vec<_>>();
+
+        assert_eq!(paths.len(), 10);
+        assert!(paths.contains(&vec!["start", "A", "c", "A", "b", "A", "end"]));
+        assert!(paths.contains(&vec!["start", "b", "end"]));
+    }
+
+    #[test]
+    fn policy_limits_caves() {
+        let graph = Graph::parse(EXAMPLE).unwrap();
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        let closed = Policy::parse(&graph, &args(&["0", "c=0", "d=0"])).unwrap();
+        assert_eq!(graph.count_paths(&closed), 5);
+
+        let twice = Policy::parse(&graph, &args(&["0", "c=2"])).unwrap();
+        assert_eq!(graph.count_paths(&twice), 16);
+
+        assert!(Policy::parse(&graph, &args(&["x=1"])).is_err());
+        assert!(Policy::parse(&graph, &args(&["0", "A=1"])).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_connected_big_caves() {
+        let error = Graph::parse(&["start-A", "A-B", "B-end"]).err().unwrap();
+        assert!(error.to_string().contains("A and B"));
+    }
+
+    #[test]
+    fn dot_labels_collapsed_links() {
+        let dot = Graph::parse(EXAMPLE).unwrap().to_dot();
+        assert!(dot.starts_with("digraph caves {\n"));
+        assert!(dot.contains("\"b\" -> \"end\" [label=\"2\", tooltip=\"A, direct\"];"));
+        assert!(!dot.contains("\"A\" [shape"));
+        assert!(!dot.contains("\"end\" ->"));
+    }
+
+    #[test]
+    fn dot_escapes_quotes() {
+        let dot = Graph::parse(&["start-a\"b", "a\"b-end"]).unwrap().to_dot();
+        assert!(dot.contains("    \"a\\\"b\" [shape=circle];"));
+        assert!(dot.contains("\"start\" -> \"a\\\"b\""));
+    }
+
+    /// Walks the tunnels as written, big caves and all, without any of the collapsing `Graph` does.
+    fn walk<'a>(
+        tunnels: &HashMap<&'a str, Vec<&'a str>>,
+        limits: &HashMap<&str, usize>,
+        path: &mut Vec<&'a str>,
+        revisits: usize,
+        paths: &mut Vec<Vec<&'a str>>,
+    ) {
+        let cave = path[path.len() - 1];
+
+        if cave == "end" {
+            paths.push(path.clone());
+            return;
+        }
+
+        for &next in tunnels.get(cave).into_iter().flatten() {
+            let small = next.as_bytes()[0].is_ascii_lowercase();
+            let visits = path.iter().filter(|cave| **cave == next).count();
+            let revisit = small && visits >= limits.get(next).copied().unwrap_or(1);
+
+            if next == "start" || (revisit && revisits == 0) {
+                continue;
+            }
+
+            path.push(next);
+            walk(tunnels, limits, path, revisits - revisit as usize, paths);
+            path.pop();
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn paths_match_walking_the_tunnels(
+            edges in prop::collection::vec((0..7usize, 0..7usize), 1..12),
+            revisits in 0..3usize,
+            limits in prop::array::uniform2(0..3usize),
+        ) {
+            // caves 4 to 6 are big, so skip the edges that would connect two of them
+            const NAMES: [&str; 7] = ["start", "end", "a", "b", "C", "D", "E"];
+
+            let lines = edges
+                .iter()
+                .filter(|(a, b)| *a < 4 || *b < 4)
+                .map(|(a, b)| format!("{}-{}", NAMES[*a], NAMES[*b]))
+                .collect::<Vec<_>>();
+            let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+
+            let graph = Graph::parse(&lines).unwrap();
+            let mut policy = Policy::new(&graph, revisits);
+            let limits = HashMap::from([("a", limits[0]), ("b", limits[1])]);
+
+            for (name, limit) in &limits {
+                if let Some(id) = graph.id(name) {
+                    policy.limits[id] = *limit;
+                }
+            }
+
+            let mut tunnels = HashMap::<&str, Vec<&str>>::new();
+
+            for (left, right) in lines.iter().filter_map(|line| line.split_once('-')) {
+                tunnels.entry(left).or_default().push(right);
+                tunnels.entry(right).or_default().push(left);
+            }
+
+            let mut expected = Vec::new();
+            walk(&tunnels, &limits, &mut vec!["start"], revisits, &mut expected);
+            let mut paths = graph.paths(&policy).collect::<Vec<_>>();
+
+            prop_assert_eq!(paths.len(), graph.count_paths(&policy));
+            paths.sort();
+            expected.sort();
+            prop_assert_eq!(paths, expected);
+        }
     }
 }
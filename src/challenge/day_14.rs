@@ -1,127 +1,356 @@
-const LETTER_COUNT: usize = 26;
-const LOOKUP_SIZE: usize = LETTER_COUNT * LETTER_COUNT;
+use crate::matrix::{Arithmetic, Exact, Matrix, Modulo, Native};
+use anyhow::{anyhow, Context};
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let insertions = Insertions::new(&input[2..]);
-    let mut polymer = Polymer::new(input[0]);
+    simulate(input, 10)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    simulate(input, 40)
+}
 
-    for _ in 0..10 {
-        polymer.apply(&insertions);
+/// Prints how often every element shows up after `tool 14 count <steps> <modulus>` steps, modulo
+/// `modulus`, or exactly along with the score when the modulus is `exact`.
+///
+/// Raises the step over the pair counts to the power of `steps` by squaring, so `10^9` steps take
+/// about 60 matrix products. The matrix only covers the pairs that can ever form, which are at
+/// most the template's pairs and two for every rule. Exact counts about double every step though,
+/// so they only stay practical up to thousands of steps.
+pub fn count(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let steps = args
+        .first()
+        .context("Missing steps, expecting `tool 14 count <steps> <modulus>`")?;
+    let steps = steps
+        .parse::<u64>()
+        .with_context(|| format!("Invalid amount of steps {}", steps))?;
+    let modulus = args
+        .get(1)
+        .context("Missing modulus, expecting a number or `exact`")?;
+    let (template, insertions) = parse(input)?;
+
+    match modulus.as_str() {
+        "exact" => {
+            let frequencies = project(&template, &insertions, steps, &Exact);
+            print_frequencies(&insertions, &frequencies);
+            println!("score: {}", score(&frequencies));
+        }
+        modulus => {
+            let modulus = modulus
+                .parse()
+                .with_context(|| format!("Invalid modulus {}", modulus))?;
+            let arithmetic = Modulo::new(modulus)?;
+
+            let frequencies = project(&template, &insertions, steps, &arithmetic);
+            print_frequencies(&insertions, &frequencies);
+        }
     }
 
-    Ok(polymer.score())
+    Ok(())
 }
 
-pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let insertions = Insertions::new(&input[2..]);
-    let mut polymer = Polymer::new(input[0]);
+fn print_frequencies<T: std::fmt::Display>(insertions: &Insertions, frequencies: &[T]) {
+    for (element, frequency) in insertions.elements.iter().zip(frequencies) {
+        println!("{}: {}", char::from(*element), frequency);
+    }
+}
+
+fn simulate(input: &[&str], steps: usize) -> anyhow::Result<u64> {
+    let (template, insertions) = parse(input)?;
 
-    for _ in 0..40 {
-        polymer.apply(&insertions);
+    // the polymer at most doubles in length every step, and no count can exceed its length
+    if steps >= 64 || ((template.len() as u128 - 1) << steps) >= u64::MAX as u128 {
+        return Err(anyhow!(
+            "Counts could overflow after {} steps, use `tool 14 count {} exact`",
+            steps,
+            steps
+        ));
     }
 
-    Ok(polymer.score())
-}
+    let mut polymer = Polymer::new(&template, &insertions, &Native);
 
-type Pair = usize;
+    for _ in 0..steps {
+        polymer.apply(&insertions, &Native);
+    }
 
-fn normalize(byte: u8) -> u8 {
-    byte - b'A'
+    Ok(score(&polymer.frequencies(&insertions, &Native)))
 }
 
-fn pair(x: u8, y: u8) -> Pair {
-    x as usize + y as usize * LETTER_COUNT
+/// Counts the elements after `steps` through powers of the step over the pair counts, instead of
+/// step by step.
+fn project<A: Arithmetic>(
+    template: &[usize],
+    insertions: &Insertions,
+    steps: u64,
+    arithmetic: &A,
+) -> Vec<A::Value> {
+    let mut polymer = Polymer::new(template, insertions, arithmetic);
+
+    // pairs that never form stay at zero, so they can be left out of the matrix
+    let reachable = insertions.reachable(template);
+    let counts = reachable
+        .iter()
+        .map(|&pair| polymer.pairs[pair].clone())
+        .collect::<Vec<_>>();
+    let counts = insertions
+        .transition(&reachable, arithmetic)
+        .pow(steps, arithmetic)
+        .apply(&counts, arithmetic);
+
+    for (&pair, count) in reachable.iter().zip(counts) {
+        polymer.pairs[pair] = count;
+    }
+
+    polymer.frequencies(insertions, arithmetic)
 }
 
-fn split(pair: Pair) -> (u8, u8) {
-    let x = pair % LETTER_COUNT;
-    let y = pair / LETTER_COUNT;
+/// Difference between the most and least common of the elements in the polymer.
+fn score<T>(frequencies: &[T]) -> T
+where
+    T: Clone + Default + Ord + std::ops::Sub<Output = T>,
+{
+    let present = || {
+        frequencies
+            .iter()
+            .filter(|frequency| **frequency > T::default())
+    };
 
-    (x as u8, y as u8)
+    match (present().max(), present().min()) {
+        (Some(max), Some(min)) => max.clone() - min.clone(),
+        _ => T::default(),
+    }
 }
 
-struct Polymer([u64; LOOKUP_SIZE], [u64; LETTER_COUNT]);
+/// Reads the template and the insertion rules, indexing the elements by the bytes they are
+/// written as.
+fn parse(input: &[&str]) -> anyhow::Result<(Vec<usize>, Insertions)> {
+    let template = input.first().context("Missing template")?.as_bytes();
 
-impl Polymer {
-    fn new(string: &str) -> Self {
-        let mut pairs = [0u64; LOOKUP_SIZE];
-        let mut counts = [0u64; LETTER_COUNT];
+    if template.is_empty() {
+        return Err(anyhow!("The template is empty"));
+    }
 
-        let bytes = string.as_bytes();
+    let rules = input
+        .get(2..)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, line)| match line.as_bytes() {
+            &[x, y, b' ', b'-', b'>', b' ', insertion] => Ok((x, y, insertion)),
+            _ => Err(anyhow!("Invalid rule {} on line {}", line, index + 3)),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-        counts[normalize(bytes[0]) as usize] = 1;
+    let mut elements = template.to_vec();
+    elements.extend(
+        rules
+            .iter()
+            .flat_map(|&(x, y, insertion)| [x, y, insertion]),
+    );
+    elements.sort_unstable();
+    elements.dedup();
 
-        for (&x, &y) in bytes.iter().zip(&bytes[1..]) {
-            let x = normalize(x);
-            let y = normalize(y);
-            pairs[pair(x, y)] += 1;
-            counts[y as usize] += 1;
-        }
+    let mut indices = [0; 256];
 
-        Polymer(pairs, counts)
+    for (index, &element) in elements.iter().enumerate() {
+        indices[usize::from(element)] = index;
     }
 
-    fn apply(&mut self, insertions: &Insertions) {
-        let mut pairs = [0u64; LOOKUP_SIZE];
+    let index = |byte: u8| indices[usize::from(byte)];
+    let size = elements.len();
+    let mut lookup = vec![None; size * size];
 
-        for (p, &count) in self.0.iter().enumerate() {
-            if count == 0 {
-                continue;
-            }
+    for &(x, y, insertion) in &rules {
+        let (x, y, insertion) = (index(x), index(y), index(insertion));
+        lookup[x * size + y] = Some([x * size + insertion, insertion * size + y]);
+    }
 
-            if let Some(insertion) = insertions.lookup(p) {
-                let (x, y) = split(p);
-                pairs[pair(x, insertion)] += count;
-                pairs[pair(insertion, y)] += count;
-                self.1[insertion as usize] += count;
-            } else {
-                pairs[p] += count;
-            }
+    let template = template.iter().map(|&byte| index(byte)).collect();
+    Ok((template, Insertions { elements, lookup }))
+}
+
+/// Counts of the pairs of neighbouring elements, which are all the insertions depend on.
+struct Polymer<T> {
+    pairs: Vec<T>,
+    /// Insertions never move the last element, and it's the only one that isn't first in a pair.
+    last: usize,
+}
+
+impl<T: Clone> Polymer<T> {
+    fn new<A: Arithmetic<Value = T>>(
+        template: &[usize],
+        insertions: &Insertions,
+        arithmetic: &A,
+    ) -> Self {
+        let mut pairs = vec![arithmetic.zero(); insertions.pairs()];
+
+        for window in template.windows(2) {
+            let pair = &mut pairs[insertions.pair(window[0], window[1])];
+            *pair = arithmetic.add(pair, &arithmetic.one());
         }
 
-        self.0 = pairs;
+        Polymer {
+            pairs,
+            last: template[template.len() - 1],
+        }
     }
 
-    fn score(&self) -> u64 {
-        let mut min = u64::MAX;
-        let mut max = u64::MIN;
+    fn apply<A: Arithmetic<Value = T>>(&mut self, insertions: &Insertions, arithmetic: &A) {
+        let mut pairs = vec![arithmetic.zero(); self.pairs.len()];
 
-        for count in self.1 {
-            if count == 0 {
-                continue;
+        for (pair, count) in self.pairs.iter().enumerate() {
+            for next in insertions.successors(pair) {
+                pairs[next] = arithmetic.add(&pairs[next], count);
             }
+        }
 
-            if count < min {
-                min = count;
-            }
+        self.pairs = pairs;
+    }
 
-            if count > max {
-                max = count;
-            }
+    /// Counts of every element, in the order of the alphabet.
+    fn frequencies<A: Arithmetic<Value = T>>(
+        &self,
+        insertions: &Insertions,
+        arithmetic: &A,
+    ) -> Vec<T> {
+        let size = insertions.elements.len();
+        let mut frequencies = vec![arithmetic.zero(); size];
+
+        for (pair, count) in self.pairs.iter().enumerate() {
+            let first = &mut frequencies[pair / size];
+            *first = arithmetic.add(first, count);
         }
 
-        max - min
+        frequencies[self.last] = arithmetic.add(&frequencies[self.last], &arithmetic.one());
+        frequencies
     }
 }
 
-struct Insertions([u8; LOOKUP_SIZE]);
+/// Element to insert between every pair of elements, if any.
+struct Insertions {
+    /// Bytes the elements are written as, in ascending order.
+    elements: Vec<u8>,
+    /// Pairs that every pair splits into when something gets inserted into it.
+    lookup: Vec<Option<[usize; 2]>>,
+}
 
 impl Insertions {
-    fn new(lines: &[&str]) -> Insertions {
-        let mut rules = [0; LOOKUP_SIZE];
+    fn pairs(&self) -> usize {
+        self.lookup.len()
+    }
+
+    fn pair(&self, x: usize, y: usize) -> usize {
+        x * self.elements.len() + y
+    }
 
-        for line in lines {
-            let line = line.as_bytes();
-            rules[pair(normalize(line[0]), normalize(line[1]))] = normalize(line[6]);
+    /// Pairs a pair turns into after a step, which is itself unless something gets inserted.
+    fn successors(&self, pair: usize) -> impl Iterator<Item = usize> {
+        let (successors, count) = match self.lookup[pair] {
+            Some(successors) => (successors, 2),
+            None => ([pair; 2], 1),
+        };
+
+        successors.into_iter().take(count)
+    }
+
+    /// Pairs that show up in the polymer at some step, in ascending order.
+    fn reachable(&self, template: &[usize]) -> Vec<usize> {
+        let mut seen = vec![false; self.pairs()];
+        let mut pending = template
+            .windows(2)
+            .map(|window| self.pair(window[0], window[1]))
+            .collect::<Vec<_>>();
+
+        while let Some(pair) = pending.pop() {
+            if !std::mem::replace(&mut seen[pair], true) {
+                pending.extend(self.successors(pair));
+            }
         }
 
-        Insertions(rules)
+        (0..self.pairs()).filter(|&pair| seen[pair]).collect()
+    }
+
+    /// Step over the counts of the given pairs, which must include all of their successors.
+    fn transition<A: Arithmetic>(&self, pairs: &[usize], arithmetic: &A) -> Matrix<A::Value> {
+        let index = |pair| pairs.binary_search(&pair).unwrap();
+        let edges = pairs.iter().enumerate().flat_map(|(column, &pair)| {
+            self.successors(pair).map(move |next| (index(next), column))
+        });
+
+        Matrix::from_edges(pairs.len(), edges, arithmetic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+    use proptest::prelude::*;
+
+    const EXAMPLE: &[&str] = &[
+        "NNCB", "", "CH -> B", "HH -> N", "CB -> H", "NH -> C", "HB -> C", "HC -> B", "HN -> C",
+        "NN -> C", "BH -> H", "NC -> B", "NB -> B", "BN -> B", "BB -> N", "BC -> B", "CC -> N",
+        "CN -> C",
+    ];
+
+    #[test]
+    fn simulate_matches_example() {
+        assert_eq!(simulate(EXAMPLE, 10).unwrap(), 1588);
+        assert_eq!(simulate(EXAMPLE, 40).unwrap(), 2188189693529);
+    }
+
+    #[test]
+    fn simulate_rejects_overflowing_counts() {
+        assert!(simulate(EXAMPLE, 62).is_ok());
+        assert!(simulate(EXAMPLE, 63).is_err());
     }
 
-    fn lookup(&self, pair: Pair) -> Option<u8> {
-        match self.0[pair] {
-            0 => None,
-            insertion => Some(insertion),
+    #[test]
+    fn parse_accepts_any_bytes() {
+        let input = ["aA1", "", "aA -> A", "A1 -> #"];
+        let (template, insertions) = parse(&input).unwrap();
+        assert_eq!(insertions.elements, b"#1Aa");
+        assert_eq!(template, vec![3, 2, 1]);
+
+        let frequencies = project(&template, &insertions, 1, &Exact);
+        let expected = [1u8, 1, 2, 1].map(BigUint::from);
+        assert_eq!(frequencies, expected);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_rules() {
+        assert!(parse(&["AB", "", "AB -> CD"]).is_err());
+        assert!(parse(&["AB", "", "ABC -> D"]).is_err());
+        assert!(parse(&["", "", "AB -> C"]).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn project_matches_simulate(
+            template in "[ABC]{1,8}",
+            rules in prop::collection::vec(("[ABCD]{2}", "[ABCD]"), 0..12),
+            steps in 0..12usize,
+            modulus in 1..1_000_000u64,
+        ) {
+            let rules = rules
+                .iter()
+                .map(|(pair, insertion)| format!("{} -> {}", pair, insertion))
+                .collect::<Vec<_>>();
+            let input = [template.as_str(), ""]
+                .into_iter()
+                .chain(rules.iter().map(String::as_str))
+                .collect::<Vec<_>>();
+            let (template, insertions) = parse(&input).unwrap();
+
+            let mut polymer = Polymer::new(&template, &insertions, &Exact);
+            (0..steps).for_each(|_| polymer.apply(&insertions, &Exact));
+            let exact = polymer.frequencies(&insertions, &Exact);
+            let modular = project(&template, &insertions, steps as u64, &Modulo::new(modulus).unwrap());
+
+            prop_assert_eq!(&project(&template, &insertions, steps as u64, &Exact), &exact);
+            prop_assert_eq!(
+                exact.iter().map(|count| count % modulus).collect::<Vec<_>>(),
+                modular.into_iter().map(BigUint::from).collect::<Vec<_>>()
+            );
         }
     }
 }
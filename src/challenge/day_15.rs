@@ -1,138 +1,717 @@
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use anyhow::{anyhow, Context};
 
-pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let weights = input
-        .iter()
-        .flat_map(|line| line.bytes())
-        .map(|byte| byte - b'0')
-        .collect::<Vec<_>>();
+/// Moves to the four neighbours first, so that the first four directions are the orthogonal ones.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+    (1, -1),
+];
+
+/// Highest risk of a cell, which bounds how far ahead of the current risk the queue has to look.
+const MAX_RISK: u32 = 9;
 
-    Ok(Graph::new(input.len(), &weights).score_best_path())
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let grid = Grid::parse(input)?;
+    Ok(Graph::new(&Tiles::new(&grid, 1, 1), &Settings::default()).find_risk())
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let size = input.len() * 5;
-    let mut buffer = Vec::with_capacity(input.len());
-    let mut weights = Vec::with_capacity(size * size);
+    let grid = Grid::parse(input)?;
+    Ok(Graph::new(&Tiles::new(&grid, 5, 5), &Settings::default()).find_risk())
+}
+
+/// Prints the grid with the safest path highlighted, followed by its risk.
+///
+/// Takes the settings `Settings::parse` reads, like `tool 15 path tiles=5 moves=8 turn=3`.
+pub fn path(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let settings = Settings::parse(args)?;
+    let grid = Grid::parse(input)?;
+    let tiles = Tiles::new(&grid, settings.tiles.0, settings.tiles.1);
+    let route = Graph::new(&tiles, &settings).find_path();
 
-    for &line in input {
-        for byte in line.bytes() {
-            let weight = byte - b'0';
-            buffer.push(weight);
-            weights.push(weight);
+    print!("{}", tiles.draw(&route.cells));
+    println!("risk = {}, steps = {}", route.risk, route.cells.len() - 1);
+    Ok(())
+}
+
+/// Finds the safest path with both Dijkstra and A*, and prints how much work each of them did.
+///
+/// Takes the same settings as `path`, apart from `astar`. Tiles are computed as the search reaches
+/// them, so even `tiles=100` only costs a bit per cell.
+pub fn compare(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let settings = Settings::parse(args)?;
+    let grid = Grid::parse(input)?;
+    let tiles = Tiles::new(&grid, settings.tiles.0, settings.tiles.1);
+
+    for heuristic in [false, true] {
+        let settings = Settings {
+            heuristic,
+            ..settings
+        };
+        let mut graph = Graph::new(&tiles, &settings);
+        let start = std::time::Instant::now();
+        let risk = graph.find_risk();
+
+        println!(
+            "{}: risk = {} (duration = {:?})",
+            if heuristic { "A*" } else { "Dijkstra" },
+            risk,
+            start.elapsed()
+        );
+        print!("{}", graph.stats);
+    }
+
+    Ok(())
+}
+
+/// How the path may move through the grid.
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    /// Times the grid is repeated across and down, each repetition adding one to the risks.
+    tiles: (usize, usize),
+    /// Whether diagonal neighbours can be entered as well, rather than only the four orthogonal
+    /// ones.
+    diagonal: bool,
+    /// Risk added every time the path changes direction.
+    turn_penalty: u32,
+    /// Whether to search with A* rather than Dijkstra.
+    heuristic: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            tiles: (1, 1),
+            diagonal: false,
+            turn_penalty: 0,
+            heuristic: false,
         }
+    }
+}
+
+impl Settings {
+    /// Reads any of `tiles=<n>` or `tiles=<across>x<down>`, `moves=4` or `moves=8`, `turn=<penalty>`
+    /// and `astar`.
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut settings = Settings::default();
+
+        for arg in args {
+            match arg.split_once('=') {
+                Some(("tiles", tiles)) => {
+                    let (across, down) = tiles.split_once('x').unwrap_or((tiles, tiles));
+                    let parse = |factor: &str| {
+                        factor
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|factor| *factor > 0)
+                            .with_context(|| format!("Invalid tiling factor {}", factor))
+                    };
 
-        for i in 1..5 {
-            for weight in &buffer {
-                weights.push((*weight + i - 1) % 9 + 1);
+                    settings.tiles = (parse(across)?, parse(down)?);
+                }
+                Some(("moves", "4")) => settings.diagonal = false,
+                Some(("moves", "8")) => settings.diagonal = true,
+                Some(("turn", penalty)) => {
+                    // the queue keeps a bucket for every risk a single step can add
+                    settings.turn_penalty = penalty
+                        .parse::<u16>()
+                        .with_context(|| format!("Invalid turn penalty {}", penalty))?
+                        .into();
+                }
+                None if arg == "astar" => settings.heuristic = true,
+                _ => return Err(anyhow!("Unknown setting {}", arg)),
             }
         }
 
-        buffer.clear();
+        Ok(settings)
+    }
+
+    fn directions(&self) -> &'static [(isize, isize)] {
+        match self.diagonal {
+            true => &DIRECTIONS,
+            false => &DIRECTIONS[..4],
+        }
+    }
+}
+
+/// Risk levels of the cave, row by row.
+#[derive(Debug)]
+struct Grid {
+    width: usize,
+    height: usize,
+    weights: Vec<u8>,
+}
+
+impl Grid {
+    fn parse(input: &[&str]) -> anyhow::Result<Self> {
+        let width = input.first().map_or(0, |line| line.len());
+
+        if width == 0 {
+            return Err(anyhow!("The grid is empty"));
+        }
+
+        let mut weights = Vec::with_capacity(width * input.len());
+
+        for (y, line) in input.iter().enumerate() {
+            if line.len() != width {
+                return Err(anyhow!(
+                    "Line {} is {} wide instead of {}",
+                    y + 1,
+                    line.len(),
+                    width
+                ));
+            }
+
+            for byte in line.bytes() {
+                match byte {
+                    b'1'..=b'9' => weights.push(byte - b'0'),
+                    _ => {
+                        return Err(anyhow!(
+                            "Invalid risk {} on line {}",
+                            char::from(byte),
+                            y + 1
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(Grid {
+            width,
+            height: input.len(),
+            weights,
+        })
+    }
+}
+
+/// Grid repeated `across` times to the right and `down` times downwards, with every repetition
+/// adding one to the risks of the one before and wrapping them back around to 1 past 9.
+///
+/// The risks are worked out when they're needed rather than stored, since large tilings wouldn't
+/// fit in memory otherwise. Where every column and row falls in the grid is stored though, which
+/// only takes memory along the sides and saves dividing on every lookup.
+struct Tiles<'a> {
+    grid: &'a Grid,
+    width: usize,
+    height: usize,
+    /// Offset of every column into a row of the grid, and what its tile adds to the risks.
+    columns: Vec<(usize, u8)>,
+    /// Offset of every row into the grid, and what its tile adds to the risks.
+    rows: Vec<(usize, u8)>,
+}
+
+impl<'a> Tiles<'a> {
+    fn new(grid: &'a Grid, across: usize, down: usize) -> Self {
+        // increases wrap around every 9 tiles, which keeps them small
+        let columns = (0..grid.width * across)
+            .map(|x| (x % grid.width, (x / grid.width % 9) as u8))
+            .collect();
+        let rows = (0..grid.height * down)
+            .map(|y| (y % grid.height * grid.width, (y / grid.height % 9) as u8))
+            .collect();
+
+        Tiles {
+            grid,
+            width: grid.width * across,
+            height: grid.height * down,
+            columns,
+            rows,
+        }
+    }
+
+    fn cells(&self) -> usize {
+        self.width * self.height
     }
 
-    for i in 1..5 {
-        for index in 0..input.len() * size {
-            weights.push((weights[index] + i - 1) % 9 + 1);
+    fn weight(&self, x: usize, y: usize) -> u8 {
+        let (column, across) = self.columns[x];
+        let (row, down) = self.rows[y];
+
+        (self.grid.weights[row + column] + across + down - 1) % 9 + 1
+    }
+
+    /// Lowest risk of any cell, looking at each repetition of the risks once.
+    fn min_weight(&self) -> u8 {
+        let increases = (self.width / self.grid.width + self.height / self.grid.height - 1).min(9);
+
+        (0..increases)
+            .flat_map(|increase| {
+                self.grid
+                    .weights
+                    .iter()
+                    .map(move |weight| ((*weight as usize + increase - 1) % 9 + 1) as u8)
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Prints the risks, making the ones on the path bold and red.
+    fn draw(&self, path: &[usize]) -> String {
+        let mut on_path = vec![false; self.cells()];
+        path.iter().for_each(|cell| on_path[*cell] = true);
+
+        let mut output = String::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let weight = char::from(b'0' + self.weight(x, y));
+
+                match on_path[y * self.width + x] {
+                    true => output.push_str(&format!("\x1b[1;31m{}\x1b[0m", weight)),
+                    false => output.push(weight),
+                }
+            }
+
+            output.push('\n');
         }
+
+        output
     }
+}
+
+/// Safest path from the top left to the bottom right of the grid.
+struct Route {
+    risk: u32,
+    /// Cells along the path, from the top left one.
+    cells: Vec<usize>,
+}
 
-    Ok(Graph::new(size, &weights).score_best_path())
+/// Counters describing how much work a search did.
+#[derive(Default)]
+struct Stats {
+    /// States taken off the queue to look at their neighbours.
+    expanded: usize,
+    /// States taken off the queue again after a safer way to reach them had been settled.
+    stale: usize,
+    peak_queue: usize,
 }
 
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  expanded   = {}", self.expanded)?;
+        writeln!(f, "  stale      = {}", self.stale)?;
+        writeln!(f, "  peak queue = {}", self.peak_queue)
+    }
+}
+
+/// Search over the states of the path, which are the cells of the grid, along with the direction
+/// the path entered them from when turns are penalised.
+///
+/// Moving into a cell always adds the same risk however the path gets there, so states are only
+/// marked once they're settled rather than scored, which takes a bit per state. Without turn
+/// penalties or a heuristic, cells come off the queue in order of their risk, so the first way
+/// into a cell is already the safest one and it's settled as it's pushed.
 struct Graph<'a> {
-    size: usize,
-    weights: &'a [u8],
-    scores: Vec<u32>,
-    queue: BinaryHeap<Entry>,
+    tiles: &'a Tiles<'a>,
+    directions: &'static [(isize, isize)],
+    /// States per cell: one per direction plus one for the start, or a single one if turns are
+    /// free.
+    headings: usize,
+    turn_penalty: u32,
+    /// Lowest risk of a single step, which the heuristic multiplies by the amount of steps left.
+    step_risk: Option<u32>,
+    settled: Vec<u64>,
+    /// Whether states are settled as they're pushed rather than as they're popped.
+    eager: bool,
+    /// Step into every settled state, when the path is being recorded.
+    steps: Option<Vec<u8>>,
+    queue: BucketQueue<Entry>,
+    stats: Stats,
 }
 
 impl<'a> Graph<'a> {
-    fn new(size: usize, weights: &'a [u8]) -> Self {
-        let length = weights.len();
+    fn new(tiles: &'a Tiles<'a>, settings: &Settings) -> Self {
+        let directions = settings.directions();
+        let headings = match settings.turn_penalty {
+            0 => 1,
+            _ => directions.len() + 1,
+        };
+        let step_risk = settings.heuristic.then(|| tiles.min_weight() as u32);
+        // moving away from the end raises the estimate by at most a step's risk
+        let span = MAX_RISK + settings.turn_penalty + step_risk.unwrap_or(0);
 
         Graph {
-            weights,
-            size,
-            scores: vec![u32::MAX; length],
-            queue: BinaryHeap::new(),
+            tiles,
+            directions,
+            headings,
+            turn_penalty: settings.turn_penalty,
+            step_risk,
+            settled: vec![0; (tiles.cells() * headings).div_ceil(64)],
+            eager: headings == 1 && step_risk.is_none(),
+            steps: None,
+            queue: BucketQueue::new(span as usize),
+            stats: Stats::default(),
         }
     }
 
-    fn score_best_path(&mut self) -> u32 {
-        let end = self.scores.len() - 1;
+    fn find_risk(&mut self) -> u32 {
+        self.search().score
+    }
 
-        self.scores[0] = 0;
-        self.queue.push(Entry::new(0, 0));
+    fn find_path(&mut self) -> Route {
+        self.steps = Some(vec![0; self.tiles.cells() * self.headings]);
 
-        while let Some(entry) = self.queue.pop() {
-            let index = entry.index();
+        let end = self.search();
+        self.route(&end)
+    }
 
-            if index == end {
-                break;
-            }
+    #[tracing::instrument(name = "search", skip_all)]
+    fn search(&mut self) -> Entry {
+        let end = self.tiles.cells() - 1;
+        // the start state is the one past the directions, that no turn is counted from
+        let start = Entry {
+            state: self.headings - 1,
+            score: 0,
+            step: 0,
+        };
 
-            let score = self.scores[index];
+        if self.eager {
+            self.settle(start.state, start.step);
+        }
 
-            let x = index % self.size;
-            let y = index / self.size;
+        self.queue.push(self.estimate(0), start);
 
-            if x > 0 {
-                self.visit(index - 1, score);
-            }
+        while let Some(entry) = self.queue.pop() {
+            let state = entry.state;
 
-            if x + 1 < self.size {
-                self.visit(index + 1, score);
+            if !self.eager && !self.settle(state, entry.step) {
+                self.stats.stale += 1;
+                continue;
             }
 
-            if y > 0 {
-                self.visit(index - self.size, score);
+            if self.split(state).0 == end {
+                return entry;
             }
 
-            if y + 1 < self.size {
-                self.visit(index + self.size, score);
+            self.stats.expanded += 1;
+            self.expand(&entry);
+            self.stats.peak_queue = self.stats.peak_queue.max(self.queue.len());
+        }
+
+        unreachable!("every cell can be reached")
+    }
+
+    fn expand(&mut self, entry: &Entry) {
+        let (cell, heading) = self.split(entry.state);
+        let (x, y) = (cell % self.tiles.width, cell / self.tiles.width);
+
+        for (direction, (dx, dy)) in self.directions.iter().enumerate() {
+            let (x, y) = match (x.checked_add_signed(*dx), y.checked_add_signed(*dy)) {
+                (Some(x), Some(y)) if x < self.tiles.width && y < self.tiles.height => (x, y),
+                _ => continue,
+            };
+
+            let cell = y * self.tiles.width + x;
+            let mut score = entry.score + self.tiles.weight(x, y) as u32;
+
+            // without headings, the step is the direction taken, and with them, it's the heading
+            // the path came from, since the direction taken is the heading of the new state
+            let (state, step) = match self.headings {
+                1 => (cell, direction),
+                headings => {
+                    if heading != direction && heading != headings - 1 {
+                        score += self.turn_penalty;
+                    }
+
+                    (cell * headings + direction, heading)
+                }
+            };
+
+            let step = step as u8;
+            let fresh = match self.eager {
+                true => self.settle(state, step),
+                false => self.settled[state / 64] & 1 << (state % 64) == 0,
+            };
+
+            if fresh {
+                let priority = score + self.estimate(cell);
+                self.queue.push(priority, Entry { state, score, step });
             }
         }
+    }
 
-        self.scores[end]
+    /// Cell of the state and the heading the path entered it with, which is always 0 without
+    /// headings and spares dividing by 1 on every step.
+    fn split(&self, state: usize) -> (usize, usize) {
+        match self.headings {
+            1 => (state, 0),
+            headings => (state / headings, state % headings),
+        }
     }
 
-    fn visit(&mut self, index: usize, parent_score: u32) {
-        let score = parent_score + self.weights[index] as u32;
+    /// Marks the state as settled and records the step into it, unless it was settled already.
+    fn settle(&mut self, state: usize, step: u8) -> bool {
+        let (word, bit) = (state / 64, 1 << (state % 64));
 
-        if score < self.scores[index] {
-            self.scores[index] = score;
-            self.queue.push(Entry::new(index, score));
+        if self.settled[word] & bit != 0 {
+            return false;
+        }
+
+        self.settled[word] |= bit;
+
+        if let Some(steps) = &mut self.steps {
+            steps[state] = step;
+        }
+
+        true
+    }
+
+    /// Lower bound of the risk left from a cell to the end, which is zero without a heuristic.
+    fn estimate(&self, cell: usize) -> u32 {
+        let step_risk = match self.step_risk {
+            Some(step_risk) => step_risk,
+            None => return 0,
+        };
+
+        let dx = self.tiles.width - 1 - cell % self.tiles.width;
+        let dy = self.tiles.height - 1 - cell / self.tiles.width;
+
+        // a diagonal step covers both axes at once
+        let steps = match self.directions.len() {
+            4 => dx + dy,
+            _ => dx.max(dy),
+        };
+
+        steps as u32 * step_risk
+    }
+
+    /// Follows the recorded steps back from the end to the start.
+    fn route(&self, end: &Entry) -> Route {
+        let steps = self.steps.as_ref().expect("steps are recorded");
+        let start = self.headings - 1;
+        let mut state = end.state;
+        let mut cells = vec![state / self.headings];
+
+        while state != start {
+            let cell = state / self.headings;
+            let (direction, heading) = match self.headings {
+                1 => (steps[state] as usize, 0),
+                _ => (state % self.headings, steps[state] as usize),
+            };
+
+            let (dx, dy) = self.directions[direction];
+            let x = (cell % self.tiles.width).wrapping_add_signed(-dx);
+            let y = (cell / self.tiles.width).wrapping_add_signed(-dy);
+            let cell = y * self.tiles.width + x;
+
+            state = cell * self.headings + heading;
+            cells.push(cell);
+        }
+
+        cells.reverse();
+
+        Route {
+            risk: end.score,
+            cells,
         }
     }
 }
 
-#[derive(Eq, PartialEq)]
+/// State on the queue, with the risk of reaching it and the step taken into it.
 struct Entry {
-    index: u32,
+    state: usize,
     score: u32,
+    step: u8,
 }
 
-impl Entry {
-    fn new(index: usize, score: u32) -> Self {
-        let index = index as u32;
-        Entry { index, score }
+/// Priority queue for priorities that never drop below the last one taken off, nor rise more than
+/// a fixed span above it, which keeps entries in a ring of buckets, one per priority, as in Dial's
+/// algorithm.
+struct BucketQueue<T> {
+    /// Buckets for at least every priority in the span, rounded up to a power of two so that
+    /// finding a priority's bucket takes a mask rather than a division.
+    buckets: Vec<Vec<T>>,
+    /// Priority of the last entry taken off the queue, or of the first one pushed before that.
+    current: Option<u32>,
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    fn new(span: usize) -> Self {
+        BucketQueue {
+            buckets: (0..(span + 1).next_power_of_two())
+                .map(|_| Vec::new())
+                .collect(),
+            current: None,
+            len: 0,
+        }
     }
 
-    fn index(&self) -> usize {
-        self.index as usize
+    fn push(&mut self, priority: u32, item: T) {
+        let current = *self.current.get_or_insert(priority);
+
+        debug_assert!(priority >= current);
+        debug_assert!(((priority - current) as usize) < self.buckets.len());
+
+        let bucket = priority as usize & (self.buckets.len() - 1);
+        self.buckets[bucket].push(item);
+        self.len += 1;
     }
-}
 
-impl Ord for Entry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.score.cmp(&self.score)
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let current = self.current.as_mut()?;
+
+        loop {
+            let bucket = *current as usize & (self.buckets.len() - 1);
+
+            if let Some(item) = self.buckets[bucket].pop() {
+                self.len -= 1;
+                return Some(item);
+            }
+
+            *current += 1;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
     }
 }
 
-impl PartialOrd for Entry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const EXAMPLE: &[&str] = &[
+        "1163751742",
+        "1381373672",
+        "2136511328",
+        "3694931569",
+        "7463417111",
+        "1319128137",
+        "1359912421",
+        "3125421639",
+        "1293138521",
+        "2311944581",
+    ];
+
+    fn risk(grid: &Grid, settings: &Settings) -> u32 {
+        let (across, down) = settings.tiles;
+        Graph::new(&Tiles::new(grid, across, down), settings).find_risk()
+    }
+
+    fn grid() -> impl Strategy<Value = Grid> {
+        (1..8usize, 1..8usize).prop_flat_map(|(width, height)| {
+            prop::collection::vec(1..=9u8, width * height).prop_map(move |weights| Grid {
+                width,
+                height,
+                weights,
+            })
+        })
+    }
+
+    fn settings() -> impl Strategy<Value = Settings> {
+        (1..3usize, 1..3usize, any::<bool>(), 0..5u32).prop_map(
+            |(across, down, diagonal, turn_penalty)| Settings {
+                tiles: (across, down),
+                diagonal,
+                turn_penalty,
+                ..Settings::default()
+            },
+        )
+    }
+
+    #[test]
+    fn find_path_matches_example() {
+        let grid = Grid::parse(EXAMPLE).unwrap();
+        let settings = Settings::default();
+        assert_eq!(risk(&grid, &settings), 40);
+
+        let tiled = Settings {
+            tiles: (5, 5),
+            ..settings
+        };
+        assert_eq!(risk(&grid, &tiled), 315);
+    }
+
+    #[test]
+    fn tiles_handle_rectangles() {
+        let grid = Grid::parse(&["89", "91", "12"]).unwrap();
+        let tiles = Tiles::new(&grid, 3, 2);
+        let row = |y| (0..6).map(|x| tiles.weight(x, y)).collect::<Vec<_>>();
+
+        assert_eq!((tiles.width, tiles.height), (6, 6));
+        assert_eq!(row(0), [8, 9, 9, 1, 1, 2]);
+        assert_eq!(row(5), [2, 3, 3, 4, 4, 5]);
+        assert_eq!(tiles.min_weight(), 1);
+        assert_eq!(Tiles::new(&grid, 1, 1).min_weight(), 1);
+        assert_eq!(
+            Tiles::new(&Grid::parse(&["56"]).unwrap(), 2, 1).min_weight(),
+            5
+        );
+    }
+
+    #[test]
+    fn parse_rejects_ragged_grids() {
+        assert!(Grid::parse(&["123", "45"]).is_err());
+        assert!(Grid::parse(&["120"]).is_err());
+        assert!(Settings::parse(&["tiles=0".to_string()]).is_err());
+        assert!(Settings::parse(&["moves=6".to_string()]).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn a_star_matches_dijkstra(grid in grid(), settings in settings()) {
+            let a_star = Settings { heuristic: true, ..settings };
+            prop_assert_eq!(risk(&grid, &a_star), risk(&grid, &settings));
+        }
+
+        #[test]
+        fn route_adds_up_to_its_risk(grid in grid(), settings in settings()) {
+            let tiles = Tiles::new(&grid, settings.tiles.0, settings.tiles.1);
+            let route = Graph::new(&tiles, &settings).find_path();
+            let position = |cell: usize| ((cell % tiles.width) as isize, (cell / tiles.width) as isize);
+            let mut previous = None;
+            let mut risk = 0;
+
+            for step in route.cells.windows(2) {
+                let ((x, y), (next_x, next_y)) = (position(step[0]), position(step[1]));
+                let direction = (next_x - x, next_y - y);
+                prop_assert!(settings.directions().contains(&direction));
+
+                if previous.is_some_and(|previous| previous != direction) {
+                    risk += settings.turn_penalty;
+                }
+
+                risk += tiles.weight(next_x as usize, next_y as usize) as u32;
+                previous = Some(direction);
+            }
+
+            prop_assert_eq!(route.cells[0], 0);
+            prop_assert_eq!(*route.cells.last().unwrap(), tiles.cells() - 1);
+            prop_assert_eq!(risk, route.risk);
+        }
+
+        #[test]
+        fn bucket_queue_pops_in_order(pushes in prop::collection::vec(prop::collection::vec(0..=9u32, 0..4), 1..50)) {
+            let mut queue = BucketQueue::new(9);
+            let mut popped = Vec::new();
+            queue.push(0, 0);
+
+            // every entry taken off pushes a few more at most 9 above it, like a search would
+            for offsets in &pushes {
+                let Some(priority) = queue.pop() else { break };
+                popped.push(priority);
+                offsets.iter().for_each(|offset| queue.push(priority + offset, priority + offset));
+            }
+
+            prop_assert!(popped.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+
+        #[test]
+        fn diagonal_moves_are_no_riskier(grid in grid()) {
+            let diagonal = Settings { diagonal: true, ..Settings::default() };
+            prop_assert!(risk(&grid, &diagonal) <= risk(&grid, &Settings::default()));
+        }
     }
 }
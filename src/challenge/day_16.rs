@@ -1,184 +1,838 @@
+use crate::bits::{BitReader, BitWriter};
 use anyhow::anyhow;
+use num_bigint::BigUint;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(parse(&parse_bits(input[0])?).version)
+    Ok(Packet::decode(input[0])?.version_sum())
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(parse(&parse_bits(input[0])?).value)
-}
-
-const BITS: [[bool; 4]; 16] = [
-    [false, false, false, false],
-    [false, false, false, true],
-    [false, false, true, false],
-    [false, false, true, true],
-    [false, true, false, false],
-    [false, true, false, true],
-    [false, true, true, false],
-    [false, true, true, true],
-    [true, false, false, false],
-    [true, false, false, true],
-    [true, false, true, false],
-    [true, false, true, true],
-    [true, true, false, false],
-    [true, true, false, true],
-    [true, true, true, false],
-    [true, true, true, true],
-];
-
-struct ParseResult {
-    version: usize,
-    value: u64,
-    length: usize,
+    let packet = Packet::decode(input[0])?;
+
+    // operators without the sub-packets they need have no value, unlike their versions
+    packet.validate()?;
+
+    // puzzle inputs always fit into an u64, generated or hand-made transmissions might not
+    Ok(match packet.checked_value() {
+        Ok(value) => value.to_string(),
+        Err(_) => packet.big_value().to_string(),
+    })
 }
 
-struct LiteralResult {
-    value: u64,
-    length: usize,
+/// Prints the transmission as a single line S-expression.
+pub fn print(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    let packet = Packet::decode(input[0])?;
+    println!("{}", packet);
+    warn_if_invalid(&packet);
+    Ok(())
 }
 
-struct NestedResult {
-    version: usize,
-    values: Vec<u64>,
-    length: usize,
+/// Prints the transmission as an indented S-expression, including versions and length types.
+pub fn tree(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    let packet = Packet::decode(input[0])?;
+    println!("{:#}", packet);
+    warn_if_invalid(&packet);
+    Ok(())
 }
 
-fn parse(bits: &[bool]) -> ParseResult {
-    let version = (bits[0] as usize) << 2 | (bits[1] as usize) << 1 | bits[2] as usize;
-    let type_id = (bits[3] as usize) << 2 | (bits[4] as usize) << 1 | bits[5] as usize;
+/// Evaluates the transmission both with overflow checks and with arbitrary precision.
+pub fn eval(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    let packet = Packet::decode(input[0])?;
+    packet.validate()?;
 
-    if type_id == 4 {
-        let LiteralResult { value, length } = parse_literal(&bits[6..]);
-        let length = length + 6;
+    match packet.checked_value() {
+        Ok(value) => println!("checked: {}", value),
+        Err(overflow) => println!("checked: {}", overflow),
+    }
 
-        return ParseResult {
-            version,
-            value,
-            length,
-        };
+    println!("exact: {}", packet.big_value());
+    Ok(())
+}
+
+/// Decodes the transmission and encodes it again, which should reproduce the input.
+pub fn encode(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    let encoded = Packet::decode(input[0])?.encode()?;
+    println!("{}", encoded);
+
+    if encoded != input[0] {
+        eprintln!("Encoded transmission differs from the input, which might have extra padding");
+    }
+
+    Ok(())
+}
+
+fn warn_if_invalid(packet: &Packet) {
+    if let Err(error) = packet.validate() {
+        eprintln!("Invalid transmission: {}", error);
+    }
+}
+
+const LITERAL_TYPE_ID: u8 = 4;
+
+/// Width of the total length in bits of the sub-packets, for the bits length type.
+const LENGTH_WIDTH: usize = 15;
+
+/// Width of the number of sub-packets, for the count length type.
+const COUNT_WIDTH: usize = 11;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Packet {
+    version: u8,
+    body: Body,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Body {
+    /// The 4-bit groups of the value, most significant first, including any leading zero groups.
+    Literal(Vec<u8>),
+    Operator(Operator, LengthType, Vec<Packet>),
+}
+
+#[derive(IntoPrimitive, TryFromPrimitive, Copy, Clone, Eq, PartialEq, Debug)]
+#[repr(u8)]
+enum Operator {
+    Sum = 0,
+    Product = 1,
+    Minimum = 2,
+    Maximum = 3,
+    GreaterThan = 5,
+    LessThan = 6,
+    EqualTo = 7,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum LengthType {
+    /// The sub-packets are prefixed with their total length in bits.
+    Bits,
+    /// The sub-packets are prefixed with their count.
+    Count,
+}
+
+impl Packet {
+    fn decode(hex: &str) -> anyhow::Result<Self> {
+        let bytes = parse_hex(hex)?;
+        let mut reader = BitReader::new(&bytes, hex.len() * 4);
+        let packet = Packet::parse(&mut reader)?;
+
+        while reader.remaining() > 0 {
+            let position = reader.position();
+            let count = reader.remaining().min(64);
+            let padding = reader.read(count)?;
+
+            if padding != 0 {
+                let index = position + count - (64 - padding.leading_zeros() as usize);
+                return Err(anyhow!("Padding bit {} is not zero", index));
+            }
+        }
+
+        Ok(packet)
+    }
+
+    /// Parses the outermost packet, keeping the operators it is nested in on a stack instead of
+    /// recursing, so arbitrarily deep transmissions can't overflow the call stack.
+    fn parse(reader: &mut BitReader) -> anyhow::Result<Self> {
+        let mut open: Vec<OpenOperator> = Vec::new();
+
+        loop {
+            let version = reader.read(3)? as u8;
+            let type_id = reader.read(3)? as u8;
+
+            if type_id == LITERAL_TYPE_ID {
+                let body = Body::Literal(parse_literal(reader)?);
+
+                match open.last_mut() {
+                    Some(parent) => parent.packets.push(Packet { version, body }),
+                    None => return Ok(Packet { version, body }),
+                }
+            } else {
+                let operator = Operator::try_from(type_id)?;
+
+                let (length_type, width) = if reader.read_bit()? {
+                    (LengthType::Count, COUNT_WIDTH)
+                } else {
+                    (LengthType::Bits, LENGTH_WIDTH)
+                };
+
+                let length = reader.read(width)? as usize;
+                open.push(OpenOperator::new(
+                    version,
+                    operator,
+                    length_type,
+                    length,
+                    reader,
+                ));
+            }
+
+            // a packet can complete every operator it is the last sub-packet of, innermost first
+            while open.last().unwrap().is_complete(reader)? {
+                let packet = open.pop().unwrap().into_packet();
+
+                match open.last_mut() {
+                    Some(parent) => parent.packets.push(packet),
+                    None => return Ok(packet),
+                }
+            }
+        }
+    }
+
+    fn packets(&self) -> &[Packet] {
+        match &self.body {
+            Body::Literal(_) => &[],
+            Body::Operator(_, _, packets) => packets,
+        }
     }
 
-    let result = if bits[6] {
-        parse_nested_by_count(&bits[7..])
-    } else {
-        parse_nested_by_length(&bits[7..])
-    };
+    /// Visits every packet depth first, both before and after its sub-packets, together with its
+    /// path, stopping at the first error.
+    fn walk<E>(
+        &self,
+        mut visit: impl FnMut(Visit, &Packet, &[usize]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut path = Vec::new();
+        let mut stack = vec![(self, 0)];
+        visit(Visit::Enter, self, &path)?;
+
+        while let Some((packet, next)) = stack.last_mut() {
+            let packet: &Packet = packet;
+
+            match packet.packets().get(*next) {
+                Some(nested) => {
+                    path.push(*next);
+                    *next += 1;
+                    visit(Visit::Enter, nested, &path)?;
+                    stack.push((nested, 0));
+                }
+                None => {
+                    visit(Visit::Leave, packet, &path)?;
+                    stack.pop();
+                    path.pop();
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-    let version = version + result.version;
-    let length = result.length + 7;
+    fn version_sum(&self) -> usize {
+        let mut sum = 0;
+        let mut pending = vec![self];
+
+        while let Some(packet) = pending.pop() {
+            sum += packet.version as usize;
+            pending.extend(packet.packets());
+        }
+
+        sum
+    }
 
-    let value = match type_id {
-        0 => result.values.iter().copied().sum(),
-        1 => result.values.iter().copied().product(),
-        2 => result.values.iter().copied().min().unwrap(),
-        3 => result.values.iter().copied().max().unwrap(),
-        5 => (result.values[0] > result.values[1]) as u64,
-        6 => (result.values[0] < result.values[1]) as u64,
-        7 => (result.values[0] == result.values[1]) as u64,
-        _ => 0,
-    };
+    /// Checks that every operator has sub-packets it can be evaluated with.
+    fn validate(&self) -> anyhow::Result<()> {
+        self.walk(|visit, packet, path| {
+            let operator = match (visit, &packet.body) {
+                (Visit::Enter, Body::Operator(operator, _, _)) => operator,
+                _ => return Ok(()),
+            };
+
+            let packets = packet.packets();
+
+            if operator.is_comparison() && packets.len() != 2 {
+                return Err(anyhow!(
+                    "{} at {} expects 2 sub-packets, found {}",
+                    operator,
+                    PacketPath(path),
+                    packets.len()
+                ));
+            }
+
+            if packets.is_empty() {
+                return Err(anyhow!(
+                    "{} at {} has no sub-packets",
+                    operator,
+                    PacketPath(path)
+                ));
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Evaluates a validated packet, failing as soon as any packet's value doesn't fit an u64.
+    fn checked_value(&self) -> Result<u64, Overflow> {
+        let mut values = Vec::new();
+
+        self.walk(|visit, packet, path| {
+            let value = match (visit, &packet.body) {
+                (Visit::Enter, _) => return Ok(()),
+                (Visit::Leave, Body::Literal(groups)) => {
+                    groups.iter().try_fold(0u64, |acc, &group| {
+                        Some(acc.checked_mul(16)? | group as u64)
+                    })
+                }
+                (Visit::Leave, Body::Operator(operator, _, packets)) => {
+                    let nested = values.split_off(values.len() - packets.len());
+                    operator.checked_apply(&nested)
+                }
+            };
+
+            values.push(value.ok_or_else(|| Overflow(path.to_vec()))?);
+            Ok(())
+        })?;
+
+        Ok(values[0])
+    }
+
+    /// Evaluates a validated packet with arbitrary precision.
+    fn big_value(&self) -> BigUint {
+        let mut values = Vec::new();
+
+        let Ok(()) = self.walk(|visit, packet, _| {
+            let value = match (visit, &packet.body) {
+                (Visit::Enter, _) => return Ok::<_, Infallible>(()),
+                (Visit::Leave, Body::Literal(groups)) => literal_value(groups),
+                (Visit::Leave, Body::Operator(operator, _, packets)) => {
+                    let nested = values.split_off(values.len() - packets.len());
+                    operator.big_apply(nested)
+                }
+            };
+
+            values.push(value);
+            Ok(())
+        });
+
+        values.pop().unwrap()
+    }
+
+    /// Encodes the packet as a hex transmission, padded with zeros to a whole amount of bytes.
+    ///
+    /// Fails when the sub-packets of an operator don't fit into its length type.
+    fn encode(&self) -> anyhow::Result<String> {
+        let mut writer = BitWriter::new();
+        // where the length of each operator being written goes, and where its sub-packets start
+        let mut lengths = Vec::new();
+
+        self.walk(|visit, packet, path| {
+            match (visit, &packet.body) {
+                (Visit::Enter, Body::Literal(groups)) => {
+                    writer.write(packet.version as u64, 3);
+                    writer.write(LITERAL_TYPE_ID as u64, 3);
+
+                    for (index, group) in groups.iter().enumerate() {
+                        writer.write_bit(index + 1 < groups.len());
+                        writer.write(*group as u64, 4);
+                    }
+                }
+                (Visit::Enter, Body::Operator(operator, length_type, _)) => {
+                    writer.write(packet.version as u64, 3);
+                    writer.write(u8::from(*operator) as u64, 3);
+                    writer.write_bit(*length_type == LengthType::Count);
+
+                    // the length is only known once the sub-packets are written
+                    lengths.push((writer.len(), writer.len() + length_type.width()));
+                    writer.write(0, length_type.width());
+                }
+                (Visit::Leave, Body::Literal(_)) => {}
+                (Visit::Leave, Body::Operator(operator, length_type, packets)) => {
+                    let (position, start) = lengths.pop().unwrap();
+                    let width = length_type.width();
+
+                    let length = match length_type {
+                        LengthType::Bits => writer.len() - start,
+                        LengthType::Count => packets.len(),
+                    };
+
+                    if length >> width != 0 {
+                        return Err(anyhow!(
+                            "{} at {} has a {} length of {}, which doesn't fit into {} bits",
+                            operator,
+                            PacketPath(path),
+                            length_type,
+                            length,
+                            width
+                        ));
+                    }
+
+                    writer.overwrite(position, length as u64, width);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(to_hex(&writer.into_bytes()))
+    }
+}
+
+/// Formats the packet as an S-expression like `(sum (lit 1) (max (lit 2) (lit 3)))`.
+///
+/// The alternate form puts every packet on its own line and annotates it with its version and
+/// length type, like `(sum@3/bits`.
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.walk(|visit, packet, path| {
+            if visit == Visit::Leave {
+                return match packet.body {
+                    Body::Literal(_) => Ok(()),
+                    Body::Operator(..) => write!(f, ")"),
+                };
+            }
+
+            if !path.is_empty() {
+                if f.alternate() {
+                    write!(f, "\n{:indent$}", "", indent = path.len() * 2)?;
+                } else {
+                    write!(f, " ")?;
+                }
+            }
+
+            match &packet.body {
+                Body::Literal(groups) if f.alternate() => {
+                    let value = literal_value(groups);
+                    write!(f, "(lit@{} {})", packet.version, value)
+                }
+                Body::Literal(groups) => write!(f, "(lit {})", literal_value(groups)),
+                Body::Operator(operator, length_type, _) if f.alternate() => {
+                    write!(f, "({}@{}/{}", operator, packet.version, length_type)
+                }
+                Body::Operator(operator, _, _) => write!(f, "({}", operator),
+            }
+        })
+    }
+}
+
+/// Drops the sub-packets one at a time, as dropping them recursively could overflow the stack.
+impl Drop for Packet {
+    fn drop(&mut self) {
+        let mut pending = match &mut self.body {
+            Body::Literal(_) => return,
+            Body::Operator(_, _, packets) => std::mem::take(packets),
+        };
 
-    ParseResult {
-        version,
-        value,
-        length,
+        while let Some(mut packet) = pending.pop() {
+            if let Body::Operator(_, _, packets) = &mut packet.body {
+                pending.append(packets);
+            }
+        }
     }
 }
 
-fn parse_literal(bits: &[bool]) -> LiteralResult {
-    let mut i = 0;
-    let mut value = parse_number(&bits[1..5]);
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Visit {
+    /// Before any of the sub-packets.
+    Enter,
+    /// After all of the sub-packets.
+    Leave,
+}
+
+/// An operator whose sub-packets are still being parsed.
+struct OpenOperator {
+    version: u8,
+    operator: Operator,
+    length_type: LengthType,
+    length: usize,
+    /// Position of the first sub-packet.
+    start: usize,
+    packets: Vec<Packet>,
+}
+
+impl OpenOperator {
+    fn new(
+        version: u8,
+        operator: Operator,
+        length_type: LengthType,
+        length: usize,
+        reader: &BitReader,
+    ) -> Self {
+        let capacity = match length_type {
+            LengthType::Bits => 2,
+            LengthType::Count => length,
+        };
+
+        OpenOperator {
+            version,
+            operator,
+            length_type,
+            length,
+            start: reader.position(),
+            packets: Vec::with_capacity(capacity),
+        }
+    }
 
-    while bits[i] {
-        i += 5;
-        value = parse_number_with_base(value, &bits[i + 1..i + 5]);
+    fn is_complete(&self, reader: &BitReader) -> anyhow::Result<bool> {
+        match self.length_type {
+            LengthType::Count => Ok(self.packets.len() == self.length),
+            LengthType::Bits => {
+                let used = reader.position() - self.start;
+
+                if used > self.length {
+                    return Err(anyhow!(
+                        "Sub-packets take up {} bits instead of {}",
+                        used,
+                        self.length
+                    ));
+                }
+
+                Ok(used == self.length)
+            }
+        }
     }
 
-    let length = i + 5;
-    LiteralResult { value, length }
+    fn into_packet(self) -> Packet {
+        let body = Body::Operator(self.operator, self.length_type, self.packets);
+        Packet {
+            version: self.version,
+            body,
+        }
+    }
 }
 
-fn parse_nested_by_count(bits: &[bool]) -> NestedResult {
-    let count = parse_number(&bits[..11]);
+impl Operator {
+    fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::GreaterThan | Operator::LessThan | Operator::EqualTo
+        )
+    }
 
-    let mut index = 11;
-    let mut version = 0;
-    let mut values = Vec::with_capacity(count);
+    /// Applies the operator to the values of its sub-packets, or `None` when it overflows.
+    fn checked_apply(&self, values: &[u64]) -> Option<u64> {
+        match self {
+            Operator::Sum => values
+                .iter()
+                .try_fold(0u64, |acc, &value| acc.checked_add(value)),
+            // without a zero factor the partial products only grow, so any overflow is real
+            Operator::Product if values.contains(&0) => Some(0),
+            Operator::Product => values
+                .iter()
+                .try_fold(1u64, |acc, &value| acc.checked_mul(value)),
+            Operator::Minimum => values.iter().copied().min(),
+            Operator::Maximum => values.iter().copied().max(),
+            Operator::GreaterThan => Some((values[0] > values[1]) as u64),
+            Operator::LessThan => Some((values[0] < values[1]) as u64),
+            Operator::EqualTo => Some((values[0] == values[1]) as u64),
+        }
+    }
 
-    for _ in 0..count {
-        let result = parse(&bits[index..]);
-        index += result.length;
-        version += result.version;
-        values.push(result.value);
+    fn big_apply(&self, values: Vec<BigUint>) -> BigUint {
+        match self {
+            Operator::Sum => values.into_iter().sum(),
+            Operator::Product => values.into_iter().product(),
+            Operator::Minimum => values.into_iter().min().unwrap(),
+            Operator::Maximum => values.into_iter().max().unwrap(),
+            Operator::GreaterThan => BigUint::from(values[0] > values[1]),
+            Operator::LessThan => BigUint::from(values[0] < values[1]),
+            Operator::EqualTo => BigUint::from(values[0] == values[1]),
+        }
     }
+}
 
-    let length = index;
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operator::Sum => "sum",
+            Operator::Product => "product",
+            Operator::Minimum => "min",
+            Operator::Maximum => "max",
+            Operator::GreaterThan => "gt",
+            Operator::LessThan => "lt",
+            Operator::EqualTo => "eq",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl LengthType {
+    /// Width of the length that prefixes the sub-packets.
+    fn width(&self) -> usize {
+        match self {
+            LengthType::Bits => LENGTH_WIDTH,
+            LengthType::Count => COUNT_WIDTH,
+        }
+    }
+}
 
-    NestedResult {
-        version,
-        values,
-        length,
+impl Display for LengthType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LengthType::Bits => write!(f, "bits"),
+            LengthType::Count => write!(f, "count"),
+        }
     }
 }
 
-fn parse_nested_by_length(bits: &[bool]) -> NestedResult {
-    let length = 15 + parse_number::<usize>(&bits[..15]);
+/// Location of a packet, as the index of each sub-packet on the way down from the outermost one.
+struct PacketPath<'a>(&'a [usize]);
 
-    let mut index = 15;
-    let mut version = 0;
-    let mut values = Vec::with_capacity(2);
+impl Display for PacketPath<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "root")?;
 
-    while index < length {
-        let result = parse(&bits[index..]);
-        index += result.length;
-        version += result.version;
-        values.push(result.value);
+        for index in self.0 {
+            write!(f, ".{}", index)?;
+        }
+
+        Ok(())
     }
+}
 
-    NestedResult {
-        version,
-        values,
-        length,
+/// The value of the packet at the given path doesn't fit into an u64.
+#[derive(Debug)]
+struct Overflow(Vec<usize>);
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Value of the packet at {} overflows",
+            PacketPath(&self.0)
+        )
     }
 }
 
-fn parse_number<N: Default + FromBits>(bits: &[bool]) -> N {
-    parse_number_with_base(N::default(), bits)
+impl std::error::Error for Overflow {}
+
+/// Value of a literal with the given 4-bit groups, most significant first.
+fn literal_value(groups: &[u8]) -> BigUint {
+    groups.iter().fold(BigUint::default(), |acc, &group| {
+        acc << 4 | BigUint::from(group)
+    })
 }
 
-fn parse_number_with_base<N: FromBits>(base: N, bits: &[bool]) -> N {
-    bits.iter().fold(base, |acc, bit| acc.push_bit(*bit))
+fn parse_literal(reader: &mut BitReader) -> anyhow::Result<Vec<u8>> {
+    let mut groups = Vec::new();
+
+    loop {
+        let more = reader.read_bit()?;
+        groups.push(reader.read(4)? as u8);
+
+        if !more {
+            return Ok(groups);
+        }
+    }
 }
 
-fn parse_bits(input: &str) -> anyhow::Result<Vec<bool>> {
-    let mut bits = Vec::with_capacity(input.len() * 4);
+/// Packs the hex digits into bytes, leaving the low half of the last byte empty for odd lengths.
+fn parse_hex(input: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len().div_ceil(2));
 
-    for byte in input.bytes() {
+    for (index, byte) in input.bytes().enumerate() {
         let value = match byte {
             b'0'..=b'9' => byte - b'0',
             b'A'..=b'F' => byte - b'A' + 10,
             _ => return Err(anyhow!("{} is not a hex character", char::from(byte))),
         };
 
-        bits.extend_from_slice(&BITS[value as usize]);
+        if index % 2 == 0 {
+            bytes.push(value << 4);
+        } else {
+            *bytes.last_mut().unwrap() |= value;
+        }
     }
 
-    Ok(bits)
+    Ok(bytes)
 }
 
-trait FromBits {
-    fn push_bit(self, bit: bool) -> Self;
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
 }
 
-impl FromBits for usize {
-    fn push_bit(self, bit: bool) -> Self {
-        self << 1 | (bit as usize)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn at<'a>(packet: &'a Packet, path: &[usize]) -> &'a Packet {
+        match (&packet.body, path.split_first()) {
+            (Body::Operator(_, _, packets), Some((index, rest))) => at(&packets[*index], rest),
+            _ => packet,
+        }
+    }
+
+    fn literal(value: u8) -> Packet {
+        Packet {
+            version: 0,
+            body: Body::Literal(vec![value]),
+        }
+    }
+
+    fn packet() -> impl Strategy<Value = Packet> {
+        // literals with more than 16 groups don't fit an u64
+        let literal =
+            (0..8u8, prop::collection::vec(0..16u8, 1..=18)).prop_map(|(version, groups)| Packet {
+                version,
+                body: Body::Literal(groups),
+            });
+
+        literal.prop_recursive(3, 24, 3, |inner| {
+            let operator =
+                (0..8u8).prop_filter_map("not an operator", |id| Operator::try_from(id).ok());
+            let length_type = prop_oneof![Just(LengthType::Bits), Just(LengthType::Count)];
+
+            (
+                0..8u8,
+                operator,
+                length_type,
+                prop::collection::vec(inner, 2..=3),
+            )
+                .prop_map(|(version, operator, length_type, mut packets)| {
+                    if operator.is_comparison() {
+                        packets.truncate(2);
+                    }
+
+                    Packet {
+                        version,
+                        body: Body::Operator(operator, length_type, packets),
+                    }
+                })
+        })
+    }
+
+    #[test]
+    fn formats_expressions() {
+        let packet = Packet::decode("9C0141080250320F1802104A08").unwrap();
+
+        assert_eq!(
+            packet.to_string(),
+            "(eq (sum (lit 1) (lit 3)) (product (lit 2) (lit 2)))"
+        );
+
+        assert_eq!(
+            format!("{:#}", packet),
+            "(eq@4/bits\n  (sum@2/count\n    (lit@2 1)\n    (lit@4 3))\n  (product@6/count\n    (lit@0 2)\n    (lit@2 2)))"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_transmissions() {
+        assert!(Packet::decode("D2FE28").is_ok());
+        assert!(Packet::decode("D2FE29").is_err());
+
+        let comparison = Packet {
+            version: 0,
+            body: Body::Operator(Operator::LessThan, LengthType::Count, vec![literal(1); 3]),
+        };
+
+        let empty = Packet {
+            version: 0,
+            body: Body::Operator(Operator::Minimum, LengthType::Bits, vec![]),
+        };
+
+        assert!(comparison.validate().is_err());
+        assert!(Packet::decode(&comparison.encode().unwrap())
+            .unwrap()
+            .validate()
+            .is_err());
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_lengths_that_dont_fit() {
+        let operator = |length_type, packets| Packet {
+            version: 0,
+            body: Body::Operator(Operator::Sum, length_type, packets),
+        };
+
+        // 11 bits per literal, so 2978 of them are one bit too many for the bits length type
+        assert!(operator(LengthType::Bits, vec![literal(1); 2978])
+            .encode()
+            .is_ok());
+        assert!(operator(LengthType::Bits, vec![literal(1); 2979])
+            .encode()
+            .is_err());
+
+        assert!(operator(LengthType::Count, vec![literal(1); 2047])
+            .encode()
+            .is_ok());
+        assert!(operator(LengthType::Count, vec![literal(1); 2048])
+            .encode()
+            .is_err());
+
+        let nested = operator(LengthType::Count, vec![literal(1); 2048]);
+        let error = operator(LengthType::Bits, vec![literal(1), nested])
+            .encode()
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "sum at root.1 has a count length of 2048, which doesn't fit into 11 bits"
+        );
+    }
+
+    #[test]
+    fn sums_versions_of_operators_without_values() {
+        let empty = Packet {
+            version: 5,
+            body: Body::Operator(Operator::Minimum, LengthType::Bits, vec![]),
+        };
+
+        let encoded = empty.encode().unwrap();
+        assert_eq!(part_a(&[&encoded]).unwrap().to_string(), "5");
+        assert!(part_b(&[&encoded]).is_err());
+    }
+
+    #[test]
+    fn handles_deeply_nested_transmissions() {
+        let depth = 200_000;
+        let mut writer = BitWriter::new();
+
+        // sums with a single sub-packet each, around a literal 1, all of version 1
+        for _ in 0..depth {
+            writer.write(1, 3);
+            writer.write(u8::from(Operator::Sum) as u64, 3);
+            writer.write_bit(true);
+            writer.write(1, COUNT_WIDTH);
+        }
+
+        writer.write(1, 3);
+        writer.write(LITERAL_TYPE_ID as u64, 3);
+        writer.write(1, 5);
+
+        let hex = to_hex(&writer.into_bytes());
+        let packet = Packet::decode(&hex).unwrap();
+
+        assert_eq!(packet.version_sum(), depth + 1);
+        assert!(packet.validate().is_ok());
+        assert_eq!(packet.checked_value().unwrap(), 1);
+        assert_eq!(packet.big_value(), BigUint::from(1u8));
+        assert_eq!(
+            packet.to_string(),
+            format!("{}(lit 1){}", "(sum ".repeat(depth), ")".repeat(depth))
+        );
+        assert_eq!(packet.encode().unwrap(), hex);
     }
-}
 
-impl FromBits for u64 {
-    fn push_bit(self, bit: bool) -> Self {
-        self << 1 | (bit as u64)
+    proptest! {
+        #[test]
+        fn hex_round_trips(hex in "([0-9A-F]{2}){1,32}") {
+            prop_assert_eq!(to_hex(&parse_hex(&hex).unwrap()), hex);
+        }
+
+        #[test]
+        fn packet_round_trips(packet in packet()) {
+            let encoded = packet.encode().unwrap();
+            let decoded = Packet::decode(&encoded).unwrap();
+
+            prop_assert_eq!(&decoded, &packet);
+            prop_assert_eq!(decoded.encode().unwrap(), encoded);
+        }
+
+        #[test]
+        fn checked_value_matches_big_value(packet in packet()) {
+            match packet.checked_value() {
+                Ok(value) => prop_assert_eq!(packet.big_value(), BigUint::from(value)),
+                Err(Overflow(path)) => {
+                    prop_assert!(at(&packet, &path).big_value() > BigUint::from(u64::MAX))
+                }
+            }
+        }
+
+        #[test]
+        fn literal_value_from_groups(value in any::<u128>()) {
+            let groups = (0..32).rev().map(|i| (value >> (i * 4) & 0xF) as u8).collect::<Vec<_>>();
+            prop_assert_eq!(literal_value(&groups).to_string(), value.to_string());
+        }
     }
 }
@@ -19,7 +19,7 @@ pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
     let mut max_magnitude = 0;
 
     for (i, x) in numbers[..numbers.len() - 1].iter().enumerate() {
-        for y in numbers[i..].iter() {
+        for y in numbers[i + 1..].iter() {
             max_magnitude = max_magnitude
                 .max((x + y).magnitude())
                 .max((y + x).magnitude());
@@ -181,3 +181,69 @@ impl Add for &Number {
         number
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Generates a reduced number, whose outermost pair sits inside `depth` other pairs.
+    fn pair(depth: u8) -> BoxedStrategy<String> {
+        (element(depth + 1), element(depth + 1))
+            .prop_map(|(left, right)| format!("[{},{}]", left, right))
+            .boxed()
+    }
+
+    fn element(depth: u8) -> BoxedStrategy<String> {
+        let regular = (0..10u8).prop_map(|value| value.to_string());
+
+        if depth == 4 {
+            regular.boxed()
+        } else {
+            prop_oneof![regular, pair(depth)].boxed()
+        }
+    }
+
+    fn is_reduced(number: &Number) -> bool {
+        number
+            .0
+            .iter()
+            .all(|digit| digit.depth() <= 4 && digit.value() <= 9)
+    }
+
+    #[test]
+    fn largest_magnitude_adds_different_numbers() {
+        // adding the first number to itself would give the larger magnitude of 225
+        let input = ["[9,9]", "[1,1]"];
+        assert_eq!(part_b(&input).unwrap().to_string(), "145");
+    }
+
+    proptest! {
+        #[test]
+        fn addition_is_reduced(a in pair(0), b in pair(0)) {
+            let sum = &Number::parse(&a) + &Number::parse(&b);
+            prop_assert!(is_reduced(&sum));
+        }
+
+        #[test]
+        fn addition_matches_reference(a in pair(0), b in pair(0)) {
+            let expected = crate::reference::day_18::part_a(&[&a, &b]).unwrap().to_string();
+            let sum = &Number::parse(&a) + &Number::parse(&b);
+            prop_assert_eq!(sum.magnitude().to_string(), expected);
+        }
+
+        #[test]
+        fn magnitude_of_unreduced_sum(a in pair(1), b in pair(1)) {
+            // both sides are shallow enough for the sum to need no reduction at all
+            let a = Number::parse(&a);
+            let b = Number::parse(&b);
+            prop_assert_eq!((&a + &b).magnitude(), 3 * a.magnitude() + 2 * b.magnitude());
+        }
+
+        #[test]
+        fn magnitude_of_regular_pair(left in 0..10usize, right in 0..10usize) {
+            let number = Number::parse(&format!("[{},{}]", left, right));
+            prop_assert_eq!(number.magnitude(), 3 * left + 2 * right);
+        }
+    }
+}
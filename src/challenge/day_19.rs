@@ -1,9 +1,15 @@
-use std::collections::hash_map::Entry;
+use anyhow::{anyhow, Context};
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
-use std::ops::{Add, Mul, Sub};
+use std::iter::StepBy;
+use std::num::NonZeroUsize;
+use std::ops::{Add, Mul, Range, Sub};
 
 const MIN_BEACONS: usize = 12;
-const MIN_FINGERPRINTS: usize = 66; // 12! / (2! * 10!)
+
+/// Matching pairs kept for each pair of reports to guess transformations from, which are mostly
+/// genuine once the reports overlap.
+const SAMPLED_MATCHES: usize = 256;
 
 const ROTATIONS: [Rotation; 24] = [
     Rotation::new([1, 0, 0, 0, 1, 0, 0, 0, 1]),
@@ -33,131 +39,893 @@ const ROTATIONS: [Rotation; 24] = [
 ];
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(solve(input).0)
+    Ok(solve(input, &Settings::default())?.beacons.len())
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let scanners = solve(input).1;
+    Ok(solve(input, &Settings::default())?.max_distance())
+}
+
+/// Assembles the map with `[min_beacons] [tolerance]` rather than the puzzle's 12 exactly matching
+/// beacons, for reports that overlap less or were measured with some noise.
+///
+/// Beacons measured within ±n of their true position need a tolerance of about 2n + 1, the extra
+/// one covering the rounding of the scanner's position.
+pub fn align(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let settings = Settings::parse(args)?;
+    let map = solve(input, &settings)?;
 
-    let max_distance = pairs(&scanners)
-        .map(|(a, b)| (a.x() - b.x()).abs() + (a.y() - b.y()).abs() + (a.z() - b.z()).abs())
-        .max()
-        .unwrap();
+    println!("beacons: {}", map.beacons.len());
+    println!("largest distance: {}", map.max_distance());
 
-    Ok(max_distance)
+    for (index, scanner) in map.scanners.iter().enumerate() {
+        let Point(x, y, z) = scanner.position;
+        print!("scanner {}: {},{},{}", index, x, y, z);
+
+        match scanner.aligned_to {
+            Some(aligned_to) => println!(" (aligned to scanner {})", aligned_to),
+            None => println!(),
+        }
+    }
+
+    Ok(())
 }
 
-fn solve(input: &[&str]) -> (usize, Vec<Point>) {
-    let reports = parse(input);
-    let mut beacons = reports[0].beacons().iter().cloned().collect::<HashSet<_>>();
+/// Prints the assembled map as `json`, or as a `ply` or `obj` point cloud for 3D viewers.
+///
+/// Takes the same `[min_beacons] [tolerance]` as `align` after the format.
+pub fn export(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let settings = Settings::parse(args.get(1..).unwrap_or_default())?;
+    let map = solve(input, &settings)?;
 
-    let mut scanners = Vec::with_capacity(reports.len());
-    scanners.push(Point::new(0, 0, 0));
+    match args.first().map(String::as_str) {
+        Some("json") => println!("{}", map.to_json()),
+        Some("ply") => print!("{}", map.to_ply()),
+        Some("obj") => print!("{}", map.to_obj()),
+        _ => {
+            return Err(anyhow!(
+                "Expecting `tool 19 export <json|ply|obj> [min_beacons] [tolerance]`"
+            ))
+        }
+    }
 
-    let mut matched = reports[0]
-        .fingerprint()
-        .map(|(fingerprint, beacons)| (fingerprint, (beacons.0.clone(), beacons.1.clone())))
-        .collect::<HashMap<_, _>>();
+    Ok(())
+}
 
-    let mut unmatched = reports[1..]
-        .iter()
-        .map(|report| (report, report.fingerprint().collect::<Vec<_>>()))
-        .collect::<Vec<_>>();
+/// How closely two reports have to agree before their scanners are aligned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Settings {
+    /// Beacons two scanners need to have in common.
+    min_beacons: usize,
+    /// Largest difference along any axis between two sightings of the same beacon, which is 0 for
+    /// the puzzle's exact reports.
+    tolerance: i16,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            min_beacons: MIN_BEACONS,
+            tolerance: 0,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads `[min_beacons] [tolerance]`, falling back to the defaults for missing values.
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut settings = Settings::default();
+
+        if let Some(min_beacons) = args.first() {
+            settings.min_beacons = min_beacons
+                .parse()
+                .with_context(|| format!("Invalid beacon count {}", min_beacons))?;
+        }
+
+        if let Some(tolerance) = args.get(1) {
+            settings.tolerance = tolerance
+                .parse()
+                .with_context(|| format!("Invalid tolerance {}", tolerance))?;
+        }
+
+        // two beacons can't tell apart rotations around the axis through them
+        if settings.min_beacons < 3 {
+            return Err(anyhow!("Scanners need at least 3 beacons in common"));
+        }
+
+        if settings.tolerance < 0 {
+            return Err(anyhow!("The tolerance can't be negative"));
+        }
+
+        Ok(settings)
+    }
+
+    /// Pairs of beacons two scanners have in common when they share `min_beacons` beacons, which is
+    /// 66 for 12 beacons.
+    fn min_fingerprints(&self) -> usize {
+        self.min_beacons * (self.min_beacons - 1) / 2
+    }
+}
+
+/// Scanners and beacons, in the coordinates of the first scanner.
+struct Map {
+    /// Scanners in the order of their reports.
+    scanners: Vec<Scanner>,
+    beacons: HashSet<Point>,
+}
+
+/// Where a scanner ended up, and how its report was turned to line up with the others.
+struct Scanner {
+    position: Point,
+    /// Index into `ROTATIONS` of the rotation applied to the report.
+    rotation: usize,
+    /// Scanner whose beacons confirmed the alignment, which is `None` for the first scanner.
+    aligned_to: Option<usize>,
+}
+
+impl Map {
+    fn max_distance(&self) -> i16 {
+        pairs(&self.scanners)
+            .map(|(a, b)| (&a.position, &b.position))
+            .map(|(a, b)| (a.x() - b.x()).abs() + (a.y() - b.y()).abs() + (a.z() - b.z()).abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn sorted_beacons(&self) -> Vec<&Point> {
+        let mut beacons = self.beacons.iter().collect::<Vec<_>>();
+        beacons.sort_by_key(|beacon| (beacon.x(), beacon.y(), beacon.z()));
+        beacons
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let point = |point: &Point| serde_json::json!([point.x(), point.y(), point.z()]);
+
+        let scanners = self
+            .scanners
+            .iter()
+            .enumerate()
+            .map(|(index, scanner)| {
+                serde_json::json!({
+                    "id": index,
+                    "position": point(&scanner.position),
+                    "rotation": ROTATIONS[scanner.rotation].0.chunks(3).collect::<Vec<_>>(),
+                    "aligned_to": scanner.aligned_to,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let beacons = self
+            .sorted_beacons()
+            .into_iter()
+            .map(point)
+            .collect::<Vec<_>>();
+
+        serde_json::json!({ "scanners": scanners, "beacons": beacons })
+    }
+
+    /// Scanners are coloured red, beacons white.
+    fn to_ply(&self) -> String {
+        let mut ply = String::new();
+        let beacons = self.sorted_beacons();
+
+        ply.push_str("ply\nformat ascii 1.0\n");
+        ply.push_str("comment scanners are red, beacons are white\n");
+        ply.push_str(&format!(
+            "element vertex {}\n",
+            self.scanners.len() + beacons.len()
+        ));
+        ply.push_str("property int x\nproperty int y\nproperty int z\n");
+        ply.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\n");
+        ply.push_str("end_header\n");
+
+        for scanner in &self.scanners {
+            let Point(x, y, z) = scanner.position;
+            ply.push_str(&format!("{} {} {} 255 0 0\n", x, y, z));
+        }
+
+        for Point(x, y, z) in beacons {
+            ply.push_str(&format!("{} {} {} 255 255 255\n", x, y, z));
+        }
+
+        ply
+    }
+
+    /// Scanners and beacons are separate objects made up of points.
+    fn to_obj(&self) -> String {
+        let mut obj = String::new();
+        let beacons = self.sorted_beacons();
+
+        obj.push_str("o scanners\n");
+
+        for scanner in &self.scanners {
+            let Point(x, y, z) = scanner.position;
+            obj.push_str(&format!("v {} {} {}\n", x, y, z));
+        }
+
+        obj.push_str(&format!("p {}\n", vertices(1, self.scanners.len())));
+        obj.push_str("o beacons\n");
+
+        for Point(x, y, z) in &beacons {
+            obj.push_str(&format!("v {} {} {}\n", x, y, z));
+        }
+
+        obj.push_str(&format!(
+            "p {}\n",
+            vertices(self.scanners.len() + 1, beacons.len())
+        ));
+
+        obj
+    }
+}
+
+/// Lists `count` vertex numbers of an OBJ file, which start at 1.
+fn vertices(start: usize, count: usize) -> String {
+    (start..start + count)
+        .map(|vertex| vertex.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Beacons bucketed into cells, so that sightings within the tolerance of each other are found
+/// without scanning all of them.
+struct Cloud {
+    tolerance: i16,
+    cells: HashMap<Point, Vec<Point>>,
+}
+
+impl Cloud {
+    fn new(tolerance: i16) -> Self {
+        Cloud {
+            tolerance,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, point: &Point) -> Point {
+        let size = 2 * self.tolerance + 1;
+        Point::new(
+            point.x().div_euclid(size),
+            point.y().div_euclid(size),
+            point.z().div_euclid(size),
+        )
+    }
+
+    /// Finds a beacon that could be another sighting of `point`.
+    fn find(&self, point: &Point) -> Option<&Point> {
+        // cells are wider than the tolerance, so a match is at most one cell away
+        let reach = self.tolerance.min(1);
+        let Point(x, y, z) = self.cell(point);
+
+        (-reach..=reach)
+            .flat_map(|dx| (-reach..=reach).map(move |dy| (dx, dy)))
+            .flat_map(|(dx, dy)| (-reach..=reach).map(move |dz| Point(x + dx, y + dy, z + dz)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .find(|beacon| beacon.within(point, self.tolerance))
+    }
+
+    /// Adds the beacon unless it has been seen before.
+    fn insert(&mut self, beacon: Point) {
+        if self.find(&beacon).is_none() {
+            self.cells
+                .entry(self.cell(&beacon))
+                .or_default()
+                .push(beacon);
+        }
+    }
+
+    fn into_beacons(self) -> HashSet<Point> {
+        self.cells.into_values().flatten().collect()
+    }
+}
+
+/// Pairs of beacons of all reports, sorted by their fingerprint so that pairs whose fingerprints
+/// differ by the tolerance end up in the same or a neighbouring bucket.
+///
+/// Large inputs have tens of millions of pairs, so each is packed into an integer holding its
+/// bucket, report and beacons, in that order, which sorts several times faster than a struct.
+struct Catalogue<'a> {
+    reports: &'a [Report],
+    tolerance: i16,
+    entries: Vec<u128>,
+    /// Range of entries of each bucket, in the order of the entries.
+    buckets: Vec<(Fingerprint, Range<usize>)>,
+}
+
+#[derive(Clone, Copy)]
+struct CatalogueEntry {
+    fingerprint: Fingerprint,
+    report: u32,
+    beacons: (u16, u16),
+}
 
-    while !unmatched.is_empty() {
-        let mut still_unmatched = Vec::with_capacity(unmatched.len());
+/// Two beacons of the same report.
+type Pair<'a> = (&'a Point, &'a Point);
 
-        for (report, fingerprints) in unmatched {
-            let result = solve_report(report, &fingerprints, &beacons, &matched);
+/// Matching pairs of beacons of two reports, as indices into the earlier and the later report.
+type Match = ((u16, u16), (u16, u16));
+
+/// Number of pairs of beacons every two reports have in common, by the indices of the earlier and
+/// the later report.
+///
+/// Counting matches runs through every bucket, so the counts are kept densely rather than in a
+/// hash map, which takes 640 KB per core for 400 scanners.
+struct Tally {
+    reports: usize,
+    counts: Vec<u32>,
+}
+
+impl<'a> Catalogue<'a> {
+    fn new(reports: &'a [Report], tolerance: i16) -> Self {
+        let beacons = reports.iter().map(|report| report.beacons().len());
+        let pairs = beacons
+            .map(|count| count * count.saturating_sub(1) / 2)
+            .sum();
+        let mut entries = Vec::with_capacity(pairs);
+
+        for (index, report) in reports.iter().enumerate() {
+            let beacons = report.beacons();
+
+            for (a, b) in pairs_of_indices(beacons.len()) {
+                let key = bucket(&beacons[a].fingerprint(&beacons[b]), tolerance);
+                entries.push(pack(&key, index, (a, b)));
+            }
+        }
+
+        entries.sort_unstable();
+
+        // exact fingerprints only collide by chance, one pair per report is plenty
+        if tolerance == 0 {
+            entries.dedup_by_key(|entry| *entry >> 32);
+        }
 
-            if let Some((scanner, report)) = result {
-                for (fingerprint, beacons) in report.fingerprint() {
-                    let entry = match matched.entry(fingerprint) {
-                        Entry::Occupied(_) => continue,
-                        Entry::Vacant(entry) => entry,
-                    };
+        let mut buckets = Vec::<(Fingerprint, Range<usize>)>::new();
 
-                    entry.insert((beacons.0.clone(), beacons.1.clone()));
+        for (index, entry) in entries.iter().enumerate() {
+            let key = unpack(*entry).0;
+
+            match buckets.last_mut() {
+                Some((last, range)) if *last == key => range.end = index + 1,
+                _ => buckets.push((key, index..index + 1)),
+            }
+        }
+
+        Catalogue {
+            reports,
+            tolerance,
+            entries,
+            buckets,
+        }
+    }
+
+    /// Counts the matching pairs of every two reports.
+    fn tally(&self) -> Tally {
+        let reports = self.reports.len();
+
+        let tallies = in_parallel(self.buckets.len(), |buckets| {
+            let mut tally = Tally {
+                reports,
+                counts: vec![0; reports * reports],
+            };
+
+            self.matches(buckets, |earlier, later| {
+                tally.counts[earlier.report as usize * reports + later.report as usize] += 1;
+            });
+
+            tally
+        });
+
+        tallies
+            .into_iter()
+            .reduce(|mut total, tally| {
+                for (count, other) in total.counts.iter_mut().zip(tally.counts) {
+                    *count += other;
                 }
 
-                beacons.extend(report.into_beacons());
-                scanners.push(scanner);
-            } else {
-                still_unmatched.push((report, fingerprints));
+                total
+            })
+            .unwrap()
+    }
+
+    /// Collects the first matches of each candidate, from which transformations are guessed.
+    fn sample(&self, candidates: &mut [Candidate]) {
+        let reports = self.reports.len();
+        let mut slots = vec![u32::MAX; reports * reports];
+
+        for (index, candidate) in candidates.iter().enumerate() {
+            slots[candidate.earlier * reports + candidate.later] = index as u32;
+        }
+
+        let samples = in_parallel(self.buckets.len(), |buckets| {
+            let mut samples = vec![Vec::new(); candidates.len()];
+            let mut slots = slots.clone();
+
+            self.matches(buckets, |earlier, later| {
+                let slot = &mut slots[earlier.report as usize * reports + later.report as usize];
+
+                if let Some(sample) = samples.get_mut(*slot as usize) {
+                    sample.push((earlier.beacons, later.beacons));
+
+                    // most matches belong to full samples, which this skips without looking them up
+                    if sample.len() == SAMPLED_MATCHES {
+                        *slot = u32::MAX;
+                    }
+                }
+            });
+
+            samples
+        });
+
+        for samples in samples {
+            for (candidate, sample) in candidates.iter_mut().zip(samples) {
+                let room = SAMPLED_MATCHES - candidate.sample.len();
+                candidate.sample.extend(sample.into_iter().take(room));
+            }
+        }
+    }
+
+    /// Calls `visit` with the pairs of every two reports that match, the earlier report's first,
+    /// comparing the pairs in each of the buckets with those in the same and the following
+    /// neighbouring buckets.
+    fn matches(
+        &self,
+        buckets: impl Iterator<Item = usize>,
+        mut visit: impl FnMut(&CatalogueEntry, &CatalogueEntry),
+    ) {
+        let slack = 2 * self.tolerance;
+        let reach = self.tolerance.min(1);
+
+        // only buckets that come later, so that every two neighbours are compared once
+        let neighbours = (-reach..=reach)
+            .flat_map(|a| (-reach..=reach).map(move |b| (a, b)))
+            .flat_map(|(a, b)| (-reach..=reach).map(move |c| [a, b, c]))
+            .filter(|offset| *offset > [0, 0, 0])
+            .collect::<Vec<_>>();
+
+        let mut compare = |a: &CatalogueEntry, b: &CatalogueEntry| {
+            if a.report != b.report
+                && (0..3).all(|i| (a.fingerprint[i] - b.fingerprint[i]).abs() <= slack)
+            {
+                match a.report < b.report {
+                    true => visit(a, b),
+                    false => visit(b, a),
+                }
+            }
+        };
+
+        let mut entries = Vec::new();
+        let mut others = Vec::new();
+
+        for bucket in buckets {
+            let (key, range) = &self.buckets[bucket];
+            entries.clear();
+            entries.extend(
+                self.entries[range.clone()]
+                    .iter()
+                    .map(|entry| self.entry(*entry)),
+            );
+
+            for (i, a) in entries.iter().enumerate() {
+                for b in &entries[i + 1..] {
+                    compare(a, b);
+                }
+            }
+
+            for offset in &neighbours {
+                let neighbour = [0, 1, 2].map(|i| key[i] + offset[i]);
+
+                if let Ok(neighbour) = self.buckets.binary_search_by_key(&neighbour, |b| b.0) {
+                    let range = self.buckets[neighbour].1.clone();
+                    others.clear();
+                    others.extend(self.entries[range].iter().map(|entry| self.entry(*entry)));
+
+                    for a in &entries {
+                        for b in &others {
+                            compare(a, b);
+                        }
+                    }
+                }
             }
         }
+    }
+
+    fn entry(&self, entry: u128) -> CatalogueEntry {
+        let (key, report, (a, b)) = unpack(entry);
+
+        // buckets only hold the fingerprint itself when there's no tolerance
+        let fingerprint = match self.tolerance {
+            0 => key,
+            _ => self.reports[report].fingerprint(a, b),
+        };
+
+        CatalogueEntry {
+            fingerprint,
+            report: report as u32,
+            beacons: (a as u16, b as u16),
+        }
+    }
+}
 
-        unmatched = still_unmatched;
+/// Two sightings of a pair differ by up to twice the tolerance along each axis.
+fn bucket(fingerprint: &Fingerprint, tolerance: i16) -> Fingerprint {
+    if tolerance == 0 {
+        *fingerprint
+    } else {
+        fingerprint.map(|distance| distance / (2 * tolerance + 1))
     }
+}
 
-    (beacons.len(), scanners)
+/// Packs a pair of beacons into a catalogue entry, which works as distances are never negative.
+fn pack(key: &Fingerprint, report: usize, (a, b): (usize, usize)) -> u128 {
+    let key = key
+        .iter()
+        .fold(0, |packed, &distance| packed << 16 | distance as u128);
+    key << 64 | (report as u128) << 32 | (a as u128) << 16 | b as u128
 }
 
-fn solve_report(
-    report: &Report,
-    fingerprints: &[(Fingerprint, (&Point, &Point))],
-    beacons: &HashSet<Point>,
-    matched: &HashMap<Fingerprint, (Point, Point)>,
-) -> Option<(Point, Report)> {
-    let pairs = fingerprints
+fn unpack(entry: u128) -> (Fingerprint, usize, (usize, usize)) {
+    let key = [2, 1, 0].map(|i| (entry >> (64 + 16 * i)) as u16 as i16);
+    let report = (entry >> 32) as u32 as usize;
+    let beacons = ((entry >> 16) as u16 as usize, entry as u16 as usize);
+    (key, report, beacons)
+}
+
+impl Tally {
+    fn count(&self, earlier: usize, later: usize) -> usize {
+        self.counts[earlier * self.reports + later] as usize
+    }
+}
+
+/// Rotation and translation that turn beacons of one report into the coordinates of another.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct Transform {
+    /// Index into `ROTATIONS`.
+    rotation: usize,
+    translation: Point,
+}
+
+impl Transform {
+    fn identity() -> Self {
+        Transform {
+            rotation: 0,
+            translation: Point::new(0, 0, 0),
+        }
+    }
+
+    fn apply(&self, point: &Point) -> Point {
+        &(&ROTATIONS[self.rotation] * point) + &self.translation
+    }
+
+    /// Transform that applies `inner` first, and then this one.
+    fn then(&self, inner: &Transform) -> Self {
+        Transform {
+            rotation: rotation_index(&(&ROTATIONS[self.rotation] * &ROTATIONS[inner.rotation])),
+            translation: self.apply(&inner.translation),
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        let rotation = ROTATIONS[self.rotation].transpose();
+        let translation = &Point::new(0, 0, 0) - &(&rotation * &self.translation);
+
+        Transform {
+            rotation: rotation_index(&rotation),
+            translation,
+        }
+    }
+}
+
+/// Reports that share enough fingerprints to be worth aligning.
+struct Candidate {
+    earlier: usize,
+    later: usize,
+    count: usize,
+    /// The first matches, from which transformations are guessed.
+    sample: Vec<Match>,
+}
+
+impl Candidate {
+    /// Finds the transformation that turns beacons of the later report into the coordinates of
+    /// the earlier one.
+    fn align(
+        &self,
+        reports: &[Report],
+        clouds: &[Cloud],
+        settings: &Settings,
+    ) -> Option<Transform> {
+        let (known, observed) = (
+            reports[self.earlier].beacons(),
+            reports[self.later].beacons(),
+        );
+
+        let pairs = self
+            .sample
+            .iter()
+            .map(|((a, b), (c, d))| {
+                let known = (&known[*a as usize], &known[*b as usize]);
+                let observed = (&observed[*c as usize], &observed[*d as usize]);
+                (observed, known)
+            })
+            .collect::<Vec<_>>();
+
+        align_pair(
+            &reports[self.later],
+            &pairs,
+            &clouds[self.earlier],
+            settings,
+        )
+    }
+}
+
+/// Finds the pairs of reports worth aligning once, then places the reports breadth first from
+/// scanner 0, which keeps the work proportional to the number of pairs of beacons rather than
+/// retrying unaligned reports against the growing map.
+fn solve(input: &[&str], settings: &Settings) -> anyhow::Result<Map> {
+    let reports = parse(input);
+
+    let candidates = {
+        let _span = tracing::info_span!("overlaps").entered();
+        find_candidates(&reports, settings)
+    };
+
+    let transforms = {
+        let _span = tracing::info_span!("align").entered();
+        place(&reports, &candidates, settings)
+    };
+
+    let unaligned = (0..reports.len())
+        .filter(|&index| transforms[index].is_none())
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>();
+
+    if !unaligned.is_empty() {
+        return Err(anyhow!(
+            "Scanners {} don't share {} beacons with any scanner aligned to scanner 0",
+            unaligned.join(", "),
+            settings.min_beacons
+        ));
+    }
+
+    let _span = tracing::info_span!("assemble").entered();
+    let mut beacons = Cloud::new(settings.tolerance);
+    let mut scanners = Vec::with_capacity(reports.len());
+
+    for (report, transform) in reports.iter().zip(transforms) {
+        let (transform, aligned_to) = transform.unwrap();
+
+        for beacon in report.beacons() {
+            beacons.insert(transform.apply(beacon));
+        }
+
+        scanners.push(Scanner {
+            position: transform.translation,
+            rotation: transform.rotation,
+            aligned_to,
+        });
+    }
+
+    let beacons = beacons.into_beacons();
+    Ok(Map { scanners, beacons })
+}
+
+/// Finds every pair of reports that has the matching pairs of beacons `min_beacons` common beacons
+/// would give, spreading the work over all available cores.
+fn find_candidates(reports: &[Report], settings: &Settings) -> Vec<Candidate> {
+    let catalogue = Catalogue::new(reports, settings.tolerance);
+    let tally = catalogue.tally();
+
+    let mut candidates = (0..reports.len())
+        .flat_map(|earlier| (earlier + 1..reports.len()).map(move |later| (earlier, later)))
+        .filter(|&(earlier, later)| tally.count(earlier, later) >= settings.min_fingerprints())
+        .map(|(earlier, later)| Candidate {
+            earlier,
+            later,
+            count: tally.count(earlier, later),
+            sample: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    catalogue.sample(&mut candidates);
+    candidates
+}
+
+/// Places the reports one layer of the breadth first walk at a time, trying the candidates that
+/// connect each unplaced report to the latest layer, the ones with the most matches first, until
+/// one of them lines up.
+///
+/// Only reports that aren't placed yet are aligned, which mostly takes a single candidate each
+/// rather than every overlapping pair.
+fn place(
+    reports: &[Report],
+    candidates: &[Candidate],
+    settings: &Settings,
+) -> Vec<Option<(Transform, Option<usize>)>> {
+    let mut neighbours = vec![Vec::new(); reports.len()];
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        neighbours[candidate.earlier].push((candidate.later, index));
+        neighbours[candidate.later].push((candidate.earlier, index));
+    }
+
+    let clouds = reports
         .iter()
-        .filter_map(|(fingerprint, observed)| {
-            matched.get(fingerprint).map(|known| (observed, known))
+        .map(|report| {
+            let mut cloud = Cloud::new(settings.tolerance);
+
+            for beacon in report.beacons() {
+                cloud.insert(beacon.clone());
+            }
+
+            cloud
         })
         .collect::<Vec<_>>();
 
-    if pairs.len() < MIN_FINGERPRINTS {
-        return None;
+    let mut transforms = (0..reports.len()).map(|_| None).collect::<Vec<_>>();
+    transforms[0] = Some((Transform::identity(), None));
+    let mut layer = vec![0];
+
+    while !layer.is_empty() {
+        let mut attempts = layer
+            .iter()
+            .flat_map(|&placed| &neighbours[placed])
+            .filter(|(report, _)| transforms[*report].is_none())
+            .map(|&(report, candidate)| (report, Reverse(candidates[candidate].count), candidate))
+            .collect::<Vec<_>>();
+
+        attempts.sort_unstable();
+        let attempts = attempts.chunk_by(|a, b| a.0 == b.0).collect::<Vec<_>>();
+
+        let aligned = in_parallel(attempts.len(), |attempts_of_worker| {
+            attempts_of_worker
+                .filter_map(|index| {
+                    attempts[index].iter().find_map(|&(report, _, candidate)| {
+                        let candidate = &candidates[candidate];
+                        let transform = candidate.align(reports, &clouds, settings)?;
+
+                        match report == candidate.later {
+                            true => Some((report, candidate.earlier, transform)),
+                            false => Some((report, candidate.later, transform.inverse())),
+                        }
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut aligned = aligned.into_iter().flatten().collect::<Vec<_>>();
+        aligned.sort_unstable_by_key(|(report, _, _)| *report);
+        layer.clear();
+
+        for (report, other, local) in aligned {
+            let (transform, _) = transforms[other].clone().unwrap();
+            transforms[report] = Some((transform.then(&local), Some(other)));
+            layer.push(report);
+        }
     }
 
-    let mut previous = Option::None;
+    transforms
+}
+
+/// Calls `work` on every available core, each with its share of the indices below `count`, and
+/// returns the results in the order of the workers.
+fn in_parallel<T: Send>(count: usize, work: impl Fn(StepBy<Range<usize>>) -> T + Sync) -> Vec<T> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(count.max(1));
+
+    std::thread::scope(|scope| {
+        let work = &work;
+
+        let workers = (0..workers)
+            .map(|worker| scope.spawn(move || work((worker..count).step_by(workers))))
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect()
+    })
+}
+
+/// Aligns a report RANSAC-style: every pair of beacons matching a known pair suggests a rotation
+/// and translation, which is kept once enough beacons agree with it.
+fn align_pair(
+    report: &Report,
+    pairs: &[(Pair, Pair)],
+    beacons: &Cloud,
+    settings: &Settings,
+) -> Option<Transform> {
+    // colliding fingerprints can suggest a wrong transformation, which shouldn't stop us from
+    // trying the correct rotation with a different translation later on
+    let mut attempted = HashSet::new();
+    let slack = 2 * settings.tolerance;
 
     let mut transformations = pairs
         .iter()
-        .filter_map(|(observed, known)| find_rotation(observed, known));
+        .flat_map(|(observed, known)| find_rotations(observed, known, slack));
 
-    transformations.find_map(|(rotation, translation)| {
-        if matches!(previous, Some(previous) if std::ptr::eq(rotation, previous)) {
+    transformations.find_map(|mut transform| {
+        if !attempted.insert(transform.clone()) {
             return None;
         }
 
-        let report = report.transform(rotation, &translation);
+        let mut residuals = residuals(report, &transform, beacons);
 
-        let count = report
-            .beacons()
-            .iter()
-            .filter(|beacon| beacons.contains(beacon))
-            .count();
-
-        if count < MIN_BEACONS {
-            previous = Some(rotation);
-            None
-        } else {
-            let scanner = &(rotation * &Point::new(0, 0, 0)) + &translation;
-            Some((scanner, report))
+        // the translation of two noisy beacons is only a rough guess, the one that fits all agreeing
+        // beacons best in the least squares sense moves them by their average residual
+        if settings.tolerance > 0 && residuals.len() >= settings.min_beacons {
+            transform.translation = &transform.translation + &mean(&residuals);
+            residuals = self::residuals(report, &transform, beacons);
         }
+
+        (residuals.len() >= settings.min_beacons).then_some(transform)
     })
 }
 
-fn find_rotation(
-    observed: &(&Point, &Point),
-    known: &(Point, Point),
-) -> Option<(&'static Rotation, Point)> {
-    ROTATIONS
+/// Offsets from the transformed beacons to the beacons they were matched with.
+fn residuals(report: &Report, transform: &Transform, beacons: &Cloud) -> Vec<Point> {
+    report
+        .beacons()
         .iter()
-        .find(|&rotation| {
-            &known.0 - &(rotation * observed.0) == &known.1 - &(rotation * observed.1)
+        .filter_map(|beacon| {
+            let beacon = transform.apply(beacon);
+            beacons.find(&beacon).map(|known| known - &beacon)
         })
-        .map(|rotation| (rotation, &known.0 - &(rotation * observed.0)))
+        .collect()
 }
 
-fn pairs<T>(items: &[T]) -> impl Iterator<Item = (&T, &T)> {
-    items
+/// Average of the points, rounded to the nearest integer.
+fn mean(points: &[Point]) -> Point {
+    let count = points.len() as f64;
+    let average = |axis: fn(&Point) -> i16| {
+        (points.iter().map(|point| axis(point) as f64).sum::<f64>() / count).round() as i16
+    };
+
+    Point::new(average(Point::x), average(Point::y), average(Point::z))
+}
+
+/// Finds the transformations that turn the observed pair of beacons into the known pair, give or
+/// take `slack` along each axis.
+fn find_rotations<'a>(
+    observed: &'a Pair,
+    known: &'a Pair,
+    slack: i16,
+) -> impl Iterator<Item = Transform> + 'a {
+    let offset = observed.1 - observed.0;
+    let expected = known.1 - known.0;
+
+    ROTATIONS
         .iter()
         .enumerate()
-        .flat_map(|(i, a)| items[i..].iter().map(move |b| (a, b)))
+        .filter(move |(_, rotation)| (*rotation * &offset).within(&expected, slack))
+        .map(move |(index, rotation)| {
+            let a = known.0 - &(rotation * observed.0);
+            let b = known.1 - &(rotation * observed.1);
+
+            Transform {
+                rotation: index,
+                translation: a.midpoint(&b),
+            }
+        })
+}
+
+fn rotation_index(rotation: &Rotation) -> usize {
+    ROTATIONS
+        .iter()
+        .position(|other| other == rotation)
+        .unwrap()
 }
 
+/// Every unordered pair of distinct items.
+fn pairs<T>(items: &[T]) -> impl Iterator<Item = (&T, &T)> {
+    pairs_of_indices(items.len()).map(|(a, b)| (&items[a], &items[b]))
+}
+
+fn pairs_of_indices(count: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..count).flat_map(move |a| (a + 1..count).map(move |b| (a, b)))
+}
+
+#[tracing::instrument(name = "parse", skip_all)]
 fn parse(input: &[&str]) -> Vec<Report> {
     input
         .split(|line| line.is_empty())
@@ -177,9 +945,9 @@ fn parse(input: &[&str]) -> Vec<Report> {
         .collect()
 }
 
-type Fingerprint = usize;
+type Fingerprint = [i16; 3];
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 struct Point(i16, i16, i16);
 
 impl Point {
@@ -199,12 +967,33 @@ impl Point {
         self.2
     }
 
+    /// Distances along each axis, sorted so that they don't depend on the scanner's orientation.
     fn fingerprint(&self, other: &Self) -> Fingerprint {
-        let dx = (self.0 - other.0).abs() as usize;
-        let dy = (self.1 - other.1).abs() as usize;
-        let dz = (self.2 - other.2).abs() as usize;
+        let mut distances = [
+            (self.0 - other.0).abs(),
+            (self.1 - other.1).abs(),
+            (self.2 - other.2).abs(),
+        ];
 
-        (dx + dy + dz) | (dx.max(dy).max(dz) << 16)
+        distances.sort_unstable();
+        distances
+    }
+
+    /// Whether the points are at most `tolerance` apart along every axis.
+    fn within(&self, other: &Self, tolerance: i16) -> bool {
+        (self.0 - other.0).abs() <= tolerance
+            && (self.1 - other.1).abs() <= tolerance
+            && (self.2 - other.2).abs() <= tolerance
+    }
+
+    /// Point halfway between the two, rounded down.
+    fn midpoint(&self, other: &Self) -> Self {
+        let half = |a: i16, b: i16| ((a as i32 + b as i32).div_euclid(2)) as i16;
+        Point(
+            half(self.0, other.0),
+            half(self.1, other.1),
+            half(self.2, other.2),
+        )
     }
 }
 
@@ -224,12 +1013,44 @@ impl Sub for &Point {
     }
 }
 
+#[derive(Debug, Eq, PartialEq)]
 struct Rotation([i16; 9]);
 
 impl Rotation {
     const fn new(values: [i16; 9]) -> Self {
         Rotation(values)
     }
+
+    /// The inverse of a rotation.
+    fn transpose(&self) -> Self {
+        let mut values = [0; 9];
+
+        for row in 0..3 {
+            for column in 0..3 {
+                values[column * 3 + row] = self.0[row * 3 + column];
+            }
+        }
+
+        Rotation(values)
+    }
+}
+
+impl Mul for &Rotation {
+    type Output = Rotation;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut values = [0; 9];
+
+        for row in 0..3 {
+            for column in 0..3 {
+                values[row * 3 + column] = (0..3)
+                    .map(|k| self.0[row * 3 + k] * rhs.0[k * 3 + column])
+                    .sum();
+            }
+        }
+
+        Rotation(values)
+    }
 }
 
 impl Mul<&Point> for &Rotation {
@@ -259,21 +1080,180 @@ impl Report {
         &self.0
     }
 
-    fn into_beacons(self) -> Vec<Point> {
-        self.0
+    fn fingerprint(&self, a: usize, b: usize) -> Fingerprint {
+        self.0[a].fingerprint(&self.0[b])
     }
+}
 
-    fn transform(&self, rotation: &Rotation, translation: &Point) -> Self {
-        let beacons = self
-            .0
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::Day;
+    use proptest::prelude::*;
+
+    fn compose(a: &Rotation, b: &Rotation) -> [i16; 9] {
+        let mut values = [0; 9];
+
+        for row in 0..3 {
+            for column in 0..3 {
+                values[row * 3 + column] =
+                    (0..3).map(|k| a.0[row * 3 + k] * b.0[k * 3 + column]).sum();
+            }
+        }
+
+        values
+    }
+
+    fn contains(values: &[i16; 9]) -> bool {
+        ROTATIONS.iter().any(|rotation| &rotation.0 == values)
+    }
+
+    fn squared_distance(a: &Point, b: &Point) -> i32 {
+        let d = a - b;
+        [d.x(), d.y(), d.z()]
             .iter()
-            .map(|beacon| &(rotation * beacon) + translation)
-            .collect::<Vec<_>>();
+            .map(|&v| v as i32 * v as i32)
+            .sum()
+    }
 
-        Report(beacons)
+    fn point() -> impl Strategy<Value = Point> {
+        (-1000..=1000i16, -1000..=1000i16, -1000..=1000i16)
+            .prop_map(|(x, y, z)| Point::new(x, y, z))
+    }
+
+    fn report(index: usize, beacons: impl Iterator<Item = (i16, i16, i16)>) -> String {
+        let lines = beacons.map(|(x, y, z)| format!("{},{},{}", x, y, z));
+        std::iter::once(format!("--- scanner {} ---", index))
+            .chain(lines)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn beacons() -> impl Iterator<Item = (i16, i16, i16)> {
+        (1..=12i16).map(|i| (i * 37 % 101, i * i * 13 % 97, i * 71 % 89 - 40))
+    }
+
+    #[test]
+    fn solve_records_alignment() {
+        let shifted = beacons().map(|(x, y, z)| (x - 500, y + 20, z - 7));
+        let input = [report(0, beacons()), report(1, shifted)].join("\n\n");
+        let map = solve(&input.lines().collect::<Vec<_>>(), &Settings::default()).unwrap();
+
+        assert_eq!(map.beacons.len(), 12);
+        assert_eq!(map.scanners[1].position, Point::new(500, -20, 7));
+        assert_eq!(map.scanners[1].aligned_to, Some(0));
+        assert_eq!(map.scanners[0].aligned_to, None);
+    }
+
+    #[test]
+    fn solve_names_unalignable_scanners() {
+        let lonely = (1..=12i16).map(|i| (i * 7, -i * 11, i * i));
+        let input = [
+            report(0, beacons()),
+            report(1, beacons()),
+            report(2, lonely),
+        ]
+        .join("\n\n");
+        let error = solve(&input.lines().collect::<Vec<_>>(), &Settings::default())
+            .err()
+            .unwrap();
+
+        assert!(error.to_string().starts_with("Scanners 2 don't share"));
+    }
+
+    #[test]
+    fn rotations_are_distinct() {
+        for (i, a) in ROTATIONS.iter().enumerate() {
+            for b in &ROTATIONS[i + 1..] {
+                assert_ne!(a.0, b.0);
+            }
+        }
+    }
+
+    #[test]
+    fn rotations_contain_identity() {
+        assert!(contains(&[1, 0, 0, 0, 1, 0, 0, 0, 1]));
     }
 
-    fn fingerprint(&self) -> impl Iterator<Item = (Fingerprint, (&Point, &Point))> {
-        pairs(&self.0).map(|(a, b)| (a.fingerprint(b), (a, b)))
+    #[test]
+    fn retries_rotation_with_another_translation() {
+        // the first fingerprint shared with scanner 0 belongs to another pair of beacons, which
+        // suggests the right rotation with the wrong translation and used to rule the rotation out
+        let input = crate::generator::generate(Day::Day19, 2, Some(2)).unwrap();
+        let input = input.lines().collect::<Vec<_>>();
+
+        assert_eq!(part_a(&input).unwrap().to_string(), "24");
+        assert_eq!(part_b(&input).unwrap().to_string(), "2548");
+    }
+
+    #[test]
+    fn settings_need_three_beacons() {
+        let args = ["2".to_string()];
+        assert!(Settings::parse(&args).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn solve_tolerates_noise(
+            noise in prop::collection::vec((-1..=1i16, -1..=1i16, -1..=1i16), 12),
+            rotation in 0..24usize,
+        ) {
+            let rotation = &ROTATIONS[rotation];
+            let observed = beacons().zip(noise).map(|((x, y, z), (dx, dy, dz))| {
+                let Point(x, y, z) = rotation * &Point::new(x - 300 + dx, y + dy, z + 40 + dz);
+                (x, y, z)
+            });
+            let false_beacons = (0..3).map(|i| (900, -900, 900 - i * 100));
+
+            let input = [report(0, beacons()), report(1, observed.chain(false_beacons))].join("\n\n");
+            let settings = Settings { min_beacons: 12, tolerance: 3 };
+            let map = solve(&input.lines().collect::<Vec<_>>(), &settings).unwrap();
+
+            prop_assert!(map.scanners[1].position.within(&Point::new(300, 0, -40), 1));
+            prop_assert_eq!(map.beacons.len(), 15);
+        }
+
+        #[test]
+        fn transform_inverse_undoes_it(rotation in 0..24usize, translation in point(), p in point()) {
+            let transform = Transform { rotation, translation };
+            prop_assert_eq!(transform.inverse().apply(&transform.apply(&p)), p);
+        }
+
+        #[test]
+        fn transform_then_composes(
+            a in (0..24usize, point()),
+            b in (0..24usize, point()),
+            p in point(),
+        ) {
+            let a = Transform { rotation: a.0, translation: a.1 };
+            let b = Transform { rotation: b.0, translation: b.1 };
+            prop_assert_eq!(a.then(&b).apply(&p), a.apply(&b.apply(&p)));
+        }
+
+        #[test]
+        fn rotations_are_closed(a in 0..24usize, b in 0..24usize) {
+            prop_assert!(contains(&compose(&ROTATIONS[a], &ROTATIONS[b])));
+        }
+
+        #[test]
+        fn rotations_have_inverses(a in 0..24usize) {
+            let identity = [1, 0, 0, 0, 1, 0, 0, 0, 1];
+            let inverse = ROTATIONS.iter().filter(|b| compose(&ROTATIONS[a], b) == identity);
+            prop_assert_eq!(inverse.count(), 1);
+        }
+
+        #[test]
+        fn rotation_composes_with_mul(a in 0..24usize, b in 0..24usize, p in point()) {
+            let composed = Rotation::new(compose(&ROTATIONS[a], &ROTATIONS[b]));
+            prop_assert_eq!(&composed * &p, &ROTATIONS[a] * &(&ROTATIONS[b] * &p));
+        }
+
+        #[test]
+        fn rotation_preserves_distances(rotation in 0..24usize, a in point(), b in point()) {
+            let rotation = &ROTATIONS[rotation];
+            let (ra, rb) = (rotation * &a, rotation * &b);
+            prop_assert_eq!(squared_distance(&ra, &rb), squared_distance(&a, &b));
+            prop_assert_eq!(ra.fingerprint(&rb), a.fingerprint(&b));
+        }
     }
 }
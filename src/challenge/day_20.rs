@@ -62,14 +62,14 @@ impl Image {
         let lit_value = true;
 
         let mut pixels = Vec::with_capacity(width * height);
-        pixels.extend(std::iter::repeat(false).take(width * 2 - 2));
+        pixels.extend(std::iter::repeat_n(false, width * 2 - 2));
 
         for line in input {
-            pixels.extend(std::iter::repeat(false).take(4));
+            pixels.extend(std::iter::repeat_n(false, 4));
             pixels.extend(line.bytes().map(|byte| byte == b'#'));
         }
 
-        pixels.extend(std::iter::repeat(false).take(width * 2 + 2));
+        pixels.extend(std::iter::repeat_n(false, width * 2 + 2));
 
         Image {
             width,
@@ -1,144 +1,555 @@
+use crate::box_set::{BoxSet, Cuboid};
+use crate::challenge::Day;
+use anyhow::{anyhow, Context};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Ways of keeping track of the cubes that are on.
+const BACKENDS: [Backend; 3] = [Backend::Signed, Backend::Compressed, Backend::Tree];
+
+/// Time a backend gets for each benchmark input.
+const BENCH_BUDGET: Duration = Duration::from_secs(2);
+
+/// Reboot step that switches a cuboid on or off.
+type Step = (bool, Cuboid<3>);
+
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(solve(input, false))
+    Ok(reboot(input, Some(&Cuboid::cube(-50, 50)))?.volume())
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Ok(solve(input, true))
+    Ok(reboot(input, None)?.volume())
+}
+
+/// Prints how many cubes are on and off within each region, given as
+/// `x=min..max,y=min..max,z=min..max`, and within all of them together.
+pub fn volume(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    if args.is_empty() {
+        return Err(anyhow!("Expecting `tool 22 volume <region> [region ...]`"));
+    }
+
+    let regions = args
+        .iter()
+        .map(|region| region.parse::<Cuboid<3>>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let reactor = reboot(input, None)?;
+
+    for region in &regions {
+        let on = reactor.volume_within(region);
+        println!("{}: {} on, {} off", region, on, region.volume() - on);
+    }
+
+    // regions may overlap, which the union only counts once
+    let combined = regions.into_iter().fold(BoxSet::new(), |combined, region| {
+        combined.union(&region.into())
+    });
+
+    let on = reactor.intersection(&combined).volume();
+    let off = combined.difference(&reactor).volume();
+    println!("total: {} on, {} off", on, off);
+
+    Ok(())
+}
+
+/// Counts the cubes that end up on with `tool 22 run <signed|compressed|tree>`, along with how long
+/// each part took.
+pub fn run(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let backend = args
+        .first()
+        .context("Expecting `tool 22 run <signed|compressed|tree>`")?
+        .parse::<Backend>()?;
+
+    let initialization = Cuboid::cube(-50, 50);
+
+    for (part, region) in [("A", Some(&initialization)), ("B", None)] {
+        let steps = parse(input, region)?;
+        let start = Instant::now();
+        let volume = backend.volume(&steps, None).unwrap();
+
+        println!(
+            "Part {}: {} (duration = {:?})",
+            part,
+            volume,
+            start.elapsed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Times every backend on the puzzle input and then on generated inputs with
+/// `tool 22 bench [max_size] [max_density]`.
+///
+/// The size doubles from 25 steps up to `max_size`, and the density from 1 up to `max_density`.
+/// Denser inputs have their cuboids pushed that many times closer together, so they overlap more.
+/// A backend that runs out of time isn't tried on generated inputs that are at least as dense
+/// anymore.
+pub fn bench(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let max_size = match args.first() {
+        Some(size) => size.parse()?,
+        None => 400,
+    };
+
+    let max_density = match args.get(1) {
+        Some(density) => density.parse()?,
+        None => 16,
+    };
+
+    print!("{:>6} {:>8}", "size", "density");
+
+    for backend in BACKENDS {
+        print!(" {:>12}", backend);
+    }
+
+    println!();
+
+    // the puzzle input has no density, so a timeout on it doesn't rule anything out
+    print!("{:>6} {:>8}", "input", "-");
+    time_backends(&parse(input, None)?, 0, &mut [None; BACKENDS.len()])?;
+
+    let mut gave_up = [None; BACKENDS.len()];
+    let sizes = std::iter::successors(Some(25), |size| Some(size * 2));
+    let densities = std::iter::successors(Some(1), |density| Some(density * 2));
+
+    for size in sizes.take_while(|size| *size <= max_size) {
+        let input = crate::generator::generate(Day::Day22, size as u64, Some(size))?;
+        let lines = input.lines().collect::<Vec<_>>();
+        let steps = parse(&lines, None)?;
+
+        for density in densities
+            .clone()
+            .take_while(|density| *density <= max_density)
+        {
+            let steps = steps
+                .iter()
+                .map(|(on, cuboid)| (*on, squeeze(cuboid, density)))
+                .collect::<Vec<_>>();
+
+            print!("{:>6} {:>8}", size, density);
+            time_backends(&steps, density, &mut gave_up)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints how long each backend takes on the steps, skipping those that already gave up at this
+/// `density` or below, and checks that the ones that finished agree.
+fn time_backends(
+    steps: &[Step],
+    density: i64,
+    gave_up: &mut [Option<i64>; BACKENDS.len()],
+) -> anyhow::Result<()> {
+    let mut volumes = Vec::new();
+
+    for (backend, gave_up) in BACKENDS.iter().zip(gave_up) {
+        if gave_up.is_some_and(|at| density >= at) {
+            print!(" {:>12}", "-");
+            continue;
+        }
+
+        let start = Instant::now();
+
+        match backend.volume(steps, Some(start + BENCH_BUDGET)) {
+            Some(volume) => {
+                volumes.push(volume);
+                print!(" {:>12}", format!("{:.2?}", start.elapsed()));
+            }
+            None => {
+                *gave_up = Some(density);
+                print!(" {:>12}", "timeout");
+            }
+        }
+    }
+
+    println!();
+
+    if volumes.iter().any(|volume| *volume != volumes[0]) {
+        return Err(anyhow!("Backends disagree: {:?}", volumes));
+    }
+
+    Ok(())
 }
 
-fn solve(input: &[&str], large: bool) -> u64 {
-    let mut cuboids = Vec::<Cuboid>::new();
-    let mut count = 0;
+/// Moves the cuboid `density` times closer to the origin, keeping its size.
+fn squeeze(cuboid: &Cuboid<3>, density: i64) -> Cuboid<3> {
+    let min = cuboid.min.map(|min| min / density);
+    let max = std::array::from_fn(|axis| min[axis] + cuboid.max[axis] - cuboid.min[axis]);
+
+    Cuboid { min, max }
+}
 
-    for line in input {
-        if let Some(cuboid) = parse_cuboid(line, large) {
-            let mut index = 0;
-            let mut length = cuboids.len();
+/// Runs the reboot steps, ignoring all cubes outside of `region`.
+fn reboot(input: &[&str], region: Option<&Cuboid<3>>) -> anyhow::Result<BoxSet<3>> {
+    let mut reactor = BoxSet::new();
 
-            while index < length {
-                let existing = &cuboids[index];
-                let current_index = index;
-                index += 1;
+    for (on, cuboid) in parse(input, region)? {
+        if on {
+            reactor.insert(cuboid);
+        } else {
+            reactor.remove(cuboid);
+        }
+    }
 
-                let intersection = match existing.intersection(&cuboid) {
-                    Some(intersection) => intersection,
-                    None => continue,
-                };
+    Ok(reactor)
+}
 
-                if intersection.enabled {
-                    count += intersection.size();
-                } else {
-                    count -= intersection.size();
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Backend {
+    /// Overlapping cuboids with signed volumes, see `BoxSet`.
+    Signed,
+    /// Dense grid over the coordinates where cuboids start or end, filled one slab along x at a
+    /// time.
+    Compressed,
+    /// k-d tree that splits space along the faces of the cuboids.
+    Tree,
+}
+
+impl Backend {
+    /// Counts the cubes that are on after all steps, unless that takes until past the `deadline`.
+    fn volume(&self, steps: &[Step], deadline: Option<Instant>) -> Option<i128> {
+        let expired = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+
+        match self {
+            Backend::Signed => {
+                let mut reactor = BoxSet::new();
+
+                for (on, cuboid) in steps {
+                    if expired() {
+                        return None;
+                    }
+
+                    if *on {
+                        reactor.insert(*cuboid);
+                    } else {
+                        reactor.remove(*cuboid);
+                    }
                 }
 
-                if !intersection.negates(existing) {
-                    cuboids.push(intersection);
-                    continue;
+                Some(reactor.volume())
+            }
+            Backend::Compressed => compressed_volume(steps, expired),
+            Backend::Tree => {
+                let mut tree = Tree::new(steps);
+
+                for (on, cuboid) in steps {
+                    if expired() {
+                        return None;
+                    }
+
+                    tree.switch(cuboid, *on);
                 }
 
-                cuboids.swap_remove(current_index);
+                Some(tree.volume())
+            }
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = anyhow::Error;
 
-                if length > cuboids.len() {
-                    index = current_index;
-                    length -= 1;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BACKENDS
+            .into_iter()
+            .find(|backend| backend.to_string() == s)
+            .with_context(|| {
+                format!(
+                    "Unknown backend {}, expecting signed, compressed or tree",
+                    s
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Signed => f.pad("signed"),
+            Backend::Compressed => f.pad("compressed"),
+            Backend::Tree => f.pad("tree"),
+        }
+    }
+}
+
+/// Sorted coordinates along `axis` where the cuboids start, or stop right before.
+fn boundaries<'a>(cuboids: impl Iterator<Item = &'a Cuboid<3>>, axis: usize) -> Vec<i64> {
+    let mut boundaries = cuboids
+        .flat_map(|cuboid| [cuboid.min[axis], cuboid.max[axis] + 1])
+        .collect::<Vec<_>>();
+
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+}
+
+/// Between two x boundaries the same steps apply everywhere, so each slab only needs a grid over
+/// the y and z boundaries of the cuboids that reach into it.
+fn compressed_volume(steps: &[Step], expired: impl Fn() -> bool) -> Option<i128> {
+    let xs = boundaries(steps.iter().map(|(_, cuboid)| cuboid), 0);
+    let mut volume = 0;
+
+    for slab in xs.windows(2) {
+        if expired() {
+            return None;
+        }
+
+        let active = steps
+            .iter()
+            .filter(|(_, cuboid)| (cuboid.min[0]..=cuboid.max[0]).contains(&slab[0]))
+            .collect::<Vec<_>>();
+
+        if active.is_empty() {
+            continue;
+        }
+
+        let ys = boundaries(active.iter().map(|(_, cuboid)| cuboid), 1);
+        let zs = boundaries(active.iter().map(|(_, cuboid)| cuboid), 2);
+        let index = |boundaries: &[i64], value| boundaries.binary_search(&value).unwrap();
+        let mut grid = vec![false; ys.len() * zs.len()];
+
+        for (on, cuboid) in active {
+            let (y1, y2) = (index(&ys, cuboid.min[1]), index(&ys, cuboid.max[1] + 1));
+            let (z1, z2) = (index(&zs, cuboid.min[2]), index(&zs, cuboid.max[2] + 1));
+
+            for y in y1..y2 {
+                grid[y * zs.len() + z1..y * zs.len() + z2].fill(*on);
+            }
+        }
+
+        let mut area = 0;
+
+        for y in 0..ys.len() - 1 {
+            for z in 0..zs.len() - 1 {
+                if grid[y * zs.len() + z] {
+                    area += (ys[y + 1] - ys[y]) as i128 * (zs[z + 1] - zs[z]) as i128;
                 }
             }
+        }
 
-            if cuboid.enabled {
-                count += cuboid.size();
-                cuboids.push(cuboid);
+        volume += area * (slab[1] - slab[0]) as i128;
+    }
+
+    Some(volume)
+}
+
+/// Region of space that is either entirely on or off, or split in two.
+enum Node {
+    Leaf(bool),
+    /// Split along `axis`, with the `high` half starting at `at`.
+    Split {
+        axis: usize,
+        at: i64,
+        low: Box<Node>,
+        high: Box<Node>,
+    },
+}
+
+struct Tree {
+    bounds: Cuboid<3>,
+    root: Node,
+}
+
+impl Tree {
+    /// Creates an empty tree covering all the cuboids of the steps.
+    fn new(steps: &[Step]) -> Self {
+        let min = std::array::from_fn(|axis| {
+            let min = steps.iter().map(|(_, cuboid)| cuboid.min[axis]).min();
+            min.unwrap_or(0)
+        });
+
+        let max = std::array::from_fn(|axis| {
+            let max = steps.iter().map(|(_, cuboid)| cuboid.max[axis]).max();
+            max.unwrap_or(0)
+        });
+
+        Tree {
+            bounds: Cuboid { min, max },
+            root: Node::Leaf(false),
+        }
+    }
+
+    fn switch(&mut self, cuboid: &Cuboid<3>, on: bool) {
+        Self::switch_node(&mut self.root, &self.bounds, cuboid, on);
+    }
+
+    fn switch_node(node: &mut Node, region: &Cuboid<3>, cuboid: &Cuboid<3>, on: bool) {
+        let overlap = match region.intersection(cuboid) {
+            Some(overlap) => overlap,
+            None => return,
+        };
+
+        if overlap == *region {
+            *node = Node::Leaf(on);
+            return;
+        }
+
+        if let Node::Leaf(state) = *node {
+            if state == on {
+                return;
+            }
+
+            // a face of the cuboid cuts through the region, since it doesn't cover all of it
+            let (axis, at) = (0..3)
+                .find_map(|axis| {
+                    if cuboid.min[axis] > region.min[axis] {
+                        Some((axis, cuboid.min[axis]))
+                    } else if cuboid.max[axis] < region.max[axis] {
+                        Some((axis, cuboid.max[axis] + 1))
+                    } else {
+                        None
+                    }
+                })
+                .unwrap();
+
+            *node = Node::Split {
+                axis,
+                at,
+                low: Box::new(Node::Leaf(state)),
+                high: Box::new(Node::Leaf(state)),
+            };
+        }
+
+        if let Node::Split {
+            axis,
+            at,
+            low,
+            high,
+        } = node
+        {
+            let (low_region, high_region) = split(region, *axis, *at);
+            Self::switch_node(low, &low_region, cuboid, on);
+            Self::switch_node(high, &high_region, cuboid, on);
+
+            // both halves ending up the same way is common after switching a large cuboid
+            if let (Node::Leaf(a), Node::Leaf(b)) = (&**low, &**high) {
+                if a == b {
+                    *node = Node::Leaf(*a);
+                }
             }
         }
     }
 
-    count
-}
-
-#[derive(Debug)]
-struct Cuboid {
-    enabled: bool,
-    min_x: isize,
-    max_x: isize,
-    min_y: isize,
-    max_y: isize,
-    min_z: isize,
-    max_z: isize,
-}
-
-impl Cuboid {
-    fn size(&self) -> u64 {
-        ((self.max_x - self.min_x + 1)
-            * (self.max_y - self.min_y + 1)
-            * (self.max_z - self.min_z + 1)) as u64
-    }
-
-    fn negates(&self, other: &Self) -> bool {
-        self.enabled != other.enabled
-            && self.min_x == other.min_x
-            && self.max_x == other.max_x
-            && self.min_y == other.min_y
-            && self.max_y == other.max_y
-            && self.min_z == other.min_z
-            && self.max_z == other.max_z
-    }
-
-    fn intersection(&self, other: &Self) -> Option<Cuboid> {
-        let min_x = self.min_x.max(other.min_x);
-        let max_x = self.max_x.min(other.max_x);
-        let min_y = self.min_y.max(other.min_y);
-        let max_y = self.max_y.min(other.max_y);
-        let min_z = self.min_z.max(other.min_z);
-        let max_z = self.max_z.min(other.max_z);
-
-        if min_x <= max_x && min_y <= max_y && min_z <= max_z {
-            let enabled = !self.enabled;
-            Some(Cuboid {
-                enabled,
-                min_x,
-                max_x,
-                min_y,
-                max_y,
-                min_z,
-                max_z,
-            })
-        } else {
-            None
+    fn volume(&self) -> i128 {
+        Self::volume_of(&self.root, &self.bounds)
+    }
+
+    fn volume_of(node: &Node, region: &Cuboid<3>) -> i128 {
+        match node {
+            Node::Leaf(true) => region.volume(),
+            Node::Leaf(false) => 0,
+            Node::Split {
+                axis,
+                at,
+                low,
+                high,
+            } => {
+                let (low_region, high_region) = split(region, *axis, *at);
+                Self::volume_of(low, &low_region) + Self::volume_of(high, &high_region)
+            }
         }
     }
 }
 
-fn parse_cuboid(input: &str, large: bool) -> Option<Cuboid> {
-    let (enabled, input) = if *input.as_bytes().get(1).unwrap() == b'n' {
-        (true, &input[3..])
-    } else {
-        (false, &input[4..])
+/// Cuts the region in two, right before `at` along `axis`.
+fn split(region: &Cuboid<3>, axis: usize, at: i64) -> (Cuboid<3>, Cuboid<3>) {
+    let (mut low, mut high) = (*region, *region);
+    low.max[axis] = at - 1;
+    high.min[axis] = at;
+
+    (low, high)
+}
+
+/// Parses the reboot steps, clipping them to `region` and leaving out those outside of it.
+fn parse(input: &[&str], region: Option<&Cuboid<3>>) -> anyhow::Result<Vec<Step>> {
+    let mut steps = Vec::with_capacity(input.len());
+
+    for (index, line) in input.iter().enumerate() {
+        let step = parse_step(line, region).with_context(|| format!("line {}", index + 1))?;
+        steps.extend(step);
+    }
+
+    Ok(steps)
+}
+
+/// Parses the step and clips it to `region`, or returns `None` when it misses the region.
+fn parse_step(input: &str, region: Option<&Cuboid<3>>) -> anyhow::Result<Option<Step>> {
+    let (state, cuboid) = input
+        .split_once(' ')
+        .with_context(|| format!("Expecting `on|off <cuboid>`, found {}", input))?;
+
+    let on = match state {
+        "on" => true,
+        "off" => false,
+        _ => return Err(anyhow!("Expecting on or off, found {}", state)),
+    };
+
+    let cuboid = match region {
+        Some(region) => Cuboid::parse_within(cuboid, region)?,
+        None => Some(cuboid.parse()?),
     };
 
-    let (min_x, input) = input[2..].split_once('.').unwrap();
-    let (max_x, input) = input[1..].split_once(',').unwrap();
-    let (min_y, input) = input[2..].split_once('.').unwrap();
-    let (max_y, input) = input[1..].split_once(',').unwrap();
-    let (min_z, input) = input[2..].split_once('.').unwrap();
-    let max_z = &input[1..];
-
-    let min_x = min_x.parse::<isize>().unwrap();
-    let max_x = max_x.parse::<isize>().unwrap();
-    let min_y = min_y.parse::<isize>().unwrap();
-    let max_y = max_y.parse::<isize>().unwrap();
-    let min_z = min_z.parse::<isize>().unwrap();
-    let max_z = max_z.parse::<isize>().unwrap();
-
-    if !large && min_x.abs() > 50 {
-        None
-    } else {
-        Some(Cuboid {
-            enabled,
-            min_x,
-            max_x,
-            min_y,
-            max_y,
-            min_z,
-            max_z,
-        })
+    Ok(cuboid.map(|cuboid| (on, cuboid)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Small, heavily overlapping steps, so every backend has to split and merge cuboids.
+    fn steps() -> impl Strategy<Value = Vec<Step>> {
+        let range = (-8..=8i64, 0..=6i64).prop_map(|(min, length)| (min, min + length));
+        let cuboid = prop::array::uniform3(range).prop_map(|ranges| Cuboid {
+            min: ranges.map(|(min, _)| min),
+            max: ranges.map(|(_, max)| max),
+        });
+
+        prop::collection::vec((any::<bool>(), cuboid), 0..16)
+    }
+
+    const EXAMPLE: [&str; 4] = [
+        "on x=10..12,y=10..12,z=10..12",
+        "on x=11..13,y=11..13,z=11..13",
+        "off x=9..11,y=9..11,z=9..11",
+        "on x=10..10,y=10..10,z=10..10",
+    ];
+
+    #[test]
+    fn reboot_counts_example() {
+        assert_eq!(reboot(&EXAMPLE, None).unwrap().volume(), 39);
+    }
+
+    #[test]
+    fn reboot_clips_to_region() {
+        let region = Cuboid::new([10, 10, 10], [11, 11, 11]).unwrap();
+        let reactor = reboot(&EXAMPLE, None).unwrap();
+
+        assert_eq!(reboot(&EXAMPLE, Some(&region)).unwrap().volume(), 1);
+        assert_eq!(reactor.volume_within(&region), 1);
+    }
+
+    #[test]
+    fn backends_agree_on_example() {
+        let steps = parse(&EXAMPLE, None).unwrap();
+
+        for backend in BACKENDS {
+            assert_eq!(backend.volume(&steps, None), Some(39), "{}", backend);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn backends_agree_on_random_steps(steps in steps()) {
+            let volumes = BACKENDS.map(|backend| backend.volume(&steps, None).unwrap());
+            prop_assert!(volumes.iter().all(|volume| *volume == volumes[0]), "{:?}", volumes);
+        }
+    }
+
+    #[test]
+    fn parse_step_rejects_unknown_states() {
+        assert!(parse_step("toggle x=1..2,y=1..2,z=1..2", None).is_err());
+        assert!(parse_step("on x=1..2,y=1..2", None).is_err());
     }
 }
@@ -1,111 +1,555 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
 use std::collections::{BinaryHeap, HashMap};
+use std::io::Write;
 
-const ROOM_COUNT: usize = 4;
+/// Rows part B unfolds between the two rows of the standard burrow.
+const UNFOLDED_ROWS: [&str; 2] = ["DCBA", "DBAC"];
 
-const ENERGY_PER_STEP: [u32; ROOM_COUNT] = [1, 10, 100, 1000];
+/// Each type of amphipod takes 10 times as much energy per step as the previous one, so more types
+/// would overflow the cost.
+const MAX_TYPES: usize = 10;
 
-const HALLWAY_LENGTH: usize = 11;
-const DOOR_POSITIONS: [usize; ROOM_COUNT] = [2, 4, 6, 8];
+/// Enough for every type of amphipod to fill a room as deep as part B's.
+const MAX_AMPHIPODS: usize = MAX_TYPES * 4;
 
 const INVALID_SPOT: u8 = 254;
 const EMPTY_SPOT: u8 = 255;
 
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    Search::find_best_path(GameState::<8>::parse(input))
+    find_best_path(input)
 }
 
 pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
-    let extended = [
-        input[0],
-        input[1],
-        input[2],
-        "  #D#C#B#A#  ",
-        "  #D#B#A#C#  ",
-        input[3],
-        input[4],
-    ];
-
-    Search::find_best_path(GameState::<16>::parse(&extended))
+    let unfolded = unfold(input)?;
+    find_best_path(&unfolded.iter().map(String::as_str).collect::<Vec<_>>())
 }
 
+/// Searches with states just large enough for the amphipods, as copying and hashing the states is
+/// most of the work.
+fn find_best_path(input: &[&str]) -> anyhow::Result<u64> {
+    let (burrow, positions) = parse(input)?;
+
+    match positions.len() {
+        0..=8 => Search::find_best_path(&burrow, GameState::<8>::start(&positions)?),
+        9..=16 => Search::find_best_path(&burrow, GameState::<16>::start(&positions)?),
+        _ => Search::find_best_path(&burrow, GameState::<MAX_AMPHIPODS>::start(&positions)?),
+    }
+}
+
+/// Prints every move of the cheapest solution, along with the burrow after it.
+///
+/// Pass `unfold` to solve the unfolded burrow of part B.
+pub fn moves(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let (burrow, path) = replay(input, args)?;
+
+    println!("{}", burrow.draw(&path[0]));
+
+    for (previous, state) in path.iter().zip(&path[1..]) {
+        println!("{}", burrow.describe_move(previous, state));
+        println!(
+            "(energy = {}, estimate = {})",
+            state.cost(),
+            state.estimate(&burrow)
+        );
+        println!("{}", burrow.draw(state));
+    }
+
+    Ok(())
+}
+
+/// Replays the cheapest solution in the terminal, redrawing the burrow after every move.
+///
+/// Takes the delay between moves in milliseconds (500 by default), and `unfold` to solve the
+/// unfolded burrow of part B.
+pub fn animate(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let (burrow, path) = replay(input, args)?;
+
+    let delay = match args.iter().find(|arg| *arg != "unfold") {
+        Some(delay) => delay.parse().context("Delay has to be in milliseconds")?,
+        None => 500,
+    };
+
+    for (step, state) in path.iter().enumerate() {
+        // clear the screen and move the cursor to the top left
+        print!("\x1b[2J\x1b[H");
+        println!("{}", burrow.draw(state));
+
+        match step.checked_sub(1) {
+            Some(previous) => println!("{}", burrow.describe_move(&path[previous], state)),
+            None => println!("Start"),
+        }
+
+        println!("energy = {}", state.cost());
+        std::io::stdout().flush()?;
+        std::thread::sleep(std::time::Duration::from_millis(delay));
+    }
+
+    Ok(())
+}
+
+/// Prints how much work the search had to do to find the cheapest solution.
+///
+/// Pass `unfold` to solve the unfolded burrow of part B.
+pub fn stats(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let (burrow, state) = load(input, args)?;
+    let mut search = Search::new(&burrow, false);
+
+    println!("energy = {}", search.solve(state)?.cost());
+    print!("{}", search.stats);
+    Ok(())
+}
+
+/// Checks the heuristic against the exact remaining energy of every reachable state, and compares
+/// the result of the search with plain Dijkstra.
+///
+/// Pass `unfold` to solve the unfolded burrow of part B.
+pub fn check(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let (burrow, state) = load(input, args)?;
+
+    let mut a_star = Search::new(&burrow, false);
+    let a_star_cost = a_star.solve(state.clone())?.cost();
+    println!("A*: energy = {}", a_star_cost);
+    print!("{}", a_star.stats);
+
+    let mut dijkstra = Search::new(&burrow, false).without_heuristic();
+    let dijkstra_cost = dijkstra.solve(state.clone())?.cost();
+    println!("Dijkstra: energy = {}", dijkstra_cost);
+    print!("{}", dijkstra.stats);
+
+    let mut remaining = HashMap::new();
+    remaining_cost(&burrow, &state, &mut remaining);
+
+    let mut worst: Option<(u64, &PlayerPositions<MAX_AMPHIPODS>, u64)> = None;
+    let mut overestimates = 0;
+
+    for (positions, exact) in &remaining {
+        let exact = match exact {
+            Some(exact) => *exact,
+            None => continue,
+        };
+
+        let estimate = GameState::new(0, *positions).estimate(&burrow);
+
+        if estimate > exact {
+            overestimates += 1;
+
+            if worst.is_none_or(|(excess, _, _)| estimate - exact > excess) {
+                worst = Some((estimate - exact, positions, exact));
+            }
+        }
+    }
+
+    println!(
+        "{} reachable states, {} of which can still be won",
+        remaining.len(),
+        remaining.values().filter(|exact| exact.is_some()).count()
+    );
+
+    if let Some((excess, positions, exact)) = worst {
+        let state = GameState::new(0, *positions);
+        println!("{}", burrow.draw(&state));
+
+        return Err(anyhow!(
+            "Heuristic overestimates {} states, by up to {} (estimate = {}, exact = {})",
+            overestimates,
+            excess,
+            exact + excess,
+            exact
+        ));
+    }
+
+    if a_star_cost != dijkstra_cost {
+        return Err(anyhow!(
+            "A* found {} energy, while Dijkstra found {}",
+            a_star_cost,
+            dijkstra_cost
+        ));
+    }
+
+    println!("Heuristic never overestimates");
+    Ok(())
+}
+
+/// Finds the least energy needed to win from `state`, for it and every state reachable from it.
+///
+/// Amphipods move at most twice (out of their room, and into their own room where they stay), so
+/// states can never repeat along a path and the exact costs can be found recursively.
+fn remaining_cost<const N: usize>(
+    burrow: &Burrow,
+    state: &GameState<N>,
+    remaining: &mut HashMap<PlayerPositions<N>, Option<u64>>,
+) -> Option<u64> {
+    if let Some(exact) = remaining.get(state.positions()) {
+        return *exact;
+    }
+
+    let exact = if state.is_won(burrow) {
+        Some(0)
+    } else {
+        let mut spots = Vec::new();
+        let mut next = Vec::new();
+        GameState::new(0, *state.positions()).find_next_states(burrow, &mut spots, &mut next);
+
+        next.iter()
+            .filter_map(|next| Some(next.cost() + remaining_cost(burrow, next, remaining)?))
+            .min()
+    };
+
+    remaining.insert(*state.positions(), exact);
+    exact
+}
+
+/// Solves the burrow, returning every state of the cheapest solution.
+fn replay(
+    input: &[&str],
+    args: &[String],
+) -> anyhow::Result<(Burrow, Vec<GameState<MAX_AMPHIPODS>>)> {
+    let (burrow, state) = load(input, args)?;
+    let path = Search::find_best_moves(&burrow, state)?;
+    Ok((burrow, path))
+}
+
+/// Parses the burrow for a tool, unfolding it first when asked to.
+fn load(input: &[&str], args: &[String]) -> anyhow::Result<(Burrow, GameState<MAX_AMPHIPODS>)> {
+    if args.iter().any(|arg| arg == "unfold") {
+        let unfolded = unfold(input)?;
+        GameState::parse(&unfolded.iter().map(String::as_str).collect::<Vec<_>>())
+    } else {
+        GameState::parse(input)
+    }
+}
+
+/// Inserts the two folded rows of the diagram below the first row of rooms.
+fn unfold(input: &[&str]) -> anyhow::Result<Vec<String>> {
+    let first_row = input.get(2).context("Missing rooms")?;
+    let columns = room_columns(first_row);
+
+    if columns.len() != UNFOLDED_ROWS[0].len() {
+        return Err(anyhow!(
+            "Only burrows with {} rooms can be unfolded, found {}",
+            UNFOLDED_ROWS[0].len(),
+            columns.len()
+        ));
+    }
+
+    let (left, right) = (columns[0] - 1, columns[columns.len() - 1] + 1);
+
+    let rows = UNFOLDED_ROWS.iter().map(|amphipods| {
+        let mut amphipods = amphipods.chars();
+
+        first_row
+            .char_indices()
+            .take(right + 1)
+            .map(|(x, char)| match x {
+                _ if columns.contains(&x) => amphipods.next().unwrap(),
+                _ if x < left => ' ',
+                _ => char,
+            })
+            .collect::<String>()
+    });
+
+    Ok(input[..3]
+        .iter()
+        .map(|line| line.to_string())
+        .chain(rows)
+        .chain(input[3..].iter().map(|line| line.to_string()))
+        .collect())
+}
+
+/// Columns of a row in the diagram that are part of a room.
+fn room_columns(row: &str) -> Vec<usize> {
+    row.char_indices()
+        .filter(|(_, char)| *char == '.' || char.is_ascii_uppercase())
+        .map(|(x, _)| x)
+        .collect()
+}
+
+/// Layout of the burrow, which doesn't change while the amphipods move around.
+///
+/// Spots are numbered from the left of the hallway to the right, followed by the rooms from left to
+/// right, each from the top down.
+struct Burrow {
+    hallway_length: usize,
+    /// Hallway spot right outside each room, which amphipods can't stop on.
+    doors: Vec<usize>,
+    room_size: usize,
+    /// Energy a single step takes for each type of amphipod.
+    energy: Vec<u64>,
+}
+
+impl Burrow {
+    fn room_count(&self) -> usize {
+        self.doors.len()
+    }
+
+    fn spot_count(&self) -> usize {
+        self.hallway_length + self.amphipod_count()
+    }
+
+    fn amphipod_count(&self) -> usize {
+        self.room_count() * self.room_size
+    }
+
+    fn letter(&self, index: usize) -> char {
+        (b'A' + self.type_of(index) as u8) as char
+    }
+
+    /// Draws the burrow the way the puzzle does.
+    fn draw<const N: usize>(&self, state: &GameState<N>) -> String {
+        let width = self.hallway_length + 2;
+        let columns = self.doors.iter().map(|door| door + 1).collect::<Vec<_>>();
+        let (left, right) = (columns[0] - 1, columns[columns.len() - 1] + 1);
+
+        let mut rows = vec![vec!['#'; width], vec!['.'; width]];
+        rows[1][0] = '#';
+        rows[1][width - 1] = '#';
+
+        for depth in 0..=self.room_size {
+            let mut row = vec![if depth == 0 { '#' } else { ' ' }; right.max(width - 1) + 1];
+            row[left..=right].fill('#');
+
+            if depth < self.room_size {
+                for &x in &columns {
+                    row[x] = '.';
+                }
+            }
+
+            rows.push(row);
+        }
+
+        for (index, &position) in state.amphipods(self).iter().enumerate() {
+            let position = position as usize;
+
+            let (x, y) = match position.checked_sub(self.hallway_length) {
+                None => (position + 1, 1),
+                Some(spot) => (columns[spot / self.room_size], 2 + spot % self.room_size),
+            };
+
+            rows[y][x] = self.letter(index);
+        }
+
+        rows.iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn describe_spot(&self, position: u8) -> String {
+        match (position as usize).checked_sub(self.hallway_length) {
+            None => format!("hallway {}", position),
+            Some(spot) => format!(
+                "room {} at depth {}",
+                (b'A' + (spot / self.room_size) as u8) as char,
+                spot % self.room_size
+            ),
+        }
+    }
+
+    /// Describes which amphipod moved between two consecutive states, and how much energy it took.
+    fn describe_move<const N: usize>(
+        &self,
+        previous: &GameState<N>,
+        state: &GameState<N>,
+    ) -> String {
+        let index = (0..self.amphipod_count())
+            .find(|&index| previous.positions()[index] != state.positions()[index])
+            .expect("States differ by one move");
+
+        format!(
+            "{} moves from {} to {} ({} energy)",
+            self.letter(index),
+            self.describe_spot(previous.positions()[index]),
+            self.describe_spot(state.positions()[index]),
+            state.cost() - previous.cost()
+        )
+    }
+
+    /// Amphipods are ordered by type, with as many of each type as there are spots in a room.
+    fn type_of(&self, index: usize) -> usize {
+        index / self.room_size
+    }
+}
+
+/// Spot of each amphipod, stored inline so states are cheap to copy and hash.
+///
+/// `N` can be larger than the number of amphipods, in which case the spots past them are left at 0.
 type PlayerPositions<const N: usize> = [u8; N];
 
-struct Search<const N: usize> {
+struct Search<'a, const N: usize> {
+    burrow: &'a Burrow,
     queue: BinaryHeap<SearchState<N>>,
-    costs: HashMap<PlayerPositions<N>, u32>,
+    costs: HashMap<PlayerPositions<N>, u64>,
+    /// State each state was reached from most cheaply, when the moves have to be reconstructed.
+    previous: Option<HashMap<PlayerPositions<N>, PlayerPositions<N>>>,
+    /// Whether states are ordered by their estimate, or only by the energy spent so far.
+    heuristic: bool,
+    stats: Stats,
     buffer: Vec<GameState<N>>,
+    spots: Vec<u8>,
 }
 
-impl<const N: usize> Search<N> {
-    fn find_best_path(state: GameState<N>) -> anyhow::Result<u32> {
-        let search = Search {
+impl<'a, const N: usize> Search<'a, N> {
+    fn new(burrow: &'a Burrow, track_moves: bool) -> Self {
+        Search {
+            burrow,
             queue: BinaryHeap::new(),
             costs: HashMap::new(),
+            previous: track_moves.then(HashMap::new),
+            heuristic: true,
+            stats: Stats::default(),
             buffer: Vec::new(),
-        };
+            spots: Vec::with_capacity(burrow.spot_count()),
+        }
+    }
+
+    /// Turns the search into plain Dijkstra, which doesn't rely on the estimate being admissible.
+    fn without_heuristic(mut self) -> Self {
+        self.heuristic = false;
+        self
+    }
+
+    fn priority(&self, state: &GameState<N>) -> u64 {
+        if self.heuristic {
+            state.estimate(self.burrow)
+        } else {
+            state.cost()
+        }
+    }
+
+    fn push(&mut self, state: GameState<N>) {
+        self.queue.push(SearchState {
+            estimate: self.priority(&state),
+            positions: state.positions,
+        });
 
-        search.solve(state)
+        self.stats.peak_queue = self.stats.peak_queue.max(self.queue.len());
     }
 
-    fn solve(mut self, state: GameState<N>) -> anyhow::Result<u32> {
+    fn find_best_path(burrow: &'a Burrow, state: GameState<N>) -> anyhow::Result<u64> {
+        Ok(Search::new(burrow, false).solve(state)?.cost())
+    }
+
+    /// Finds the cheapest solution, returning every state along the way (starting with `state`).
+    fn find_best_moves(
+        burrow: &'a Burrow,
+        state: GameState<N>,
+    ) -> anyhow::Result<Vec<GameState<N>>> {
+        let mut search = Search::new(burrow, true);
+        let mut path = vec![search.solve(state)?];
+        let previous = search.previous.unwrap();
+
+        while let Some(positions) = previous.get(path[path.len() - 1].positions()) {
+            path.push(GameState::new(search.costs[positions], *positions));
+        }
+
+        path.reverse();
+        Ok(path)
+    }
+
+    #[tracing::instrument(name = "search", skip_all)]
+    fn solve(&mut self, state: GameState<N>) -> anyhow::Result<GameState<N>> {
         self.costs.insert(*state.positions(), state.cost());
-        self.queue.push(SearchState::from_game_state(state));
+        self.push(state);
 
         while let Some(state) = self.queue.pop() {
+            let estimate = state.estimate();
+
             let state = match self.costs.get(state.positions()) {
                 Some(cost) => state.build_game_state(*cost),
                 None => unreachable!(),
             };
 
-            if state.is_won() {
-                return Ok(state.cost());
+            if self.priority(&state) != estimate {
+                // a cheaper way to reach this state was queued after this one
+                self.stats.stale += 1;
+                continue;
+            }
+
+            self.stats.expanded += 1;
+
+            if state.is_won(self.burrow) {
+                return Ok(state);
             }
 
-            state.find_next_states(&mut self.buffer);
+            state.find_next_states(self.burrow, &mut self.spots, &mut self.buffer);
+            let current = state;
+            let mut buffer = std::mem::take(&mut self.buffer);
+            self.stats.generated += buffer.len();
 
-            for state in self.buffer.drain(..) {
+            for state in buffer.drain(..) {
                 let cost = state.cost();
 
                 match self.costs.entry(*state.positions()) {
-                    Entry::Occupied(mut entry) => {
-                        if *entry.get() > cost {
-                            entry.insert(cost);
+                    Entry::Occupied(mut known) => {
+                        self.stats.duplicates += 1;
+
+                        if *known.get() <= cost {
+                            continue;
                         }
+
+                        known.insert(cost);
                     }
                     Entry::Vacant(entry) => {
                         entry.insert(cost);
-                        self.queue.push(SearchState::from_game_state(state));
                     }
                 }
+
+                if let Some(previous) = &mut self.previous {
+                    previous.insert(*state.positions(), *current.positions());
+                }
+
+                self.push(state);
             }
+
+            self.buffer = buffer;
         }
 
         Err(anyhow!("Cannot find solution"))
     }
 }
 
+/// Counters describing how much work a search did.
+#[derive(Default)]
+struct Stats {
+    /// States taken off the queue to look for moves.
+    expanded: usize,
+    /// States reached by those moves.
+    generated: usize,
+    /// Generated states that had been reached before, whether or not it was cheaper this time.
+    duplicates: usize,
+    /// States taken off the queue after a cheaper way to reach them had been queued.
+    stale: usize,
+    peak_queue: usize,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  expanded   = {}", self.expanded)?;
+        writeln!(f, "  generated  = {}", self.generated)?;
+        writeln!(f, "  duplicates = {}", self.duplicates)?;
+        writeln!(f, "  stale      = {}", self.stale)?;
+        writeln!(f, "  peak queue = {}", self.peak_queue)
+    }
+}
+
 #[derive(Eq, PartialEq)]
 struct SearchState<const N: usize> {
-    estimate: u32,
+    estimate: u64,
     positions: PlayerPositions<N>,
 }
 
 impl<const N: usize> SearchState<N> {
-    fn from_game_state(state: GameState<N>) -> Self {
-        SearchState {
-            estimate: state.estimate(),
-            positions: *state.positions(),
-        }
+    fn estimate(&self) -> u64 {
+        self.estimate
     }
 
     fn positions(&self) -> &PlayerPositions<N> {
         &self.positions
     }
 
-    fn build_game_state(self, cost: u32) -> GameState<N> {
+    fn build_game_state(self, cost: u64) -> GameState<N> {
         GameState::new(cost, self.positions)
     }
 }
@@ -122,24 +566,38 @@ impl<const N: usize> PartialOrd for SearchState<N> {
     }
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 struct GameState<const N: usize> {
-    cost: u32,
+    cost: u64,
     positions: PlayerPositions<N>,
 }
 
 impl<const N: usize> GameState<N> {
-    fn new(cost: u32, positions: PlayerPositions<N>) -> Self {
+    fn new(cost: u64, positions: PlayerPositions<N>) -> Self {
         GameState { cost, positions }
     }
 
-    fn parse(input: &[&str]) -> Self {
-        let mut builder = GameStateBuilder::new();
-        builder.parse(input);
-        builder.build()
+    /// State before any amphipod moved, from the spot each one starts on.
+    fn start(spots: &[u8]) -> anyhow::Result<Self> {
+        if spots.len() > N {
+            return Err(anyhow!(
+                "Burrow has {} amphipods, at most {} are supported",
+                spots.len(),
+                N
+            ));
+        }
+
+        let mut positions = [0; N];
+        positions[..spots.len()].copy_from_slice(spots);
+        Ok(GameState::new(0, positions))
+    }
+
+    fn parse(input: &[&str]) -> anyhow::Result<(Burrow, Self)> {
+        let (burrow, spots) = parse(input)?;
+        Ok((burrow, GameState::start(&spots)?))
     }
 
-    fn cost(&self) -> u32 {
+    fn cost(&self) -> u64 {
         self.cost
     }
 
@@ -147,14 +605,19 @@ impl<const N: usize> GameState<N> {
         &self.positions
     }
 
-    fn is_won(&self) -> bool {
-        let room_size = N / ROOM_COUNT;
+    /// Spots of the burrow's amphipods, leaving out any padding.
+    fn amphipods(&self, burrow: &Burrow) -> &[u8] {
+        &self.positions[..burrow.amphipod_count()]
+    }
+
+    fn is_won(&self, burrow: &Burrow) -> bool {
+        let room_size = burrow.room_size;
 
-        self.positions()
+        self.amphipods(burrow)
             .chunks(room_size)
             .enumerate()
             .all(|(i, room)| {
-                let start = (HALLWAY_LENGTH + room_size * i) as u8;
+                let start = (burrow.hallway_length + room_size * i) as u8;
                 let stop = start + room_size as u8;
 
                 room.iter()
@@ -162,33 +625,30 @@ impl<const N: usize> GameState<N> {
             })
     }
 
-    fn find_next_states(&self, buffer: &mut Vec<Self>) {
-        let room_size = N / ROOM_COUNT;
+    fn find_next_states(&self, burrow: &Burrow, spots: &mut Vec<u8>, buffer: &mut Vec<Self>) {
+        let room_size = burrow.room_size;
+        let hallway_length = burrow.hallway_length;
 
-        let mut hallway_positions = [EMPTY_SPOT; HALLWAY_LENGTH];
-        let mut room_positions = [EMPTY_SPOT; N];
+        spots.clear();
+        spots.resize(burrow.spot_count(), EMPTY_SPOT);
 
-        for position in DOOR_POSITIONS {
-            hallway_positions[position] = INVALID_SPOT;
+        for &position in &burrow.doors {
+            spots[position] = INVALID_SPOT;
         }
 
-        for (index, position) in self.positions.iter().enumerate() {
-            let position = *position as usize;
-
-            if position < HALLWAY_LENGTH {
-                hallway_positions[position] = index as u8;
-            } else {
-                room_positions[position - HALLWAY_LENGTH] = index as u8;
-            }
+        for (index, position) in self.amphipods(burrow).iter().enumerate() {
+            spots[*position as usize] = index as u8;
         }
 
-        for (index, position) in self.positions.iter().enumerate() {
+        let (hallway_positions, room_positions) = spots.split_at(hallway_length);
+
+        for (index, position) in self.amphipods(burrow).iter().enumerate() {
             let position = *position as usize;
-            let r#type = index / room_size;
+            let r#type = burrow.type_of(index);
 
-            if position < HALLWAY_LENGTH {
+            if position < hallway_length {
                 // we're in the hallway
-                let door_position = DOOR_POSITIONS[r#type];
+                let door_position = burrow.doors[r#type];
 
                 let mut hallway_range = if position < door_position {
                     position + 1..door_position
@@ -212,26 +672,26 @@ impl<const N: usize> GameState<N> {
 
                     if room == EMPTY_SPOT {
                         let distance = distance + 2 + position - room_start;
-                        let position = position + HALLWAY_LENGTH;
-                        buffer.push(self.move_player(distance, index, r#type, position));
+                        let position = position + hallway_length;
+                        buffer.push(self.move_player(burrow, distance, index, position));
                         break;
                     }
 
-                    if room as usize / room_size != r#type {
+                    if burrow.type_of(room as usize) != r#type {
                         // room is occupied by a different kind
                         break;
                     }
                 }
             } else {
                 // we're in a room
-                let position = position - HALLWAY_LENGTH;
+                let position = position - hallway_length;
                 let room = position / room_size;
                 let depth = position % room_size;
                 let room_start = room * room_size;
 
                 if room == r#type
                     && (depth + 1..room_size)
-                        .all(|i| room_positions[room_start + i] as usize / room_size == r#type)
+                        .all(|i| burrow.type_of(room_positions[room_start + i] as usize) == r#type)
                 {
                     // we're in the right room
                     continue;
@@ -242,7 +702,7 @@ impl<const N: usize> GameState<N> {
                     continue;
                 }
 
-                let door_position = DOOR_POSITIONS[room];
+                let door_position = burrow.doors[room];
                 let mut distance = 2 + depth;
 
                 for (i, &spot) in hallway_positions
@@ -252,7 +712,7 @@ impl<const N: usize> GameState<N> {
                     .rev()
                 {
                     if spot == EMPTY_SPOT {
-                        buffer.push(self.move_player(distance, index, r#type, i));
+                        buffer.push(self.move_player(burrow, distance, index, i));
                     } else if spot != INVALID_SPOT {
                         break;
                     }
@@ -264,7 +724,7 @@ impl<const N: usize> GameState<N> {
 
                 for (i, &spot) in hallway_positions.iter().enumerate().skip(door_position + 1) {
                     if spot == EMPTY_SPOT {
-                        buffer.push(self.move_player(distance, index, r#type, i));
+                        buffer.push(self.move_player(burrow, distance, index, i));
                     } else if spot != INVALID_SPOT {
                         break;
                     }
@@ -275,43 +735,50 @@ impl<const N: usize> GameState<N> {
         }
     }
 
-    fn estimate(&self) -> u32 {
-        let room_size = N / ROOM_COUNT;
+    fn estimate(&self, burrow: &Burrow) -> u64 {
+        let room_size = burrow.room_size;
+        let hallway_length = burrow.hallway_length;
 
         let estimate = self
-            .positions
+            .amphipods(burrow)
             .iter()
             .enumerate()
             .map(|(index, position)| {
-                let r#type = index / room_size;
-                let destination = DOOR_POSITIONS[r#type];
-                let mut position = *position as usize;
-
-                if position >= HALLWAY_LENGTH {
-                    position -= HALLWAY_LENGTH;
-                }
+                let r#type = burrow.type_of(index);
+                let destination = burrow.doors[r#type];
+                let position = *position as usize;
 
-                let distance = if position < destination {
-                    destination - position
+                let distance = if position < hallway_length {
+                    // walk up to the door and step into the room
+                    position.abs_diff(destination) + 1
                 } else {
-                    position - destination
+                    let room = (position - hallway_length) / room_size;
+                    let depth = (position - hallway_length) % room_size;
+
+                    if room == r#type {
+                        0
+                    } else {
+                        // leave the room, walk over to the other door and step into that room
+                        depth + 1 + burrow.doors[room].abs_diff(destination) + 1
+                    }
                 };
 
-                distance as u32 * ENERGY_PER_STEP[r#type]
+                distance as u64 * burrow.energy[r#type]
             })
-            .sum::<u32>();
+            .sum::<u64>();
 
         self.cost + estimate
     }
 
     fn move_player(
         &self,
+        burrow: &Burrow,
         distance: usize,
         index: usize,
-        r#type: usize,
         destination: usize,
     ) -> Self {
-        let cost = self.cost + distance as u32 * ENERGY_PER_STEP[r#type];
+        let r#type = burrow.type_of(index);
+        let cost = self.cost + distance as u64 * burrow.energy[r#type];
 
         let mut positions = self.positions;
         positions[index] = destination as u8;
@@ -320,45 +787,292 @@ impl<const N: usize> GameState<N> {
     }
 }
 
-struct GameStateBuilder<const N: usize> {
-    positions: PlayerPositions<N>,
-    indices: [usize; ROOM_COUNT],
-    position: u8,
+#[tracing::instrument(name = "parse", skip_all)]
+fn parse(input: &[&str]) -> anyhow::Result<(Burrow, Vec<u8>)> {
+    let mut builder = GameStateBuilder::new();
+    builder.parse(input)?;
+    builder.build()
 }
 
-impl<const N: usize> GameStateBuilder<N> {
-    fn new() -> Self {
-        let room_size = N / ROOM_COUNT;
-        let positions = [0; N];
-        let indices = [0, room_size, 2 * room_size, 3 * room_size];
-        let position = HALLWAY_LENGTH as u8;
+/// Reads the burrow diagram, which consists of a hallway with a wall on either side, followed by
+/// rows of rooms lined up below it.
+struct GameStateBuilder {
+    hallway_length: usize,
+    columns: Vec<usize>,
+    /// Amphipods of each type, along with the spot they start on.
+    amphipods: Vec<(usize, usize)>,
+    room_size: usize,
+}
 
+impl GameStateBuilder {
+    fn new() -> Self {
         GameStateBuilder {
-            positions,
-            indices,
-            position,
+            hallway_length: 0,
+            columns: Vec::new(),
+            amphipods: Vec::new(),
+            room_size: 0,
+        }
+    }
+
+    fn parse(&mut self, input: &[&str]) -> anyhow::Result<()> {
+        let hallway = input.get(1).context("Missing hallway")?.trim_end();
+
+        let hallway = hallway
+            .strip_prefix('#')
+            .and_then(|hallway| hallway.strip_suffix('#'))
+            .context("Hallway has to be enclosed by walls")?;
+
+        self.hallway_length = hallway.len();
+        self.columns = room_columns(input.get(2).context("Missing rooms")?);
+
+        if self.columns.is_empty() {
+            return Err(anyhow!("Burrow has no rooms"));
+        }
+
+        if self.columns.len() > MAX_TYPES {
+            return Err(anyhow!(
+                "Burrow has {} rooms, at most {} are supported",
+                self.columns.len(),
+                MAX_TYPES
+            ));
+        }
+
+        if self.columns[0] == 0 || self.columns[self.columns.len() - 1] > self.hallway_length {
+            return Err(anyhow!("Rooms have to be below the hallway"));
         }
+
+        for (x, char) in hallway.chars().enumerate() {
+            if char == '.' {
+                continue;
+            }
+
+            if self.columns.contains(&(x + 1)) {
+                return Err(anyhow!("Amphipod {} is standing in a doorway", char));
+            }
+
+            self.insert(char, x)?;
+        }
+
+        for (y, row) in input[2..].iter().enumerate() {
+            if row
+                .trim_end()
+                .chars()
+                .all(|char| char == '#' || char == ' ')
+            {
+                break;
+            }
+
+            if room_columns(row) != self.columns {
+                return Err(anyhow!("Row {} of rooms doesn't line up with the first", y));
+            }
+
+            self.room_size += 1;
+        }
+
+        for (room, &x) in self.columns.clone().iter().enumerate() {
+            for y in 0..self.room_size {
+                let char = input[2 + y][x..].chars().next().unwrap();
+
+                if char != '.' {
+                    let spot = self.hallway_length + room * self.room_size + y;
+                    self.insert(char, spot)?;
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    fn parse(&mut self, input: &[&str]) {
-        let room_size = N / ROOM_COUNT;
+    /// Returns the layout of the burrow, along with the spot of each amphipod ordered by type.
+    fn build(self) -> anyhow::Result<(Burrow, Vec<u8>)> {
+        let room_count = self.columns.len();
+        let spot_count = self.hallway_length + room_count * self.room_size;
 
-        for x in 0..ROOM_COUNT {
-            for y in 0..room_size {
-                self.insert(input[2 + y].as_bytes()[3 + 2 * x]);
+        if spot_count >= INVALID_SPOT as usize {
+            return Err(anyhow!("Burrow has too many spots ({})", spot_count));
+        }
+
+        let mut positions = Vec::with_capacity(room_count * self.room_size);
+
+        for r#type in 0..room_count {
+            let spots = self
+                .amphipods
+                .iter()
+                .filter(|(other, _)| *other == r#type)
+                .map(|(_, spot)| *spot as u8)
+                .collect::<Vec<_>>();
+
+            if spots.len() != self.room_size {
+                return Err(anyhow!(
+                    "Expected {} amphipods of type {}, found {}",
+                    self.room_size,
+                    (b'A' + r#type as u8) as char,
+                    spots.len()
+                ));
             }
+
+            positions.extend(spots);
+        }
+
+        let burrow = Burrow {
+            hallway_length: self.hallway_length,
+            doors: self.columns.iter().map(|x| x - 1).collect(),
+            room_size: self.room_size,
+            energy: (0..room_count as u32).map(|i| 10u64.pow(i)).collect(),
+        };
+
+        Ok((burrow, positions))
+    }
+
+    fn insert(&mut self, r#type: char, spot: usize) -> anyhow::Result<()> {
+        let index = (r#type as u8).wrapping_sub(b'A') as usize;
+
+        if !r#type.is_ascii_uppercase() || index >= self.columns.len() {
+            return Err(anyhow!("Amphipod {} has no room to go to", r#type));
+        }
+
+        self.amphipods.push((index, spot));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(diagram: &str) -> anyhow::Result<u64> {
+        find_best_path(&diagram.lines().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn finds_cheaper_way_to_a_queued_state() {
+        assert_eq!(
+            solve("#############\n#...........#\n###B#C#D#B###\n  #A#D#A#C#\n  #########").unwrap(),
+            13343
+        );
+    }
+
+    #[test]
+    fn estimates_nothing_left_once_organised() {
+        let input = [
+            "#############",
+            "#...........#",
+            "###A#B#C#D###",
+            "  #A#B#C#D#",
+            "  #########",
+        ];
+
+        let (burrow, state) = GameState::<8>::parse(&input).unwrap();
+        assert!(state.is_won(&burrow));
+        assert_eq!(state.estimate(&burrow), state.cost());
+    }
+
+    #[test]
+    fn unfolds_standard_burrow() {
+        let input = [
+            "#############",
+            "#...........#",
+            "###B#C#B#D###",
+            "  #A#D#C#A#",
+            "  #########",
+        ];
+        let unfolded = unfold(&input).unwrap();
+
+        assert_eq!(unfolded[3], "  #D#C#B#A#");
+        assert_eq!(unfolded[4], "  #D#B#A#C#");
+        assert_eq!(unfolded[5], input[3]);
+    }
+
+    #[test]
+    fn solves_other_layouts() {
+        // swapping the top two amphipods through the hallway
+        assert_eq!(
+            solve("#######\n#.....#\n###B#A#\n  #A#B#\n  #####").unwrap(),
+            46
+        );
+        // an amphipod starting in the hallway only has to walk home
+        assert_eq!(solve("#####\n#.A.#\n###.#\n  #A#\n  ###").unwrap(), 2);
+        // five types, swapping the two most expensive ones
+        assert_eq!(
+            solve("#############\n#...........#\n###A#B#C#E#D#\n  #A#B#C#D#E#\n  #########")
+                .unwrap(),
+            46000
+        );
+        // too many amphipods for the states part B is solved with
+        assert_eq!(
+            solve(concat!(
+                "#############\n#.A.........#\n###.#B#C#D#E#\n",
+                "  #A#B#C#D#E#\n  #A#B#C#D#E#\n  #A#B#C#D#E#\n  ###########"
+            ))
+            .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn reconstructs_cheapest_moves() {
+        let input = [
+            "#############",
+            "#...........#",
+            "###B#C#B#D###",
+            "  #A#D#C#A#",
+            "  #########",
+        ];
+        let (burrow, state) = GameState::<8>::parse(&input).unwrap();
+        assert_eq!(burrow.draw(&state), input.join("\n"));
+
+        let path = Search::find_best_moves(&burrow, state).unwrap();
+        assert_eq!(path[0].cost(), 0);
+        assert_eq!(path[path.len() - 1].cost(), 12521);
+        assert!(path[path.len() - 1].is_won(&burrow));
+
+        for (previous, state) in path.iter().zip(&path[1..]) {
+            let mut spots = Vec::new();
+            let mut next = Vec::new();
+            previous.find_next_states(&burrow, &mut spots, &mut next);
+
+            assert!(
+                next.contains(state),
+                "{}",
+                burrow.describe_move(previous, state)
+            );
         }
     }
 
-    fn build(self) -> GameState<N> {
-        GameState::new(0, self.positions)
+    #[test]
+    fn heuristic_is_admissible() {
+        let input = [
+            "#############",
+            "#...........#",
+            "###B#C#B#D###",
+            "  #A#D#C#A#",
+            "  #########",
+        ];
+        let (burrow, state) = GameState::<8>::parse(&input).unwrap();
+
+        let mut remaining = HashMap::new();
+        assert_eq!(remaining_cost(&burrow, &state, &mut remaining), Some(12521));
+
+        for (positions, exact) in remaining {
+            if let Some(exact) = exact {
+                assert!(GameState::new(0, positions).estimate(&burrow) <= exact);
+            }
+        }
+
+        let mut dijkstra = Search::new(&burrow, false).without_heuristic();
+        assert_eq!(dijkstra.solve(state).unwrap().cost(), 12521);
     }
 
-    fn insert(&mut self, r#type: u8) {
-        let r#type = (r#type - b'A') as usize;
-        let index = self.indices[r#type];
-        self.positions[index] = self.position;
-        self.indices[r#type] += 1;
-        self.position += 1;
+    #[test]
+    fn rejects_malformed_diagrams() {
+        for diagram in [
+            "#####\n.....\n###A#\n  ###",
+            "#######\n#.....#\n###A#B#\n  #B#A#C#\n  #####",
+            "#######\n#.....#\n###A#C#\n  #B#A#\n  #####",
+            "#######\n#.....#\n###A#A#\n  #A#B#\n  #####",
+            "#######\n#..A..#\n###.#B#\n  #A#B#\n  #####",
+        ] {
+            assert!(solve(diagram).is_err(), "{:?} parsed", diagram);
+        }
     }
 }
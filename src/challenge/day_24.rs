@@ -1,4 +1,5 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
+use std::collections::HashSet;
 use std::str::FromStr;
 
 const REGISTER_COUNT: usize = 4;
@@ -48,7 +49,6 @@ const MAX_DIGIT_RANGE: [isize; 9] = [9, 8, 7, 6, 5, 4, 3, 2, 1];
 ///     acc
 /// }
 /// ```
-
 pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
     solve(&MAX_DIGIT_RANGE, input)
 }
@@ -57,17 +57,240 @@ pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
     solve(&MIN_DIGIT_RANGE, input)
 }
 
+/// Runs the program on the given inputs and prints the registers it ends with.
+///
+/// A single argument is read as a stream of digits (like a model number), while several arguments
+/// are read as one input each.
+pub fn run(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let instructions = parse(input)?;
+
+    let inputs = match args {
+        [] => return Err(anyhow!("Missing inputs, expecting `tool 24 run <digits>`")),
+        [digits] => digits
+            .chars()
+            .map(|char| {
+                char.to_digit(10)
+                    .map(|digit| digit as isize)
+                    .with_context(|| format!("{} is not a digit", char))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?,
+        inputs => inputs
+            .iter()
+            .map(|input| Ok(input.parse()?))
+            .collect::<anyhow::Result<Vec<_>>>()?,
+    };
+
+    let mut vm = VirtualMachine::new(&inputs);
+    vm.run(&instructions)?;
+
+    for (index, value) in vm.registers.iter().enumerate() {
+        println!("{} = {}", Register(index as u8), value);
+    }
+
+    Ok(())
+}
+
+/// Splits MONAD into its blocks and prints the three parameters that differ between them, along
+/// with the constraint each pair of blocks puts on the model number.
+pub fn disassemble(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    let instructions = parse(input)?;
+    let blocks = parse_blocks(&instructions)?;
+
+    println!("block kind  div add-x add-y");
+
+    for (index, block) in blocks.iter().enumerate() {
+        let kind = if block.div > 1 { "pop" } else { "push" };
+
+        println!(
+            "{:>5} {:<4} {:>4} {:>5} {:>5}",
+            index, kind, block.div, block.add_x, block.add_y
+        );
+    }
+
+    let pairs = find_pairs(&blocks).context("Blocks don't pop and push in pairs")?;
+    println!();
+
+    for (push, pop) in pairs {
+        let difference = blocks[push].add_y + blocks[pop].add_x;
+        println!("digit {} = digit {} {:+}", pop, push, difference);
+    }
+
+    Ok(())
+}
+
+/// Finds the largest and smallest accepted model numbers without assuming anything about the
+/// structure of the program.
+pub fn search(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    let instructions = parse(input)?;
+    let program = Program::compile(&instructions);
+
+    println!(
+        "compiled {} instructions into {} operations",
+        instructions.len(),
+        program.len()
+    );
+
+    println!(
+        "largest: {}",
+        find_model_number(&instructions, &MAX_DIGIT_RANGE)?
+    );
+    println!(
+        "smallest: {}",
+        find_model_number(&instructions, &MIN_DIGIT_RANGE)?
+    );
+    Ok(())
+}
+
+/// Prints the program the way the search runs it, after folding constants and removing dead code.
+pub fn compile(input: &[&str], _: &[String]) -> anyhow::Result<()> {
+    print!("{}", Program::compile(&parse(input)?));
+    Ok(())
+}
+
+/// Runs a model number through MONAD block by block, printing `z` as the stack it represents, and
+/// compares the verdict with the one given by the pairs of blocks.
+pub fn check(input: &[&str], args: &[String]) -> anyhow::Result<()> {
+    let instructions = parse(input)?;
+    let number = args
+        .first()
+        .context("Missing model number, expecting `tool 24 check <number>`")?;
+
+    let digits = number
+        .chars()
+        .map(|char| match char.to_digit(10) {
+            Some(digit @ 1..=9) => Ok(digit as isize),
+            _ => Err(anyhow!("{} is not a digit from 1 to 9", char)),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if digits.len() != DIGIT_COUNT {
+        return Err(anyhow!(
+            "Model numbers have {} digits, {} has {}",
+            DIGIT_COUNT,
+            number,
+            digits.len()
+        ));
+    }
+
+    let check = check_model_number(&instructions, &digits)?;
+
+    for (block, z) in check.z.iter().enumerate() {
+        println!("block {:>2}: z = {:<12} stack = {:?}", block, z, stack(*z));
+    }
+
+    println!(
+        "{}",
+        if check.accepted {
+            "accepted"
+        } else {
+            "rejected"
+        }
+    );
+
+    for (push, pop) in &check.mismatched {
+        println!("digits {} and {} don't match", push, pop);
+    }
+
+    if check.mismatched.is_empty() != check.accepted {
+        eprintln!("Pairs of blocks disagree with running the whole program");
+    }
+
+    println!("accepted model numbers: {}", check.count);
+    Ok(())
+}
+
+/// Outcome of running a model number through MONAD, along with the verdict of the pairs of blocks.
+struct Check {
+    /// Value of `z` after each block.
+    z: Vec<isize>,
+    accepted: bool,
+    /// Pushing and popping blocks that reject the digits they read, which should only be empty
+    /// for accepted model numbers.
+    mismatched: Vec<(usize, usize)>,
+    /// Model numbers accepted by every pair of blocks.
+    count: u64,
+}
+
+fn check_model_number(instructions: &[Instruction], digits: &[isize]) -> anyhow::Result<Check> {
+    let mut vm = VirtualMachine::new(digits);
+    let mut z = Vec::with_capacity(digits.len());
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        vm.execute(instruction)
+            .map_err(|fault| ExecutionError { index, fault })?;
+
+        let block_end = match instructions.get(index + 1) {
+            Some(next) => matches!(next, Instruction::Input(_)),
+            None => true,
+        };
+
+        if block_end {
+            z.push(vm.read(REGISTER_Z));
+        }
+    }
+
+    let blocks = parse_blocks(instructions)?;
+    let pairs = find_pairs(&blocks).context("Blocks don't pop and push in pairs")?;
+    let chunks = instructions
+        .chunks_exact(BLOCK_TEMPLATE.len())
+        .collect::<Vec<_>>();
+
+    let mut mismatched = Vec::new();
+    let mut count = 1u64;
+
+    for (push, pop) in pairs {
+        let (add_block, reduce_block) = (chunks[push], chunks[pop]);
+
+        if !pair_accepts(add_block, reduce_block, [digits[push], digits[pop]]) {
+            mismatched.push((push, pop));
+        }
+
+        count *= (1..=9)
+            .flat_map(|a| (1..=9).map(move |b| [a, b]))
+            .filter(|&inputs| pair_accepts(add_block, reduce_block, inputs))
+            .count() as u64;
+    }
+
+    Ok(Check {
+        z,
+        accepted: vm.read(REGISTER_Z) == 0,
+        mismatched,
+        count,
+    })
+}
+
+/// Splits `z` into the base 26 digits it stores, from the bottom of the stack to the top.
+fn stack(z: isize) -> Vec<isize> {
+    let mut stack = Vec::new();
+    let mut z = z;
+
+    while z > 0 {
+        stack.push(z % 26);
+        z /= 26;
+    }
+
+    stack.reverse();
+    stack
+}
+
 fn solve(digit_rage: &[isize; 9], input: &[&str]) -> anyhow::Result<u64> {
-    let instructions = input
-        .iter()
-        .map(|line| line.parse())
-        .collect::<Result<Vec<Instruction>, _>>()?;
+    let instructions = parse(input)?;
+
+    // the pairing shortcut only works for programs that look like MONAD
+    let pairs = match parse_blocks(&instructions) {
+        Ok(blocks) => find_pairs(&blocks),
+        Err(_) => None,
+    };
+
+    let pairs = match pairs {
+        Some(pairs) => pairs,
+        None => return find_model_number(&instructions, digit_rage),
+    };
 
     let blocks = instructions
         .chunks_exact(instructions.len() / DIGIT_COUNT)
         .collect::<Vec<_>>();
 
-    let pairs = find_pairs(&blocks);
     let mut digits = [0; DIGIT_COUNT];
 
     for pair in pairs {
@@ -77,24 +300,25 @@ fn solve(digit_rage: &[isize; 9], input: &[&str]) -> anyhow::Result<u64> {
     Ok(digits.iter().fold(0, |acc, digit| acc * 10 + *digit as u64))
 }
 
-fn find_pairs(blocks: &[&[Instruction]]) -> Vec<(usize, usize)> {
+/// Matches each block popping from the stack with the block that pushed the value it pops.
+fn find_pairs(blocks: &[Block]) -> Option<Vec<(usize, usize)>> {
     let mut pairs = Vec::with_capacity(DIGIT_COUNT / 2);
     let mut stack = Vec::with_capacity(DIGIT_COUNT / 2);
 
     for (i, block) in blocks.iter().enumerate() {
-        let reduce_block = block
-            .iter()
-            .any(|i| matches!(i, Instruction::Divide(REGISTER_Z, Value::Literal(div)) if *div > 1));
-
-        if reduce_block {
-            pairs.push((stack.pop().unwrap(), i));
+        if block.div > 1 {
+            pairs.push((stack.pop()?, i));
         } else {
             stack.push(i);
         }
     }
 
+    if !stack.is_empty() {
+        return None;
+    }
+
     pairs.sort_unstable();
-    pairs
+    Some(pairs)
 }
 
 fn solve_pair(
@@ -108,13 +332,7 @@ fn solve_pair(
 
     for &add_value in digit_rage {
         for &reduce_value in digit_rage {
-            let inputs = [add_value, reduce_value];
-            let mut vm = VirtualMachine::new(&inputs);
-
-            add_block.iter().for_each(|i| vm.execute(i));
-            reduce_block.iter().for_each(|i| vm.execute(i));
-
-            if vm.read(REGISTER_Z) != 0 {
+            if !pair_accepts(add_block, reduce_block, [add_value, reduce_value]) {
                 continue;
             }
 
@@ -125,7 +343,635 @@ fn solve_pair(
     }
 }
 
-#[derive(Eq, PartialEq)]
+/// Checks whether a pushing block and the block popping its value leave `z` unchanged.
+fn pair_accepts(
+    add_block: &[Instruction],
+    reduce_block: &[Instruction],
+    inputs: [isize; 2],
+) -> bool {
+    let mut vm = VirtualMachine::new(&inputs);
+
+    vm.run(add_block).is_ok() && vm.run(reduce_block).is_ok() && vm.read(REGISTER_Z) == 0
+}
+
+/// Finds the first accepted model number when trying digits in the given order.
+///
+/// The program is compiled first, so each digit only runs the operations that matter. Before
+/// trying the digits for an input, the rest of the program is run on intervals to check whether
+/// `z` can still end up at 0, and states that led nowhere are remembered so they're never tried
+/// again.
+fn find_model_number(
+    instructions: &[Instruction],
+    digit_range: &[isize; 9],
+) -> anyhow::Result<u64> {
+    let program = Program::compile(instructions);
+    let mut registers = [0; REGISTER_COUNT];
+
+    for op in &program.prelude {
+        op.execute(&mut registers)?;
+    }
+
+    let mut dead_ends = HashSet::new();
+    let mut digits = Vec::with_capacity(program.blocks.len());
+
+    if !find_digits(
+        &program,
+        0,
+        registers,
+        digit_range,
+        &mut dead_ends,
+        &mut digits,
+    ) {
+        return Err(anyhow!("No model number is accepted"));
+    }
+
+    Ok(digits.iter().fold(0, |acc, digit| acc * 10 + *digit as u64))
+}
+
+fn find_digits(
+    program: &Program,
+    block: usize,
+    registers: [isize; REGISTER_COUNT],
+    digit_range: &[isize; 9],
+    dead_ends: &mut HashSet<(usize, [isize; REGISTER_COUNT])>,
+    digits: &mut Vec<isize>,
+) -> bool {
+    if block == program.blocks.len() {
+        return registers[REGISTER_Z.index()] == 0;
+    }
+
+    let state = (block, program.blocks[block].state(registers));
+
+    if dead_ends.contains(&state) {
+        return false;
+    }
+
+    let reachable = match program.bounds(block, registers) {
+        Some(bounds) => bounds[REGISTER_Z.index()].contains(0),
+        None => false,
+    };
+
+    if reachable {
+        for &digit in digit_range {
+            let mut registers = registers;
+
+            // digits that crash the ALU are never accepted
+            if program.blocks[block].run(&mut registers, digit).is_err() {
+                continue;
+            }
+
+            digits.push(digit);
+
+            if find_digits(
+                program,
+                block + 1,
+                registers,
+                digit_range,
+                dead_ends,
+                digits,
+            ) {
+                return true;
+            }
+
+            digits.pop();
+        }
+    }
+
+    dead_ends.insert(state);
+    false
+}
+
+/// Instruction of a compiled program.
+#[derive(Copy, Clone)]
+enum Op {
+    Input(Register),
+    /// Copies a value into a register, replacing instructions like `mul x 0` and `add x z`.
+    Set(Register, Value),
+    Apply(Operation, Register, Value),
+}
+
+impl Op {
+    fn execute(&self, registers: &mut [isize; REGISTER_COUNT]) -> Result<(), Fault> {
+        let evaluate = |value: &Value| match value {
+            Value::Register(register) => registers[register.index()],
+            Value::Literal(value) => *value,
+        };
+
+        match self {
+            Op::Input(_) => unreachable!("Inputs start a new block"),
+            Op::Set(dst, value) => registers[dst.index()] = evaluate(value),
+            Op::Apply(operation, dst, value) => {
+                let value = evaluate(value);
+                registers[dst.index()] = operation.apply(registers[dst.index()], value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn dst(&self) -> Register {
+        match self {
+            Op::Input(dst) | Op::Set(dst, _) | Op::Apply(_, dst, _) => *dst,
+        }
+    }
+
+    /// Whether the operation is needed even when its result isn't, as it might crash the ALU when
+    /// the registers hold values within `bounds`.
+    fn may_fault(&self, bounds: &[Interval; REGISTER_COUNT]) -> bool {
+        match self {
+            Op::Apply(operation, dst, value) => {
+                operation.may_fault(bounds[dst.index()], value.bounds(bounds))
+            }
+            _ => false,
+        }
+    }
+
+    /// Bounds the value the operation writes, or `None` when it's sure to fault.
+    fn bounds(&self, bounds: &[Interval; REGISTER_COUNT]) -> Option<Interval> {
+        match self {
+            Op::Input(_) => Some(Interval::new(1, 9)),
+            Op::Set(_, value) => Some(value.bounds(bounds)),
+            Op::Apply(operation, dst, value) => {
+                operation.bounds(bounds[dst.index()], value.bounds(bounds))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Input(dst) => write!(f, "inp {}", dst),
+            Op::Set(dst, value) => write!(f, "set {} {}", dst, value),
+            Op::Apply(operation, dst, value) => write!(f, "{} {} {}", operation, dst, value),
+        }
+    }
+}
+
+/// The operations following an `inp` instruction, up to the next one.
+struct CompiledBlock {
+    input: Register,
+    ops: Vec<Op>,
+    /// Registers whose value is read before being written, which is all a block depends on.
+    live: [bool; REGISTER_COUNT],
+}
+
+impl CompiledBlock {
+    fn run(&self, registers: &mut [isize; REGISTER_COUNT], digit: isize) -> Result<(), Fault> {
+        registers[self.input.index()] = digit;
+
+        for op in &self.ops {
+            op.execute(registers)?;
+        }
+
+        Ok(())
+    }
+
+    /// Clears the registers the block doesn't depend on, so equivalent states compare equal.
+    fn state(&self, registers: [isize; REGISTER_COUNT]) -> [isize; REGISTER_COUNT] {
+        let mut state = registers;
+
+        for (value, live) in state.iter_mut().zip(self.live) {
+            if !live {
+                *value = 0;
+            }
+        }
+
+        state
+    }
+}
+
+/// An ALU program specialised for finding model numbers, where only `z` matters at the end.
+struct Program {
+    /// Operations before the first `inp` instruction.
+    prelude: Vec<Op>,
+    blocks: Vec<CompiledBlock>,
+}
+
+impl Program {
+    fn compile(instructions: &[Instruction]) -> Self {
+        let ops = Program::fold_constants(instructions);
+        let ops = Program::eliminate_dead_code(ops);
+
+        let mut blocks = Vec::new();
+        let mut prelude = Vec::new();
+
+        for op in ops {
+            match op {
+                Op::Input(input) => blocks.push(CompiledBlock {
+                    input,
+                    ops: Vec::new(),
+                    live: [false; REGISTER_COUNT],
+                }),
+                op => match blocks.last_mut() {
+                    Some(block) => block.ops.push(op),
+                    None => prelude.push(op),
+                },
+            }
+        }
+
+        for block in &mut blocks {
+            block.live = live_registers(&block.ops, block.input);
+        }
+
+        Program { prelude, blocks }
+    }
+
+    /// Computes everything that doesn't depend on the inputs ahead of time, replacing reads of
+    /// registers with a known value by literals.
+    fn fold_constants(instructions: &[Instruction]) -> Vec<Op> {
+        let mut known = [Some(0); REGISTER_COUNT];
+        let mut ops = Vec::with_capacity(instructions.len());
+
+        for instruction in instructions {
+            let (operation, dst, value) = match instruction.operation() {
+                Some(operation) => operation,
+                None => {
+                    known[instruction.dst().index()] = None;
+                    ops.push(Op::Input(instruction.dst()));
+                    continue;
+                }
+            };
+
+            let value = match *value {
+                Value::Register(register) => match known[register.index()] {
+                    Some(literal) => Value::Literal(literal),
+                    None => Value::Register(register),
+                },
+                literal => literal,
+            };
+
+            let current = known[dst.index()];
+            known[dst.index()] = None;
+
+            match (current, operation, value) {
+                (Some(a), _, Value::Literal(b)) => match operation.apply(a, b) {
+                    Ok(result) => known[dst.index()] = Some(result),
+                    // keep the fault for when the program runs
+                    Err(_) => ops.extend([
+                        Op::Set(dst, Value::Literal(a)),
+                        Op::Apply(operation, dst, value),
+                    ]),
+                },
+                (_, Operation::Multiply, Value::Literal(0)) => known[dst.index()] = Some(0),
+                (Some(0), Operation::Multiply, _) => known[dst.index()] = Some(0),
+                (None, Operation::Add, Value::Literal(0))
+                | (None, Operation::Multiply | Operation::Divide, Value::Literal(1)) => {
+                    known[dst.index()] = current
+                }
+                (Some(0), Operation::Add, _) | (Some(1), Operation::Multiply, _) => {
+                    ops.push(Op::Set(dst, value))
+                }
+                (Some(a), _, _) if operation.commutative() => ops.extend([
+                    Op::Set(dst, value),
+                    Op::Apply(operation, dst, Value::Literal(a)),
+                ]),
+                (Some(a), _, _) => ops.extend([
+                    Op::Set(dst, Value::Literal(a)),
+                    Op::Apply(operation, dst, value),
+                ]),
+                (None, _, _) => ops.push(Op::Apply(operation, dst, value)),
+            }
+        }
+
+        // registers that ended up with a known value still need to hold it
+        for (index, value) in known.iter().enumerate() {
+            if let Some(value) = value {
+                ops.push(Op::Set(Register(index as u8), Value::Literal(*value)));
+            }
+        }
+
+        ops
+    }
+
+    /// Removes operations whose result is overwritten before being read, or never read at all,
+    /// unless the bounds of the registers leave room for them to fault.
+    fn eliminate_dead_code(ops: Vec<Op>) -> Vec<Op> {
+        let mut bounds = [Interval::exact(0); REGISTER_COUNT];
+
+        let may_fault = ops
+            .iter()
+            .map(|op| {
+                let may_fault = op.may_fault(&bounds);
+                // after a sure fault nothing runs, so any bounds will do
+                bounds[op.dst().index()] = op.bounds(&bounds).unwrap_or(Interval::ANY);
+                may_fault
+            })
+            .collect::<Vec<_>>();
+
+        let mut live = [false; REGISTER_COUNT];
+        live[REGISTER_Z.index()] = true;
+
+        let mut kept = Vec::with_capacity(ops.len());
+
+        for (op, may_fault) in ops.into_iter().zip(may_fault).rev() {
+            let dst = op.dst().index();
+
+            if !live[dst] && !may_fault && !matches!(op, Op::Input(_)) {
+                continue;
+            }
+
+            match op {
+                Op::Input(_) => live[dst] = false,
+                Op::Set(_, value) => {
+                    live[dst] = false;
+
+                    if let Value::Register(register) = value {
+                        live[register.index()] = true;
+                    }
+                }
+                Op::Apply(_, _, value) => {
+                    live[dst] = true;
+
+                    if let Value::Register(register) = value {
+                        live[register.index()] = true;
+                    }
+                }
+            }
+
+            kept.push(op);
+        }
+
+        kept.reverse();
+        kept
+    }
+
+    fn len(&self) -> usize {
+        self.prelude.len()
+            + self
+                .blocks
+                .iter()
+                .map(|block| block.ops.len() + 1)
+                .sum::<usize>()
+    }
+
+    /// Bounds the registers after running the blocks starting at `block` on any digits.
+    ///
+    /// Returns `None` when the program is sure to divide by zero or take the modulo of a negative
+    /// number, which the puzzle considers invalid.
+    fn bounds(
+        &self,
+        block: usize,
+        registers: [isize; REGISTER_COUNT],
+    ) -> Option<[Interval; REGISTER_COUNT]> {
+        let mut bounds = registers.map(Interval::exact);
+
+        for block in &self.blocks[block..] {
+            bounds[block.input.index()] = Interval::new(1, 9);
+
+            for op in &block.ops {
+                bounds[op.dst().index()] = op.bounds(&bounds)?;
+            }
+        }
+
+        Some(bounds)
+    }
+}
+
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for op in &self.prelude {
+            writeln!(f, "{}", op)?;
+        }
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            let live = (0..REGISTER_COUNT)
+                .filter(|&register| block.live[register])
+                .map(|register| Register(register as u8).to_string())
+                .collect::<Vec<_>>();
+
+            writeln!(f, "; block {}, depends on [{}]", index, live.join(", "))?;
+            writeln!(f, "{}", Op::Input(block.input))?;
+
+            for op in &block.ops {
+                writeln!(f, "{}", op)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the registers read before being written by a block, which starts by reading `input`.
+fn live_registers(ops: &[Op], input: Register) -> [bool; REGISTER_COUNT] {
+    let mut live = [false; REGISTER_COUNT];
+    let mut written = [false; REGISTER_COUNT];
+    written[input.index()] = true;
+
+    for op in ops {
+        let (dst, value) = match op {
+            Op::Input(dst) => (*dst, None),
+            Op::Set(dst, value) => (*dst, Some(value)),
+            Op::Apply(_, dst, value) => {
+                if !written[dst.index()] {
+                    live[dst.index()] = true;
+                }
+
+                (*dst, Some(value))
+            }
+        };
+
+        if let Some(Value::Register(register)) = value {
+            if !written[register.index()] {
+                live[register.index()] = true;
+            }
+        }
+
+        written[dst.index()] = true;
+    }
+
+    live
+}
+
+/// Instructions each MONAD block consists of, where `_` marks a literal that differs between blocks.
+const BLOCK_TEMPLATE: [&str; 18] = [
+    "inp w", "mul x 0", "add x z", "mod x 26", "div z _", "add x _", "eql x w", "eql x 0",
+    "mul y 0", "add y 25", "mul y x", "add y 1", "mul z y", "mul y 0", "add y w", "add y _",
+    "mul y x", "add z y",
+];
+
+/// Parameters of a MONAD block, as described at the top of this file.
+struct Block {
+    /// Either 1 for blocks pushing onto `z`, or 26 for blocks popping from it.
+    div: isize,
+    /// Added to the top of the stack and compared against the input.
+    add_x: isize,
+    /// Added to the input before it is pushed.
+    add_y: isize,
+}
+
+fn parse_blocks(instructions: &[Instruction]) -> anyhow::Result<Vec<Block>> {
+    if instructions.len() != BLOCK_TEMPLATE.len() * DIGIT_COUNT {
+        return Err(anyhow!(
+            "Expected {} instructions, found {}",
+            BLOCK_TEMPLATE.len() * DIGIT_COUNT,
+            instructions.len()
+        ));
+    }
+
+    let mut blocks = Vec::with_capacity(DIGIT_COUNT);
+
+    for (index, block) in instructions.chunks_exact(BLOCK_TEMPLATE.len()).enumerate() {
+        let mut parameters = Vec::with_capacity(3);
+
+        for (offset, (instruction, pattern)) in block.iter().zip(BLOCK_TEMPLATE).enumerate() {
+            let instruction = instruction.to_string();
+
+            let parameter = pattern
+                .strip_suffix('_')
+                .and_then(|prefix| instruction.strip_prefix(prefix))
+                .map(|literal| literal.parse::<isize>());
+
+            match parameter {
+                Some(Ok(parameter)) => parameters.push(parameter),
+                _ if instruction == pattern => {}
+                _ => {
+                    return Err(anyhow!(
+                        "Instruction {} of block {} is `{}`, expected `{}`",
+                        offset,
+                        index,
+                        instruction,
+                        pattern
+                    ))
+                }
+            }
+        }
+
+        blocks.push(Block {
+            div: parameters[0],
+            add_x: parameters[1],
+            add_y: parameters[2],
+        });
+    }
+
+    Ok(blocks)
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<Vec<Instruction>> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            line.parse()
+                .with_context(|| format!("Invalid instruction on line {}", index + 1))
+        })
+        .collect()
+}
+
+/// Inclusive range of values a register might hold.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Interval {
+    min: isize,
+    max: isize,
+}
+
+impl Interval {
+    const ANY: Interval = Interval {
+        min: isize::MIN,
+        max: isize::MAX,
+    };
+
+    fn new(min: isize, max: isize) -> Self {
+        Interval { min, max }
+    }
+
+    fn exact(value: isize) -> Self {
+        Interval::new(value, value)
+    }
+
+    fn contains(&self, value: isize) -> bool {
+        self.min <= value && value <= self.max
+    }
+
+    fn hull(self, other: Interval) -> Self {
+        Interval::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    fn add(self, other: Interval) -> Self {
+        Interval::new(
+            self.min.saturating_add(other.min),
+            self.max.saturating_add(other.max),
+        )
+    }
+
+    fn mul(self, other: Interval) -> Self {
+        let products = [
+            self.min.saturating_mul(other.min),
+            self.min.saturating_mul(other.max),
+            self.max.saturating_mul(other.min),
+            self.max.saturating_mul(other.max),
+        ];
+
+        Interval::new(
+            *products.iter().min().unwrap(),
+            *products.iter().max().unwrap(),
+        )
+    }
+
+    /// Truncating division, skipping a divisor of 0.
+    fn div(self, other: Interval) -> Option<Self> {
+        // division is monotonic on either side of 0, so the corners bound the result
+        let negative = Interval::new(other.min, other.max.min(-1));
+        let positive = Interval::new(other.min.max(1), other.max);
+
+        [negative, positive]
+            .into_iter()
+            .filter(|divisor| divisor.min <= divisor.max)
+            .map(|divisor| {
+                let quotients = [
+                    self.min.saturating_div(divisor.min),
+                    self.min.saturating_div(divisor.max),
+                    self.max.saturating_div(divisor.min),
+                    self.max.saturating_div(divisor.max),
+                ];
+
+                Interval::new(
+                    *quotients.iter().min().unwrap(),
+                    *quotients.iter().max().unwrap(),
+                )
+            })
+            .reduce(Interval::hull)
+    }
+
+    /// Remainder, skipping negative dividends and divisors that aren't positive.
+    fn rem(self, other: Interval) -> Option<Self> {
+        let dividend = Interval::new(self.min.max(0), self.max);
+        let divisor = Interval::new(other.min.max(1), other.max);
+
+        if dividend.min > dividend.max || divisor.min > divisor.max {
+            return None;
+        }
+
+        if dividend.max < divisor.min {
+            return Some(dividend);
+        }
+
+        // a range narrower than the divisor that doesn't wrap around maps onto a single range
+        if divisor.min == divisor.max
+            && dividend.max - dividend.min < divisor.min
+            && dividend.min % divisor.min <= dividend.max % divisor.min
+        {
+            return Some(Interval::new(
+                dividend.min % divisor.min,
+                dividend.max % divisor.min,
+            ));
+        }
+
+        Some(Interval::new(0, dividend.max.min(divisor.max - 1)))
+    }
+
+    fn eql(self, other: Interval) -> Self {
+        if self.min == self.max && self == other {
+            Interval::exact(1)
+        } else if self.max < other.min || other.max < self.min {
+            Interval::exact(0)
+        } else {
+            Interval::new(0, 1)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
 struct Register(u8);
 
 impl Register {
@@ -134,29 +980,60 @@ impl Register {
     }
 }
 
-impl From<u8> for Register {
-    fn from(char: u8) -> Self {
-        Register(char - b'w')
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", (b'w' + self.0) as char)
+    }
+}
+
+impl FromStr for Register {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.as_bytes() {
+            [byte @ b'w'..=b'z'] => Ok(Register(byte - b'w')),
+            _ => Err(anyhow!("`{}` is not a register", input)),
+        }
     }
 }
 
+#[derive(Copy, Clone)]
 enum Value {
     Register(Register),
     Literal(isize),
 }
 
+impl Value {
+    fn bounds(&self, bounds: &[Interval; REGISTER_COUNT]) -> Interval {
+        match self {
+            Value::Register(register) => bounds[register.index()],
+            Value::Literal(value) => Interval::exact(*value),
+        }
+    }
+}
+
 impl FromStr for Value {
     type Err = anyhow::Error;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let byte = input.as_bytes()[0];
+        if let Ok(register) = input.parse() {
+            return Ok(Value::Register(register));
+        }
 
-        let value = match byte {
-            b'w'..=b'z' => Value::Register(byte.into()),
-            _ => Value::Literal(input.parse()?),
-        };
+        let literal = input
+            .parse()
+            .with_context(|| format!("`{}` is neither a register nor a number", input))?;
 
-        Ok(value)
+        Ok(Value::Literal(literal))
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Register(register) => write!(f, "{}", register),
+            Value::Literal(value) => write!(f, "{}", value),
+        }
     }
 }
 
@@ -173,22 +1050,147 @@ impl FromStr for Instruction {
     type Err = anyhow::Error;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let dst = input.as_bytes()[4].into();
+        let mut parts = input.split_whitespace();
+        let name = parts.next().context("Empty instruction")?;
+
+        let dst = parts
+            .next()
+            .with_context(|| format!("Missing register for {}", name))?
+            .parse()?;
 
-        let instruction = match &input[0..3] {
+        let mut value = || -> anyhow::Result<Value> {
+            parts
+                .next()
+                .with_context(|| format!("Missing value for {}", name))?
+                .parse()
+        };
+
+        let instruction = match name {
             "inp" => Instruction::Input(dst),
-            "add" => Instruction::Add(dst, input[6..].parse()?),
-            "mul" => Instruction::Multiply(dst, input[6..].parse()?),
-            "div" => Instruction::Divide(dst, input[6..].parse()?),
-            "mod" => Instruction::Modulo(dst, input[6..].parse()?),
-            "eql" => Instruction::Equals(dst, input[6..].parse()?),
+            "add" => Instruction::Add(dst, value()?),
+            "mul" => Instruction::Multiply(dst, value()?),
+            "div" => Instruction::Divide(dst, value()?),
+            "mod" => Instruction::Modulo(dst, value()?),
+            "eql" => Instruction::Equals(dst, value()?),
             unknown => return Err(anyhow!("Unknown instruction {}", unknown)),
         };
 
+        if let Some(extra) = parts.next() {
+            return Err(anyhow!("Unexpected `{}` after {}", extra, name));
+        }
+
         Ok(instruction)
     }
 }
 
+impl Instruction {
+    fn dst(&self) -> Register {
+        match self {
+            Instruction::Input(dst)
+            | Instruction::Add(dst, _)
+            | Instruction::Multiply(dst, _)
+            | Instruction::Divide(dst, _)
+            | Instruction::Modulo(dst, _)
+            | Instruction::Equals(dst, _) => *dst,
+        }
+    }
+
+    /// Splits the instruction into what it computes and its operands, or `None` for `inp`.
+    fn operation(&self) -> Option<(Operation, Register, &Value)> {
+        let (operation, dst, value) = match self {
+            Instruction::Input(_) => return None,
+            Instruction::Add(dst, value) => (Operation::Add, dst, value),
+            Instruction::Multiply(dst, value) => (Operation::Multiply, dst, value),
+            Instruction::Divide(dst, value) => (Operation::Divide, dst, value),
+            Instruction::Modulo(dst, value) => (Operation::Modulo, dst, value),
+            Instruction::Equals(dst, value) => (Operation::Equals, dst, value),
+        };
+
+        Some((operation, *dst, value))
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.operation() {
+            None => write!(f, "inp {}", self.dst()),
+            Some((operation, dst, value)) => write!(f, "{} {} {}", operation, dst, value),
+        }
+    }
+}
+
+/// Arithmetic the ALU can do, shared by the interpreter, the interval analysis and compiled programs.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Operation {
+    Add,
+    Multiply,
+    Divide,
+    Modulo,
+    Equals,
+}
+
+impl Operation {
+    fn apply(self, a: isize, b: isize) -> Result<isize, Fault> {
+        match self {
+            Operation::Add => a.checked_add(b).ok_or(Fault::Overflow),
+            Operation::Multiply => a.checked_mul(b).ok_or(Fault::Overflow),
+            Operation::Divide if b == 0 => Err(Fault::DivisionByZero),
+            Operation::Divide => a.checked_div(b).ok_or(Fault::Overflow),
+            Operation::Modulo if a < 0 || b <= 0 => Err(Fault::InvalidModulo(a, b)),
+            Operation::Modulo => Ok(a % b),
+            Operation::Equals => Ok((a == b) as isize),
+        }
+    }
+
+    /// Bounds the result for any values in `a` and `b`, or `None` when it's sure to fault.
+    fn bounds(self, a: Interval, b: Interval) -> Option<Interval> {
+        match self {
+            Operation::Add => Some(a.add(b)),
+            Operation::Multiply => Some(a.mul(b)),
+            Operation::Divide => a.div(b),
+            Operation::Modulo => a.rem(b),
+            Operation::Equals => Some(a.eql(b)),
+        }
+    }
+
+    /// Whether applying the operation to any values in `a` and `b` might fault.
+    fn may_fault(self, a: Interval, b: Interval) -> bool {
+        match self {
+            // both are monotonic in each operand, so the corners reach the furthest
+            Operation::Add => {
+                a.min.checked_add(b.min).is_none() || a.max.checked_add(b.max).is_none()
+            }
+            Operation::Multiply => [a.min, a.max]
+                .iter()
+                .any(|x| [b.min, b.max].iter().any(|y| x.checked_mul(*y).is_none())),
+            Operation::Divide => b.contains(0) || (a.contains(isize::MIN) && b.contains(-1)),
+            Operation::Modulo => a.min < 0 || b.min <= 0,
+            Operation::Equals => false,
+        }
+    }
+
+    fn commutative(self) -> bool {
+        matches!(
+            self,
+            Operation::Add | Operation::Multiply | Operation::Equals
+        )
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Add => "add",
+            Operation::Multiply => "mul",
+            Operation::Divide => "div",
+            Operation::Modulo => "mod",
+            Operation::Equals => "eql",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
 struct VirtualMachine<'a> {
     registers: [isize; REGISTER_COUNT],
     inputs: &'a [isize],
@@ -204,30 +1206,31 @@ impl<'a> VirtualMachine<'a> {
         self.registers[register.index()]
     }
 
-    fn execute(&mut self, instruction: &Instruction) {
-        match instruction {
-            Instruction::Input(dst) => {
-                self.registers[dst.index()] = self.inputs[0];
-                self.inputs = &self.inputs[1..];
-            }
-            Instruction::Add(dst, value) => {
-                self.registers[dst.index()] += self.evaluate(value);
-            }
-            Instruction::Multiply(dst, value) => {
-                self.registers[dst.index()] *= self.evaluate(value);
-            }
-            Instruction::Divide(dst, value) => {
-                self.registers[dst.index()] /= self.evaluate(value);
-            }
-            Instruction::Modulo(dst, value) => {
-                self.registers[dst.index()] %= self.evaluate(value);
+    /// Executes each instruction in turn, stopping at the first one that faults.
+    fn run(&mut self, instructions: &[Instruction]) -> Result<(), ExecutionError> {
+        for (index, instruction) in instructions.iter().enumerate() {
+            self.execute(instruction)
+                .map_err(|fault| ExecutionError { index, fault })?;
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, instruction: &Instruction) -> Result<(), Fault> {
+        match instruction.operation() {
+            None => {
+                let (&input, inputs) = self.inputs.split_first().ok_or(Fault::InputExhausted)?;
+                self.registers[instruction.dst().index()] = input;
+                self.inputs = inputs;
             }
-            Instruction::Equals(dst, value) => {
+            Some((operation, dst, value)) => {
                 let value = self.evaluate(value);
                 let dst = &mut self.registers[dst.index()];
-                *dst = (*dst == value) as isize
+                *dst = operation.apply(*dst, value)?;
             }
         }
+
+        Ok(())
     }
 
     fn evaluate(&self, value: &Value) -> isize {
@@ -237,3 +1240,233 @@ impl<'a> VirtualMachine<'a> {
         }
     }
 }
+
+/// Invalid states the puzzle says the ALU crashes in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Fault {
+    DivisionByZero,
+    /// Modulo of a negative number, or by a number that isn't positive.
+    InvalidModulo(isize, isize),
+    /// An `inp` instruction ran when there were no inputs left.
+    InputExhausted,
+    Overflow,
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::DivisionByZero => write!(f, "division by zero"),
+            Fault::InvalidModulo(a, b) => write!(f, "invalid modulo {} % {}", a, b),
+            Fault::InputExhausted => write!(f, "no input left to read"),
+            Fault::Overflow => write!(f, "arithmetic overflow"),
+        }
+    }
+}
+
+impl std::error::Error for Fault {}
+
+/// A fault along with the index of the instruction that caused it.
+#[derive(Debug)]
+struct ExecutionError {
+    index: usize,
+    fault: Fault,
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at instruction {}", self.fault, self.index)
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::Day;
+    use proptest::prelude::*;
+
+    /// An interval starting at or above `low`, along with a value inside it.
+    fn member_from(low: isize) -> impl Strategy<Value = (Interval, isize)> {
+        (low..100isize, 0..50isize).prop_flat_map(|(min, width)| {
+            (Just(Interval::new(min, min + width)), min..=min + width)
+        })
+    }
+
+    fn member() -> impl Strategy<Value = (Interval, isize)> {
+        member_from(-100)
+    }
+
+    fn instruction() -> impl Strategy<Value = String> {
+        let register = prop::sample::select(vec!["w", "x", "y", "z"]);
+        let value = prop_oneof![
+            register.clone().prop_map(str::to_string),
+            (-3..30isize).prop_map(|literal| literal.to_string()),
+            // large enough for a few multiplications to overflow
+            Just((1isize << 40).to_string()),
+        ];
+        let name = prop::sample::select(vec!["inp", "add", "mul", "div", "mod", "eql"]);
+
+        // dividing by registers faults too often to leave many programs to compare
+        (name, register, value, 1..30isize).prop_map(
+            |(name, register, value, divisor)| match name {
+                "inp" => format!("inp {}", register),
+                "div" | "mod" => format!("{} {} {}", name, register, divisor),
+                _ => format!("{} {} {}", name, register, value),
+            },
+        )
+    }
+
+    fn run(program: &str, inputs: &[isize]) -> Result<[isize; REGISTER_COUNT], ExecutionError> {
+        let instructions = parse(&program.lines().collect::<Vec<_>>()).unwrap();
+        let mut vm = VirtualMachine::new(inputs);
+        vm.run(&instructions).map(|_| vm.registers)
+    }
+
+    /// Runs the compiled program on the digits, returning the final value of `z`.
+    fn run_compiled(instructions: &[Instruction], digits: &[isize]) -> Result<isize, Fault> {
+        let program = Program::compile(instructions);
+        let mut registers = [0; REGISTER_COUNT];
+
+        for op in &program.prelude {
+            op.execute(&mut registers)?;
+        }
+
+        for (block, &digit) in program.blocks.iter().zip(digits) {
+            block.run(&mut registers, digit)?;
+        }
+
+        Ok(registers[REGISTER_Z.index()])
+    }
+
+    #[test]
+    fn reports_faults_with_their_instruction() {
+        let fault =
+            |program, inputs| run(program, inputs).map_err(|error| (error.index, error.fault));
+
+        assert_eq!(fault("inp x\nmul x 2", &[3]).unwrap(), [0, 6, 0, 0]);
+        assert_eq!(
+            fault("inp x\ndiv x y", &[3]).unwrap_err(),
+            (1, Fault::DivisionByZero)
+        );
+        assert_eq!(
+            fault("add z -1\nmod z 2", &[]).unwrap_err(),
+            (1, Fault::InvalidModulo(-1, 2))
+        );
+        assert_eq!(
+            fault("inp x\nmod x 0", &[3]).unwrap_err(),
+            (1, Fault::InvalidModulo(3, 0))
+        );
+        assert_eq!(
+            fault("inp x\ninp y", &[3]).unwrap_err(),
+            (1, Fault::InputExhausted)
+        );
+    }
+
+    #[test]
+    fn keeps_dead_operations_that_overflow() {
+        let program = "inp x\nadd y 9223372036854775807\nmul y x\nadd z x";
+        let instructions = parse(&program.lines().collect::<Vec<_>>()).unwrap();
+
+        assert_eq!(run_compiled(&instructions, &[1]), Ok(1));
+        assert_eq!(run_compiled(&instructions, &[2]), Err(Fault::Overflow));
+        assert_eq!(run(program, &[2]).unwrap_err().fault, Fault::Overflow);
+    }
+
+    #[test]
+    fn checks_model_numbers_against_pairs_of_blocks() {
+        let input = crate::generator::generate(Day::Day24, 0, None).unwrap();
+        let input = input.lines().collect::<Vec<_>>();
+        let instructions = parse(&input).unwrap();
+
+        let largest = part_a(&input).unwrap().to_string();
+        let digits = largest
+            .bytes()
+            .map(|digit| (digit - b'0') as isize)
+            .collect::<Vec<_>>();
+
+        let check = check_model_number(&instructions, &digits).unwrap();
+        assert!(check.accepted);
+        assert!(check.mismatched.is_empty());
+        assert_eq!(check.z.len(), DIGIT_COUNT);
+        assert_eq!(check.z.last(), Some(&0));
+        assert!(check.count >= 1);
+
+        // each digit is tied to another one, so changing a single digit breaks its pair
+        for index in 0..DIGIT_COUNT {
+            let mut changed = digits.clone();
+            changed[index] = changed[index] % 9 + 1;
+
+            let check = check_model_number(&instructions, &changed).unwrap();
+            assert!(!check.accepted);
+            assert_eq!(check.mismatched.len(), 1);
+            assert!(check.mismatched[0].0 == index || check.mismatched[0].1 == index);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_instructions() {
+        for line in [
+            "inp a",
+            "add x",
+            "add x 1 2",
+            "mul xy 2",
+            "eql x ?",
+            "nop x 1",
+            "",
+        ] {
+            assert!(line.parse::<Instruction>().is_err(), "{:?} parsed", line);
+        }
+
+        for line in [
+            "inp w", "add x -3", "mul y z", "div z 26", "mod x 26", "eql x w",
+        ] {
+            assert_eq!(line.parse::<Instruction>().unwrap().to_string(), line);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn compiled_programs_match_interpreter(
+            lines in prop::collection::vec(instruction(), 0..40),
+            digits in prop::collection::vec(1..=9isize, 40),
+        ) {
+            let instructions = parse(&lines.iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+            let mut vm = VirtualMachine::new(&digits);
+            let interpreted = vm.run(&instructions).map(|_| vm.read(REGISTER_Z));
+
+            // both should fault, but not necessarily at the same operation
+            prop_assert_eq!(run_compiled(&instructions, &digits).ok(), interpreted.ok());
+        }
+
+        #[test]
+        fn add_and_mul_contain_results((a, x) in member(), (b, y) in member()) {
+            prop_assert!(a.add(b).contains(x + y));
+            prop_assert!(a.mul(b).contains(x * y));
+        }
+
+        #[test]
+        fn div_contains_results((a, x) in member(), (b, y) in member()) {
+            prop_assume!(y != 0);
+            prop_assert!(a.div(b).unwrap().contains(x / y));
+        }
+
+        #[test]
+        fn rem_contains_results((a, x) in member_from(-10), (b, y) in member_from(-10)) {
+            prop_assume!(x >= 0 && y > 0);
+            prop_assert!(a.rem(b).unwrap().contains(x % y));
+        }
+
+        #[test]
+        fn eql_contains_results((a, x) in member(), (b, y) in member()) {
+            prop_assert!(a.eql(b).contains((x == y) as isize));
+        }
+
+        #[test]
+        fn invalid_operations_are_detected(a in -100..100isize, b in -100..0isize) {
+            prop_assert!(Interval::exact(a).div(Interval::exact(0)).is_none());
+            prop_assert!(Interval::exact(b).rem(Interval::exact(a.abs() + 1)).is_none());
+            prop_assert!(Interval::exact(a.abs()).rem(Interval::exact(b + 1)).is_none());
+        }
+    }
+}
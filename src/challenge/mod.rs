@@ -1,8 +1,10 @@
+use crate::options::{Format, Options};
 use anyhow::{anyhow, Context};
 use lazy_static::lazy_static;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::panic::AssertUnwindSafe;
 use std::str::FromStr;
 use std::time::Instant;
 
@@ -93,6 +95,97 @@ lazy_static! {
 
         solutions
     };
+    static ref REFERENCES: Solutions = {
+        use Day::*;
+        use Part::*;
+
+        let mut solutions = Solutions::new();
+
+        solutions.add(Day01, PartA, crate::reference::day_01::part_a);
+        solutions.add(Day01, PartB, crate::reference::day_01::part_b);
+        solutions.add(Day02, PartA, crate::reference::day_02::part_a);
+        solutions.add(Day02, PartB, crate::reference::day_02::part_b);
+        solutions.add(Day03, PartA, crate::reference::day_03::part_a);
+        solutions.add(Day03, PartB, crate::reference::day_03::part_b);
+        solutions.add(Day04, PartA, crate::reference::day_04::part_a);
+        solutions.add(Day04, PartB, crate::reference::day_04::part_b);
+        solutions.add(Day05, PartA, crate::reference::day_05::part_a);
+        solutions.add(Day05, PartB, crate::reference::day_05::part_b);
+        solutions.add(Day06, PartA, crate::reference::day_06::part_a);
+        solutions.add(Day06, PartB, crate::reference::day_06::part_b);
+        solutions.add(Day07, PartA, crate::reference::day_07::part_a);
+        solutions.add(Day07, PartB, crate::reference::day_07::part_b);
+        solutions.add(Day08, PartA, crate::reference::day_08::part_a);
+        solutions.add(Day08, PartB, crate::reference::day_08::part_b);
+        solutions.add(Day09, PartA, crate::reference::day_09::part_a);
+        solutions.add(Day09, PartB, crate::reference::day_09::part_b);
+        solutions.add(Day10, PartA, crate::reference::day_10::part_a);
+        solutions.add(Day10, PartB, crate::reference::day_10::part_b);
+        solutions.add(Day11, PartA, crate::reference::day_11::part_a);
+        solutions.add(Day11, PartB, crate::reference::day_11::part_b);
+        solutions.add(Day12, PartA, crate::reference::day_12::part_a);
+        solutions.add(Day12, PartB, crate::reference::day_12::part_b);
+        solutions.add(Day13, PartA, crate::reference::day_13::part_a);
+        solutions.add(Day13, PartB, crate::reference::day_13::part_b);
+        solutions.add(Day14, PartA, crate::reference::day_14::part_a);
+        solutions.add(Day14, PartB, crate::reference::day_14::part_b);
+        solutions.add(Day15, PartA, crate::reference::day_15::part_a);
+        solutions.add(Day15, PartB, crate::reference::day_15::part_b);
+        solutions.add(Day16, PartA, crate::reference::day_16::part_a);
+        solutions.add(Day16, PartB, crate::reference::day_16::part_b);
+        solutions.add(Day17, PartA, crate::reference::day_17::part_a);
+        solutions.add(Day17, PartB, crate::reference::day_17::part_b);
+        solutions.add(Day18, PartA, crate::reference::day_18::part_a);
+        solutions.add(Day18, PartB, crate::reference::day_18::part_b);
+        solutions.add(Day19, PartA, crate::reference::day_19::part_a);
+        solutions.add(Day19, PartB, crate::reference::day_19::part_b);
+        solutions.add(Day20, PartA, crate::reference::day_20::part_a);
+        solutions.add(Day20, PartB, crate::reference::day_20::part_b);
+        solutions.add(Day21, PartA, crate::reference::day_21::part_a);
+        solutions.add(Day21, PartB, crate::reference::day_21::part_b);
+        solutions.add(Day22, PartA, crate::reference::day_22::part_a);
+        solutions.add(Day22, PartB, crate::reference::day_22::part_b);
+        solutions.add(Day23, PartA, crate::reference::day_23::part_a);
+        solutions.add(Day23, PartB, crate::reference::day_23::part_b);
+        solutions.add(Day24, PartA, crate::reference::day_24::part_a);
+        solutions.add(Day24, PartB, crate::reference::day_24::part_b);
+        solutions.add(Day25, PartA, crate::reference::day_25::part_a);
+
+        solutions
+    };
+    static ref TOOLS: Tools = {
+        use Day::*;
+
+        let mut tools = Tools::new();
+
+        tools.add(Day06, "count", day_06::count);
+        tools.add(Day06, "series", day_06::series);
+        tools.add(Day12, "paths", day_12::paths);
+        tools.add(Day12, "dot", day_12::dot);
+        tools.add(Day14, "count", day_14::count);
+        tools.add(Day15, "path", day_15::path);
+        tools.add(Day15, "compare", day_15::compare);
+        tools.add(Day16, "print", day_16::print);
+        tools.add(Day16, "tree", day_16::tree);
+        tools.add(Day16, "encode", day_16::encode);
+        tools.add(Day16, "eval", day_16::eval);
+        tools.add(Day19, "align", day_19::align);
+        tools.add(Day19, "export", day_19::export);
+        tools.add(Day22, "run", day_22::run);
+        tools.add(Day22, "bench", day_22::bench);
+        tools.add(Day22, "volume", day_22::volume);
+        tools.add(Day23, "moves", day_23::moves);
+        tools.add(Day23, "animate", day_23::animate);
+        tools.add(Day23, "stats", day_23::stats);
+        tools.add(Day23, "check", day_23::check);
+        tools.add(Day24, "run", day_24::run);
+        tools.add(Day24, "disassemble", day_24::disassemble);
+        tools.add(Day24, "search", day_24::search);
+        tools.add(Day24, "check", day_24::check);
+        tools.add(Day24, "compile", day_24::compile);
+
+        tools
+    };
 }
 
 #[derive(
@@ -160,7 +253,13 @@ impl Day {
         }
     }
 
-    async fn input(&self) -> anyhow::Result<String> {
+    async fn input(&self, options: &Options) -> anyhow::Result<String> {
+        if let Some(path) = options.input() {
+            return tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Cannot read input from {}", path.display()));
+        }
+
         let index = u8::from(*self);
         crate::http::get(format!("https://adventofcode.com/2021/day/{}/input", index)).await
     }
@@ -250,22 +349,88 @@ impl std::fmt::Display for Challenge {
     }
 }
 
-pub async fn solve(challenge: &Challenge) -> anyhow::Result<()> {
-    SOLUTIONS.solve(challenge).await
+pub async fn solve(challenge: &Challenge, options: &Options) -> anyhow::Result<()> {
+    SOLUTIONS.solve(challenge, options).await
+}
+
+/// Handles `tool <day> <name> [args]`, running a debugging tool on the day's input.
+pub async fn run_tool(args: &[String], options: &Options) -> anyhow::Result<()> {
+    let day: Day = args
+        .first()
+        .context("Missing day, expecting `tool <day> <name> [args]`")?
+        .parse()?;
+
+    TOOLS
+        .run(
+            day,
+            args.get(1).map(String::as_str),
+            &args[2.min(args.len())..],
+            options,
+        )
+        .await
+}
+
+pub fn has_solution(challenge: &Challenge) -> bool {
+    SOLUTIONS.0.contains_key(challenge)
+}
+
+pub fn evaluate(challenge: &Challenge, input: &[&str]) -> anyhow::Result<String> {
+    SOLUTIONS.evaluate(challenge, input)
+}
+
+pub fn evaluate_reference(challenge: &Challenge, input: &[&str]) -> anyhow::Result<String> {
+    REFERENCES.evaluate(challenge, input)
 }
 
 trait Solution {
-    fn run(&self, challenge: &Challenge, input: &[&str]) -> anyhow::Result<()>;
+    fn run(&self, challenge: &Challenge, input: &[&str], options: &Options) -> anyhow::Result<()>;
+
+    fn evaluate(&self, input: &[&str]) -> anyhow::Result<String>;
 }
 
 impl<R: std::fmt::Display> Solution for fn(&[&str]) -> anyhow::Result<R> {
-    fn run(&self, challenge: &Challenge, input: &[&str]) -> anyhow::Result<()> {
+    fn run(&self, challenge: &Challenge, input: &[&str], options: &Options) -> anyhow::Result<()> {
         let start = Instant::now();
-        let result = self(input)?;
+        let (result, usage) = crate::alloc::measure(|| {
+            tracing::info_span!("solve", %challenge).in_scope(|| self(input))
+        });
+        let result = result?;
         let duration = start.elapsed();
-        println!("{}: {} (duration = {:?})", challenge, result, duration);
+
+        match (options.format(), usage) {
+            (Format::Human, None) => {
+                println!("{}: {} (duration = {:?})", challenge, result, duration)
+            }
+            (Format::Human, Some(usage)) => {
+                println!(
+                    "{}: {} (duration = {:?}, {})",
+                    challenge, result, duration, usage
+                )
+            }
+            (Format::Json, usage) => {
+                let report = serde_json::json!({
+                    "day": u8::from(challenge.0),
+                    "part": match challenge.1 {
+                        Part::PartA => "A",
+                        Part::PartB => "B",
+                    },
+                    "result": result.to_string(),
+                    "duration_ns": duration.as_nanos() as u64,
+                    "allocations": usage.map(|usage| usage.allocations),
+                    "peak_bytes": usage.map(|usage| usage.peak),
+                });
+
+                println!("{}", report);
+            }
+        }
+
+        crate::profile::report(options.format());
         Ok(())
     }
+
+    fn evaluate(&self, input: &[&str]) -> anyhow::Result<String> {
+        Ok(self(input)?.to_string())
+    }
 }
 
 struct Solutions(HashMap<Challenge, Box<dyn Solution + Sync + 'static>>);
@@ -284,13 +449,80 @@ impl Solutions {
         self.0.insert(Challenge::new(day, part), Box::new(func));
     }
 
-    async fn solve(&self, challenge: &Challenge) -> anyhow::Result<()> {
+    async fn solve(&self, challenge: &Challenge, options: &Options) -> anyhow::Result<()> {
+        let solution = self
+            .0
+            .get(challenge)
+            .with_context(|| format!("Cannot find solution for {}", challenge))?;
+
+        let input = challenge.0.input(options).await?;
+        solution.run(challenge, &input.lines().collect::<Vec<_>>(), options)
+    }
+
+    /// Runs the solution and formats its result, turning panics into errors.
+    fn evaluate(&self, challenge: &Challenge, input: &[&str]) -> anyhow::Result<String> {
         let solution = self
             .0
             .get(challenge)
             .with_context(|| format!("Cannot find solution for {}", challenge))?;
 
-        let input = challenge.0.input().await?;
-        solution.run(challenge, &input.lines().collect::<Vec<_>>())
+        match std::panic::catch_unwind(AssertUnwindSafe(|| solution.evaluate(input))) {
+            Ok(result) => result,
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+
+                Err(anyhow!("Panicked: {}", message))
+            }
+        }
+    }
+}
+
+type Tool = fn(&[&str], &[String]) -> anyhow::Result<()>;
+
+struct Tools(BTreeMap<(Day, &'static str), Tool>);
+
+impl Tools {
+    fn new() -> Self {
+        Tools(BTreeMap::new())
+    }
+
+    fn add(&mut self, day: Day, name: &'static str, tool: Tool) {
+        self.0.insert((day, name), tool);
+    }
+
+    async fn run(
+        &self,
+        day: Day,
+        name: Option<&str>,
+        args: &[String],
+        options: &Options,
+    ) -> anyhow::Result<()> {
+        let tool = name.and_then(|name| self.0.get(&(day, name)));
+
+        let tool = match tool {
+            Some(tool) => tool,
+            None => {
+                let names = self
+                    .0
+                    .keys()
+                    .filter(|(other, _)| *other == day)
+                    .map(|(_, name)| *name)
+                    .collect::<Vec<_>>();
+
+                return Err(anyhow!(
+                    "Unknown tool {} for {}, expecting one of [{}]",
+                    name.unwrap_or_default(),
+                    day,
+                    names.join(", ")
+                ));
+            }
+        };
+
+        let input = day.input(options).await?;
+        tool(&input.lines().collect::<Vec<_>>(), args)
     }
 }
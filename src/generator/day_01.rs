@@ -0,0 +1,16 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 2000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut depth = rng.gen_range(100..200u32);
+    let mut lines = Vec::with_capacity(size);
+
+    for _ in 0..size {
+        lines.push(depth.to_string());
+        depth = depth.saturating_add_signed(rng.gen_range(-20..=30)).max(1);
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,29 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 1000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut depth = 0;
+    let mut lines = Vec::with_capacity(size);
+
+    for _ in 0..size {
+        let amount = rng.gen_range(1..10);
+
+        let direction = match rng.gen_range(0..3) {
+            0 => "forward",
+            1 if depth >= amount => {
+                depth -= amount;
+                "up"
+            }
+            _ => {
+                depth += amount;
+                "down"
+            }
+        };
+
+        lines.push(format!("{} {}", direction, amount));
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,17 @@
+use super::Rng;
+use rand::seq::index::sample;
+
+pub const DEFAULT_SIZE: usize = 1000;
+
+const BIT_COUNT: usize = 12;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // an odd amount of distinct numbers avoids ties in the most common bit
+    let size = (size.clamp(1, 1 << BIT_COUNT) - 1) | 1;
+
+    sample(rng, 1 << BIT_COUNT, size)
+        .into_iter()
+        .map(|number| format!("{:0width$b}", number, width = BIT_COUNT))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,37 @@
+use super::Rng;
+use rand::seq::index::sample;
+use rand::seq::SliceRandom;
+
+pub const DEFAULT_SIZE: usize = 100;
+
+const BOARD_SIZE: usize = 5;
+const NUMBER_COUNT: usize = 100;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // drawing every number guarantees that every board wins eventually
+    let mut numbers = (0..NUMBER_COUNT).collect::<Vec<_>>();
+    numbers.shuffle(rng);
+
+    let numbers = numbers
+        .iter()
+        .map(|number| number.to_string())
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![numbers.join(",")];
+
+    for _ in 0..size.max(1) {
+        let board = sample(rng, NUMBER_COUNT, BOARD_SIZE * BOARD_SIZE).into_vec();
+        lines.push(String::new());
+
+        for row in board.chunks_exact(BOARD_SIZE) {
+            let row = row
+                .iter()
+                .map(|number| format!("{:2}", number))
+                .collect::<Vec<_>>();
+
+            lines.push(row.join(" "));
+        }
+    }
+
+    lines.join("\n")
+}
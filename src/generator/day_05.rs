@@ -0,0 +1,37 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 500;
+
+const MAP_SIZE: i32 = 1000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut lines = Vec::with_capacity(size);
+
+    while lines.len() < size {
+        let x1 = rng.gen_range(0..MAP_SIZE);
+        let y1 = rng.gen_range(0..MAP_SIZE);
+        let length = rng.gen_range(1..MAP_SIZE);
+
+        let (dx, dy) = match rng.gen_range(0..4) {
+            0 => (1, 0),
+            1 => (0, 1),
+            2 => (1, 1),
+            _ => (1, -1),
+        };
+
+        let (dx, dy) = if rng.gen_bool(0.5) {
+            (dx, dy)
+        } else {
+            (-dx, -dy)
+        };
+        let x2 = x1 + dx * length;
+        let y2 = y1 + dy * length;
+
+        if (0..MAP_SIZE).contains(&x2) && (0..MAP_SIZE).contains(&y2) {
+            lines.push(format!("{},{} -> {},{}", x1, y1, x2, y2));
+        }
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,11 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 300;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| rng.gen_range(1..=5).to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
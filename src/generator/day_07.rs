@@ -0,0 +1,17 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 1000;
+
+const MAX_POSITION: usize = 2000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // crabs tend to cluster close to the start, just like in the real input
+    (0..size.max(1))
+        .map(|_| {
+            let position = rng.gen_range(0..MAX_POSITION);
+            rng.gen_range(0..=position).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,45 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 200;
+
+const DIGITS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut lines = Vec::with_capacity(size);
+
+    for _ in 0..size {
+        let mut wiring = *b"abcdefg";
+        wiring.shuffle(rng);
+
+        let mut patterns = (0..DIGITS.len())
+            .map(|digit| scramble(rng, &wiring, digit))
+            .collect::<Vec<_>>();
+
+        patterns.shuffle(rng);
+
+        let outputs = (0..4)
+            .map(|_| {
+                let digit = rng.gen_range(0..DIGITS.len());
+                scramble(rng, &wiring, digit)
+            })
+            .collect::<Vec<_>>();
+
+        lines.push(format!("{} | {}", patterns.join(" "), outputs.join(" ")));
+    }
+
+    lines.join("\n")
+}
+
+fn scramble(rng: &mut Rng, wiring: &[u8; 7], digit: usize) -> String {
+    let mut segments = DIGITS[digit]
+        .bytes()
+        .map(|segment| wiring[(segment - b'a') as usize])
+        .collect::<Vec<_>>();
+
+    segments.shuffle(rng);
+    String::from_utf8(segments).unwrap()
+}
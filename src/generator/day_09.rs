@@ -0,0 +1,21 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 100;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            (0..size.max(1))
+                .map(|_| {
+                    if rng.gen_bool(0.3) {
+                        '9'
+                    } else {
+                        char::from(b'0' + rng.gen_range(0..9))
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
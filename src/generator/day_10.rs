@@ -0,0 +1,55 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 100;
+
+const OPENING: [u8; 4] = *b"([{<";
+const CLOSING: [u8; 4] = *b")]}>";
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // the middle score of incomplete lines only exists for an odd amount of them
+    let size = size.max(1);
+    let incomplete = (size / 2) | 1;
+
+    let mut lines = (0..size)
+        .map(|i| generate_line(rng, i >= incomplete))
+        .collect::<Vec<_>>();
+
+    lines.shuffle(rng);
+    lines.join("\n")
+}
+
+fn generate_line(rng: &mut Rng, corrupted: bool) -> String {
+    let length = rng.gen_range(20..110);
+
+    let mut line = Vec::with_capacity(length);
+    let mut stack = Vec::with_capacity(length);
+
+    loop {
+        if line.len() >= length && !stack.is_empty() {
+            break;
+        }
+
+        if stack.is_empty() || rng.gen_bool(0.55) {
+            let kind = rng.gen_range(0..OPENING.len());
+            stack.push(kind);
+            line.push(OPENING[kind]);
+        } else {
+            line.push(CLOSING[stack.pop().unwrap()]);
+        }
+    }
+
+    if corrupted {
+        let expected = stack.pop().unwrap();
+        let illegal = (expected + rng.gen_range(1..CLOSING.len())) % CLOSING.len();
+        line.push(CLOSING[illegal]);
+
+        // whatever comes after the first illegal character is irrelevant
+        for _ in 0..rng.gen_range(0..10) {
+            line.push(OPENING[rng.gen_range(0..OPENING.len())]);
+        }
+    }
+
+    String::from_utf8(line).unwrap()
+}
@@ -0,0 +1,79 @@
+use super::Rng;
+use anyhow::anyhow;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 10;
+
+const MAP_SIZE: usize = 10;
+const MAX_STEPS: usize = 10_000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> anyhow::Result<String> {
+    if size != MAP_SIZE {
+        return Err(anyhow!(
+            "The octopuses always form a {0}x{0} grid",
+            MAP_SIZE
+        ));
+    }
+
+    // the solver expects the octopuses to synchronize at some point, so keep rolling until they do
+    loop {
+        let energy = (0..MAP_SIZE * MAP_SIZE)
+            .map(|_| rng.gen_range(0..10u8))
+            .collect::<Vec<_>>();
+
+        if synchronizes(energy.clone()) {
+            return Ok(energy
+                .chunks_exact(MAP_SIZE)
+                .map(|row| {
+                    row.iter()
+                        .map(|&e| char::from(b'0' + e))
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+    }
+}
+
+fn synchronizes(mut energy: Vec<u8>) -> bool {
+    for _ in 0..MAX_STEPS {
+        let mut pending = (0..energy.len()).collect::<Vec<_>>();
+
+        while let Some(index) = pending.pop() {
+            energy[index] += 1;
+
+            if energy[index] != 10 {
+                continue;
+            }
+
+            let x = (index % MAP_SIZE) as isize;
+            let y = (index / MAP_SIZE) as isize;
+
+            for ny in y - 1..=y + 1 {
+                for nx in x - 1..=x + 1 {
+                    let inside = (0..MAP_SIZE as isize).contains(&nx)
+                        && (0..MAP_SIZE as isize).contains(&ny);
+
+                    if inside && (nx, ny) != (x, y) {
+                        pending.push(nx as usize + ny as usize * MAP_SIZE);
+                    }
+                }
+            }
+        }
+
+        let mut flashes = 0;
+
+        for e in &mut energy {
+            if *e > 9 {
+                *e = 0;
+                flashes += 1;
+            }
+        }
+
+        if flashes == energy.len() {
+            return true;
+        }
+    }
+
+    false
+}
@@ -0,0 +1,53 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+use std::collections::HashSet;
+
+pub const DEFAULT_SIZE: usize = 10;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut names = HashSet::new();
+    let mut caves = vec!["start".to_string(), "end".to_string()];
+
+    while caves.len() < size + 2 {
+        let big = caves.len() % 3 == 2;
+        let base = if big { b'A' } else { b'a' };
+        let name = [base + rng.gen_range(0..26), base + rng.gen_range(0..26)];
+        let name = String::from_utf8(name.to_vec()).unwrap();
+
+        if names.insert(name.clone()) {
+            caves.push(name);
+        }
+    }
+
+    // big caves must never be connected to each other, there would be infinitely many paths
+    let is_big = |id: usize| caves[id].as_bytes()[0].is_ascii_uppercase();
+    let mut edges = HashSet::new();
+
+    for id in 0..caves.len() {
+        for _ in 0..rng.gen_range(2..=3) {
+            let other = rng.gen_range(0..caves.len());
+
+            if other == id || (is_big(id) && is_big(other)) {
+                continue;
+            }
+
+            edges.insert((id.min(other), id.max(other)));
+        }
+    }
+
+    let mut edges = edges
+        .into_iter()
+        .map(|(a, b)| {
+            if rng.gen_bool(0.5) {
+                format!("{}-{}", caves[a], caves[b])
+            } else {
+                format!("{}-{}", caves[b], caves[a])
+            }
+        })
+        .collect::<Vec<_>>();
+
+    edges.sort_unstable();
+    edges.shuffle(rng);
+    edges.join("\n")
+}
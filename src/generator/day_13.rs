@@ -0,0 +1,71 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+use std::collections::HashSet;
+
+pub const DEFAULT_SIZE: usize = 12;
+
+const WIDTH: u16 = 39;
+const HEIGHT: u16 = 6;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // start from the folded paper and keep unfolding it, every fold doubles the paper
+    let mut dots = HashSet::new();
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            if rng.gen_bool(0.4) {
+                dots.insert((x, y));
+            }
+        }
+    }
+
+    let mut width = WIDTH;
+    let mut height = HEIGHT;
+    let mut folds = Vec::with_capacity(size);
+
+    for _ in 0..size.min(12) {
+        let vertical = width < 1000 && (height >= 1000 || rng.gen_bool(0.5));
+
+        let position = if vertical { width } else { height };
+
+        dots = dots
+            .into_iter()
+            .flat_map(|(x, y)| {
+                let mirrored = if vertical {
+                    (2 * position - x, y)
+                } else {
+                    (x, 2 * position - y)
+                };
+
+                match rng.gen_range(0..3) {
+                    0 => vec![(x, y)],
+                    1 => vec![mirrored],
+                    _ => vec![(x, y), mirrored],
+                }
+            })
+            .collect();
+
+        if vertical {
+            folds.push(format!("fold along x={}", position));
+            width = 2 * width + 1;
+        } else {
+            folds.push(format!("fold along y={}", position));
+            height = 2 * height + 1;
+        }
+    }
+
+    let mut dots = dots
+        .into_iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>();
+
+    dots.sort_unstable();
+    dots.shuffle(rng);
+    folds.reverse();
+
+    let mut lines = dots;
+    lines.push(String::new());
+    lines.extend(folds);
+    lines.join("\n")
+}
@@ -0,0 +1,29 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+
+pub const DEFAULT_SIZE: usize = 20;
+
+const ELEMENTS: &[u8] = b"BCFHKNOPSV";
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let template = (0..size.max(2))
+        .map(|_| char::from(*ELEMENTS.choose(rng).unwrap()))
+        .collect::<String>();
+
+    let mut lines = vec![template, String::new()];
+
+    for &x in ELEMENTS {
+        for &y in ELEMENTS {
+            let insertion = char::from(*ELEMENTS.choose(rng).unwrap());
+            lines.push(format!(
+                "{}{} -> {}",
+                char::from(x),
+                char::from(y),
+                insertion
+            ));
+        }
+    }
+
+    lines[2..].shuffle(rng);
+    lines.join("\n")
+}
@@ -0,0 +1,100 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 50;
+
+const MAX_DEPTH: usize = 6;
+const MAX_VALUE: u128 = 1 << 48;
+const OPERATORS: [u64; 7] = [0, 1, 2, 3, 5, 6, 7];
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut budget = size.max(1);
+    let (mut bits, _) = generate_packet(rng, &mut budget, 0);
+
+    while bits.len() % 8 != 0 {
+        bits.push(false);
+    }
+
+    bits.chunks_exact(4)
+        .map(|nibble| {
+            let value = nibble.iter().fold(0, |acc, &bit| acc << 1 | bit as u32);
+            char::from_digit(value, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect()
+}
+
+/// Returns the bits of a random packet, together with an upper bound of its value.
+fn generate_packet(rng: &mut Rng, budget: &mut usize, depth: usize) -> (Vec<bool>, u128) {
+    let mut bits = Vec::new();
+    push_number(&mut bits, rng.gen_range(0..8), 3);
+
+    *budget = budget.saturating_sub(1);
+
+    if *budget == 0 || depth >= MAX_DEPTH || (depth > 0 && rng.gen_bool(0.3)) {
+        let width = rng.gen_range(1..16);
+        let value = rng.gen_range(0..1u64 << width);
+        push_number(&mut bits, 4, 3);
+        push_literal(&mut bits, value);
+        return (bits, value as u128);
+    }
+
+    let mut type_id = *OPERATORS.choose(rng).unwrap();
+
+    let count = match type_id {
+        5..=7 => 2,
+        1 => rng.gen_range(1..=3),
+        _ => rng.gen_range(1..=4),
+    };
+
+    let mut children = Vec::with_capacity(count);
+    let mut bounds = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let (child, bound) = generate_packet(rng, budget, depth + 1);
+        children.extend(child);
+        bounds.push(bound);
+    }
+
+    // keep the result within u64 by turning large products into sums
+    let product = bounds
+        .iter()
+        .try_fold(1u128, |acc, &bound| acc.checked_mul(bound.max(1)));
+
+    if type_id == 1 && !matches!(product, Some(product) if product <= MAX_VALUE) {
+        type_id = 0;
+    }
+
+    let bound = match type_id {
+        0 => bounds.iter().sum(),
+        1 => product.unwrap(),
+        2 | 3 => bounds.iter().copied().max().unwrap(),
+        _ => 1,
+    };
+
+    push_number(&mut bits, type_id, 3);
+
+    if children.len() < 1 << 15 && rng.gen_bool(0.5) {
+        bits.push(false);
+        push_number(&mut bits, children.len() as u64, 15);
+    } else {
+        bits.push(true);
+        push_number(&mut bits, count as u64, 11);
+    }
+
+    bits.extend(children);
+    (bits, bound)
+}
+
+fn push_literal(bits: &mut Vec<bool>, value: u64) {
+    let groups = (64 - value.leading_zeros() as usize).max(1).div_ceil(4);
+
+    for group in (0..groups).rev() {
+        bits.push(group > 0);
+        push_number(bits, value >> (group * 4), 4);
+    }
+}
+
+fn push_number(bits: &mut Vec<bool>, value: u64, length: usize) {
+    bits.extend((0..length).rev().map(|i| value >> i & 1 == 1));
+}
@@ -0,0 +1,21 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 100;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // the probe has to be able to stop above the target, so it must contain a triangular number
+    let size = size.clamp(10, 1000) as i32;
+    let steps = rng.gen_range(3..=(size / 5).max(4));
+    let triangle = steps * (steps + 1) / 2;
+
+    let min_x = triangle - rng.gen_range(0..=steps);
+    let max_x = triangle + rng.gen_range(0..=steps * 2);
+    let min_y = -rng.gen_range(size / 2..=size);
+    let max_y = min_y + rng.gen_range(1..=-min_y / 2);
+
+    format!(
+        "target area: x={}..{}, y={}..{}",
+        min_x, max_x, min_y, max_y
+    )
+}
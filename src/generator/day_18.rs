@@ -0,0 +1,34 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 100;
+
+const MAX_DEPTH: usize = 4;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size.max(1))
+        .map(|_| {
+            let mut number = String::new();
+            generate_pair(rng, 1, &mut number);
+            number
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn generate_pair(rng: &mut Rng, depth: usize, buffer: &mut String) {
+    buffer.push('[');
+    generate_element(rng, depth, buffer);
+    buffer.push(',');
+    generate_element(rng, depth, buffer);
+    buffer.push(']');
+}
+
+fn generate_element(rng: &mut Rng, depth: usize, buffer: &mut String) {
+    // reduced numbers never nest pairs deeper than 4, nor contain values above 9
+    if depth < MAX_DEPTH && rng.gen_bool(0.6) {
+        generate_pair(rng, depth + 1, buffer);
+    } else {
+        buffer.push(char::from(b'0' + rng.gen_range(0..10)));
+    }
+}
@@ -0,0 +1,143 @@
+use super::Rng;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+use std::collections::HashSet;
+
+pub const DEFAULT_SIZE: usize = 30;
+
+const RANGE: i32 = 1000;
+const MAX_OFFSET: i32 = 1100;
+const MAX_POSITION: i32 = 5000;
+const SHARED_BEACONS: usize = 12;
+const EXTRA_BEACONS: usize = 12;
+
+type Point = [i32; 3];
+
+/// The layout the scanner reports were generated from, expressed relative to scanner 0.
+pub struct GroundTruth {
+    pub scanners: Vec<Point>,
+    pub beacons: usize,
+}
+
+impl std::fmt::Display for GroundTruth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "beacons: {}", self.beacons)?;
+
+        for (index, [x, y, z]) in self.scanners.iter().enumerate() {
+            writeln!(f, "scanner {}: {},{},{}", index, x, y, z)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> (String, GroundTruth) {
+    let size = size.max(1);
+    let mut scanners = vec![[0, 0, 0]];
+    let mut beacons = HashSet::new();
+
+    let extra = rng.gen_range(0..=EXTRA_BEACONS);
+    add_beacons(rng, &mut beacons, &scanners[0], &scanners[0], extra);
+
+    // every scanner shares at least 12 beacons with a previously placed one
+    while scanners.len() < size {
+        let parent = scanners[rng.gen_range(0..scanners.len())];
+        let scanner = parent.map(|p| p + rng.gen_range(-MAX_OFFSET..=MAX_OFFSET));
+
+        if scanner.iter().any(|p| p.abs() > MAX_POSITION) {
+            continue;
+        }
+
+        add_beacons(rng, &mut beacons, &parent, &scanner, SHARED_BEACONS);
+        let extra = rng.gen_range(0..=EXTRA_BEACONS);
+        add_beacons(rng, &mut beacons, &scanner, &scanner, extra);
+        scanners.push(scanner);
+    }
+
+    let rotations = rotations();
+    let mut lines = Vec::new();
+
+    for (index, scanner) in scanners.iter().enumerate() {
+        let rotation = if index == 0 {
+            &rotations[0]
+        } else {
+            rotations.choose(rng).unwrap()
+        };
+
+        if index > 0 {
+            lines.push(String::new());
+        }
+
+        lines.push(format!("--- scanner {} ---", index));
+
+        let mut report = beacons
+            .iter()
+            .filter(|beacon| (0..3).all(|i| (beacon[i] - scanner[i]).abs() <= RANGE))
+            .map(|beacon| rotate(rotation, &[0, 1, 2].map(|i| beacon[i] - scanner[i])))
+            .collect::<Vec<_>>();
+
+        report.sort_unstable();
+        report.shuffle(rng);
+
+        for [x, y, z] in report {
+            lines.push(format!("{},{},{}", x, y, z));
+        }
+    }
+
+    let truth = GroundTruth {
+        beacons: beacons.len(),
+        scanners,
+    };
+
+    (lines.join("\n"), truth)
+}
+
+/// Adds beacons visible to both scanners.
+fn add_beacons(rng: &mut Rng, beacons: &mut HashSet<Point>, a: &Point, b: &Point, count: usize) {
+    let min = [0, 1, 2].map(|i| a[i].max(b[i]) - RANGE);
+    let max = [0, 1, 2].map(|i| a[i].min(b[i]) + RANGE);
+    let target = beacons.len() + count;
+
+    while beacons.len() < target {
+        beacons.insert([0, 1, 2].map(|i| rng.gen_range(min[i]..=max[i])));
+    }
+}
+
+fn rotate(rotation: &[i32; 9], point: &Point) -> Point {
+    [0, 1, 2].map(|row| (0..3).map(|i| rotation[row * 3 + i] * point[i]).sum())
+}
+
+/// Builds all 24 rotations, the identity comes first.
+fn rotations() -> Vec<[i32; 9]> {
+    let permutations = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut rotations = Vec::with_capacity(24);
+
+    for permutation in permutations {
+        for signs in 0..8 {
+            let mut rotation = [0; 9];
+
+            for row in 0..3 {
+                let sign = if signs >> row & 1 == 0 { 1 } else { -1 };
+                rotation[row * 3 + permutation[row]] = sign;
+            }
+
+            if determinant(&rotation) == 1 {
+                rotations.push(rotation);
+            }
+        }
+    }
+
+    rotations
+}
+
+fn determinant(m: &[i32; 9]) -> i32 {
+    m[0] * (m[4] * m[8] - m[5] * m[7]) - m[1] * (m[3] * m[8] - m[5] * m[6])
+        + m[2] * (m[3] * m[7] - m[4] * m[6])
+}
@@ -0,0 +1,35 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 100;
+
+const VALUE_COUNT: usize = 512;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut algorithm = (0..VALUE_COUNT)
+        .map(|_| rng.gen_bool(0.5))
+        .collect::<Vec<_>>();
+
+    // when empty pixels light up, lit pixels have to go dark, otherwise the image is infinite
+    if algorithm[0] {
+        algorithm[VALUE_COUNT - 1] = false;
+    }
+
+    let mut lines = vec![render(&algorithm), String::new()];
+
+    for _ in 0..size.max(1) {
+        let row = (0..size.max(1))
+            .map(|_| rng.gen_bool(0.5))
+            .collect::<Vec<_>>();
+        lines.push(render(&row));
+    }
+
+    lines.join("\n")
+}
+
+fn render(pixels: &[bool]) -> String {
+    pixels
+        .iter()
+        .map(|&lit| if lit { '#' } else { '.' })
+        .collect()
+}
@@ -0,0 +1,24 @@
+use super::Rng;
+use anyhow::anyhow;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 2;
+
+pub fn generate(rng: &mut Rng, size: usize) -> anyhow::Result<String> {
+    if size != DEFAULT_SIZE {
+        return Err(anyhow!("There are always {} players", DEFAULT_SIZE));
+    }
+
+    let input = (1..=size)
+        .map(|player| {
+            format!(
+                "Player {} starting position: {}",
+                player,
+                rng.gen_range(1..=10)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(input)
+}
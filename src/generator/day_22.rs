@@ -0,0 +1,54 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 420;
+
+const INITIALIZATION_STEPS: usize = 20;
+const INITIALIZATION_RANGE: i32 = 50;
+const REBOOT_RANGE: i32 = 100_000;
+const MAX_REBOOT_SIZE: i32 = 40_000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let initialization = size.min(INITIALIZATION_STEPS);
+    let mut lines = Vec::with_capacity(size);
+
+    for i in 0..size {
+        let state = if i == 0 || rng.gen_bool(0.6) {
+            "on"
+        } else {
+            "off"
+        };
+
+        // only the initialization steps may touch the -50..50 region
+        let ranges = if i < initialization {
+            [0; 3].map(|_| {
+                let min = rng.gen_range(-INITIALIZATION_RANGE..=INITIALIZATION_RANGE);
+                let max = rng.gen_range(min..=INITIALIZATION_RANGE);
+                (min, max)
+            })
+        } else {
+            let mut ranges = [0; 3].map(|_| {
+                let min = rng.gen_range(-REBOOT_RANGE..REBOOT_RANGE);
+                (min, min + rng.gen_range(0..MAX_REBOOT_SIZE))
+            });
+
+            // keep the x range clear of the initialization region, like the puzzle input does
+            while ranges[0].0 <= INITIALIZATION_RANGE && ranges[0].1 >= -INITIALIZATION_RANGE {
+                let min = rng.gen_range(-REBOOT_RANGE..REBOOT_RANGE);
+                ranges[0] = (min, min + rng.gen_range(0..MAX_REBOOT_SIZE));
+            }
+
+            ranges
+        };
+
+        let [(x1, x2), (y1, y2), (z1, z2)] = ranges;
+
+        lines.push(format!(
+            "{} x={}..{},y={}..{},z={}..{}",
+            state, x1, x2, y1, y2, z1, z2
+        ));
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,42 @@
+use super::Rng;
+use anyhow::anyhow;
+use rand::seq::SliceRandom;
+
+pub const DEFAULT_SIZE: usize = 8;
+
+const ROOMS: &[u8] = b"ABCD";
+
+/// Fills the four rooms with `size` amphipods, so that the rooms are `size / 4` deep.
+///
+/// The hallway only has room for seven amphipods, so deeper rooms can leave them stuck.
+pub fn generate(rng: &mut Rng, size: usize) -> anyhow::Result<String> {
+    if size == 0 || !size.is_multiple_of(ROOMS.len()) {
+        return Err(anyhow!(
+            "The amphipods have to fill {} rooms, found {}",
+            ROOMS.len(),
+            size
+        ));
+    }
+
+    let depth = size / ROOMS.len();
+    let mut amphipods = ROOMS
+        .iter()
+        .flat_map(|&amphipod| std::iter::repeat_n(char::from(amphipod), depth))
+        .collect::<Vec<_>>();
+    amphipods.shuffle(rng);
+
+    let mut lines = vec!["#############".to_string(), "#...........#".to_string()];
+
+    for (index, row) in amphipods.chunks_exact(ROOMS.len()).enumerate() {
+        let (outside, inside) = match index {
+            0 => ("###", "###"),
+            _ => ("  #", "#"),
+        };
+
+        let row = row.iter().map(char::to_string).collect::<Vec<_>>();
+        lines.push(format!("{}{}{}", outside, row.join("#"), inside));
+    }
+
+    lines.push("  #########".to_string());
+    Ok(lines.join("\n"))
+}
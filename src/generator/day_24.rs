@@ -0,0 +1,83 @@
+use super::Rng;
+use anyhow::anyhow;
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 14;
+
+/// Generates a MONAD-like program reading `size` digits, which is 14 in the puzzle.
+pub fn generate(rng: &mut Rng, size: usize) -> anyhow::Result<String> {
+    if size == 0 || !size.is_multiple_of(2) {
+        return Err(anyhow!(
+            "Every digit pushing to the stack needs one popping it, found {} digits",
+            size
+        ));
+    }
+
+    // a random sequence of as many pushes as pops that never pops from an empty stack
+    let mut kinds = std::iter::repeat_n(true, size / 2)
+        .chain(std::iter::repeat_n(false, size / 2))
+        .collect::<Vec<_>>();
+
+    loop {
+        kinds.shuffle(rng);
+
+        let balanced = kinds
+            .iter()
+            .try_fold(0usize, |depth, &push| {
+                if push {
+                    Some(depth + 1)
+                } else {
+                    depth.checked_sub(1)
+                }
+            })
+            .is_some();
+
+        if balanced {
+            break;
+        }
+    }
+
+    let mut stack = Vec::with_capacity(size / 2);
+    let mut blocks = Vec::with_capacity(size);
+
+    for push in kinds {
+        if push {
+            // the check can never match a digit, so pushing blocks always push
+            let offset = rng.gen_range(0..=16);
+            stack.push(offset);
+            blocks.push(block(1, rng.gen_range(10..=16), offset));
+        } else {
+            // the paired digits differ by at most 8, so there's always a valid combination
+            let difference = rng.gen_range(-8..=8);
+            let check = difference - stack.pop().unwrap();
+            blocks.push(block(26, check, rng.gen_range(0..=16)));
+        }
+    }
+
+    Ok(blocks.join("\n"))
+}
+
+fn block(divisor: isize, check: isize, offset: isize) -> String {
+    [
+        "inp w".to_string(),
+        "mul x 0".to_string(),
+        "add x z".to_string(),
+        "mod x 26".to_string(),
+        format!("div z {}", divisor),
+        format!("add x {}", check),
+        "eql x w".to_string(),
+        "eql x 0".to_string(),
+        "mul y 0".to_string(),
+        "add y 25".to_string(),
+        "mul y x".to_string(),
+        "add y 1".to_string(),
+        "mul z y".to_string(),
+        "mul y 0".to_string(),
+        "add y w".to_string(),
+        format!("add y {}", offset),
+        "mul y x".to_string(),
+        "add z y".to_string(),
+    ]
+    .join("\n")
+}
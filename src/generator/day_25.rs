@@ -0,0 +1,58 @@
+use super::Rng;
+use rand::Rng as _;
+
+pub const DEFAULT_SIZE: usize = 139;
+
+const MAX_STEPS: usize = 10_000;
+
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = size.max(2);
+    let height = (size * 137 / 139).max(2);
+
+    // herds can keep circling forever, the solver expects them to get stuck eventually
+    loop {
+        let spots = (0..width * height)
+            .map(|_| match rng.gen_range(0..100) {
+                0..=44 => b'.',
+                45..=72 => b'>',
+                _ => b'v',
+            })
+            .collect::<Vec<_>>();
+
+        if gets_stuck(spots.clone(), width, height) {
+            return spots
+                .chunks_exact(width)
+                .map(|row| String::from_utf8(row.to_vec()).unwrap())
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+    }
+}
+
+fn gets_stuck(mut spots: Vec<u8>, width: usize, height: usize) -> bool {
+    for _ in 0..MAX_STEPS {
+        let mut moved = false;
+
+        for (herd, dx, dy) in [(b'>', 1, 0), (b'v', 0, 1)] {
+            let current = spots.clone();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let next = (x + dx) % width + (y + dy) % height * width;
+
+                    if current[x + y * width] == herd && current[next] == b'.' {
+                        spots[x + y * width] = b'.';
+                        spots[next] = herd;
+                        moved = true;
+                    }
+                }
+            }
+        }
+
+        if !moved {
+            return true;
+        }
+    }
+
+    false
+}
@@ -0,0 +1,103 @@
+use crate::challenge::Day;
+use anyhow::Context;
+use rand::SeedableRng;
+
+mod day_01;
+mod day_02;
+mod day_03;
+mod day_04;
+mod day_05;
+mod day_06;
+mod day_07;
+mod day_08;
+mod day_09;
+mod day_10;
+mod day_11;
+mod day_12;
+mod day_13;
+mod day_14;
+mod day_15;
+mod day_16;
+mod day_17;
+mod day_18;
+mod day_19;
+mod day_20;
+mod day_21;
+mod day_22;
+mod day_23;
+mod day_24;
+mod day_25;
+
+pub type Rng = rand_chacha::ChaCha8Rng;
+
+/// Generates a random, but structurally valid input for the given day.
+///
+/// The same `seed` always produces the same input. `size` controls the rough amount of items
+/// (lines, grid width, scanners, ...) and falls back to something close to the real puzzle input.
+/// Days whose inputs can't change in size reject any other size than their default.
+pub fn generate(day: Day, seed: u64, size: Option<usize>) -> anyhow::Result<String> {
+    use Day::*;
+
+    let mut rng = Rng::seed_from_u64(seed);
+
+    let input = match day {
+        Day01 => day_01::generate(&mut rng, size.unwrap_or(day_01::DEFAULT_SIZE)),
+        Day02 => day_02::generate(&mut rng, size.unwrap_or(day_02::DEFAULT_SIZE)),
+        Day03 => day_03::generate(&mut rng, size.unwrap_or(day_03::DEFAULT_SIZE)),
+        Day04 => day_04::generate(&mut rng, size.unwrap_or(day_04::DEFAULT_SIZE)),
+        Day05 => day_05::generate(&mut rng, size.unwrap_or(day_05::DEFAULT_SIZE)),
+        Day06 => day_06::generate(&mut rng, size.unwrap_or(day_06::DEFAULT_SIZE)),
+        Day07 => day_07::generate(&mut rng, size.unwrap_or(day_07::DEFAULT_SIZE)),
+        Day08 => day_08::generate(&mut rng, size.unwrap_or(day_08::DEFAULT_SIZE)),
+        Day09 => day_09::generate(&mut rng, size.unwrap_or(day_09::DEFAULT_SIZE)),
+        Day10 => day_10::generate(&mut rng, size.unwrap_or(day_10::DEFAULT_SIZE)),
+        Day11 => day_11::generate(&mut rng, size.unwrap_or(day_11::DEFAULT_SIZE))?,
+        Day12 => day_12::generate(&mut rng, size.unwrap_or(day_12::DEFAULT_SIZE)),
+        Day13 => day_13::generate(&mut rng, size.unwrap_or(day_13::DEFAULT_SIZE)),
+        Day14 => day_14::generate(&mut rng, size.unwrap_or(day_14::DEFAULT_SIZE)),
+        Day15 => day_15::generate(&mut rng, size.unwrap_or(day_15::DEFAULT_SIZE)),
+        Day16 => day_16::generate(&mut rng, size.unwrap_or(day_16::DEFAULT_SIZE)),
+        Day17 => day_17::generate(&mut rng, size.unwrap_or(day_17::DEFAULT_SIZE)),
+        Day18 => day_18::generate(&mut rng, size.unwrap_or(day_18::DEFAULT_SIZE)),
+        Day19 => day_19::generate(&mut rng, size.unwrap_or(day_19::DEFAULT_SIZE)).0,
+        Day20 => day_20::generate(&mut rng, size.unwrap_or(day_20::DEFAULT_SIZE)),
+        Day21 => day_21::generate(&mut rng, size.unwrap_or(day_21::DEFAULT_SIZE))?,
+        Day22 => day_22::generate(&mut rng, size.unwrap_or(day_22::DEFAULT_SIZE)),
+        Day23 => day_23::generate(&mut rng, size.unwrap_or(day_23::DEFAULT_SIZE))?,
+        Day24 => day_24::generate(&mut rng, size.unwrap_or(day_24::DEFAULT_SIZE))?,
+        Day25 => day_25::generate(&mut rng, size.unwrap_or(day_25::DEFAULT_SIZE)),
+    };
+
+    Ok(input)
+}
+
+/// Handles `generate <day> [seed] [size]`, printing the generated input to stdout.
+///
+/// Day 19 also prints the hidden scanner layout to stderr.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let day: Day = args
+        .first()
+        .context("Missing day, expecting `generate <day> [seed] [size]`")?
+        .parse()?;
+
+    let seed = match args.get(1) {
+        Some(seed) => seed.parse()?,
+        None => 0,
+    };
+
+    let size = match args.get(2) {
+        Some(size) => Some(size.parse()?),
+        None => None,
+    };
+
+    if day == Day::Day19 {
+        let mut rng = Rng::seed_from_u64(seed);
+        let (input, truth) = day_19::generate(&mut rng, size.unwrap_or(day_19::DEFAULT_SIZE));
+        eprint!("{}", truth);
+        println!("{}", input);
+    } else {
+        println!("{}", generate(day, seed, size)?);
+    }
+
+    Ok(())
+}
@@ -1,20 +1,37 @@
+use crate::options::Options;
 use std::io::BufRead;
 
+mod alloc;
+mod bits;
+mod box_set;
 mod challenge;
+mod generator;
 mod http;
+mod matrix;
+mod options;
+mod profile;
+mod reference;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    if std::env::args().count() > 1 {
-        run(std::env::args().skip(1).map(Ok)).await
-    } else {
-        run(std::io::stdin().lock().lines()).await
+    let (options, args) = Options::parse(std::env::args().skip(1))?;
+    let _guard = profile::init(&options)?;
+
+    match args.first().map(String::as_str) {
+        Some("generate") => generator::run(&args[1..]),
+        Some("verify") => reference::run(&args[1..]),
+        Some("tool") => challenge::run_tool(&args[1..], &options).await,
+        Some(_) => run(args.into_iter().map(Ok), &options).await,
+        None => run(std::io::stdin().lock().lines(), &options).await,
     }
 }
 
-async fn run(args: impl Iterator<Item = std::io::Result<String>>) -> anyhow::Result<()> {
+async fn run(
+    args: impl Iterator<Item = std::io::Result<String>>,
+    options: &Options,
+) -> anyhow::Result<()> {
     for arg in args {
-        challenge::solve(&arg?.parse()?).await?;
+        challenge::solve(&arg?.parse()?, options).await?;
     }
 
     Ok(())
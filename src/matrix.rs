@@ -0,0 +1,204 @@
+use anyhow::anyhow;
+use num_bigint::BigUint;
+use std::fmt::Display;
+
+/// Numbers that can be counted in, either exactly or modulo some number.
+pub trait Arithmetic {
+    type Value: Clone + Display;
+
+    fn zero(&self) -> Self::Value;
+    fn one(&self) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+
+    /// Sum of the products of the values at the same positions.
+    fn dot(&self, a: &[Self::Value], b: &[Self::Value]) -> Self::Value {
+        a.iter()
+            .zip(b)
+            .fold(self.zero(), |sum, (a, b)| self.add(&sum, &self.mul(a, b)))
+    }
+
+    fn sum<'a>(&self, values: impl IntoIterator<Item = &'a Self::Value>) -> Self::Value
+    where
+        Self::Value: 'a,
+    {
+        values
+            .into_iter()
+            .fold(self.zero(), |total, value| self.add(&total, value))
+    }
+}
+
+/// Counts without any bound.
+pub struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+
+    fn zero(&self) -> BigUint {
+        BigUint::default()
+    }
+
+    fn one(&self) -> BigUint {
+        BigUint::from(1u8)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+}
+
+/// Counts in a machine word, for counts that are known to fit.
+pub struct Native;
+
+impl Arithmetic for Native {
+    type Value = u64;
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn one(&self) -> u64 {
+        1
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        a + b
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        a * b
+    }
+}
+
+/// Counts modulo a number, for counts too large to write down.
+pub struct Modulo(u64);
+
+impl Modulo {
+    pub fn new(modulus: u64) -> anyhow::Result<Self> {
+        match modulus {
+            0 => Err(anyhow!("The modulus must be positive")),
+            modulus => Ok(Modulo(modulus)),
+        }
+    }
+}
+
+impl Arithmetic for Modulo {
+    type Value = u64;
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn one(&self) -> u64 {
+        1 % self.0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.0 as u128) as u64
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        (*a as u128 * *b as u128 % self.0 as u128) as u64
+    }
+
+    fn dot(&self, a: &[u64], b: &[u64]) -> u64 {
+        let modulus = self.0 as u128;
+
+        // every product is below 2^64, so a u128 can add up more of them than fit in memory before
+        // it needs reducing
+        let sum = if self.0 <= u32::MAX as u64 {
+            a.iter().zip(b).map(|(a, b)| (a * b) as u128).sum::<u128>()
+        } else {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| *a as u128 * *b as u128 % modulus)
+                .sum::<u128>()
+        };
+
+        (sum % modulus) as u64
+    }
+}
+
+/// Square matrix of counts, mostly used as the transition of a vector of counts from one step to
+/// the next, so that many steps can be taken at once by raising it to a power.
+#[derive(Clone)]
+pub struct Matrix<T>(Vec<Vec<T>>);
+
+impl<T: Clone> Matrix<T> {
+    /// Matrix counting the `(row, column)` edges, so that applying it adds every entry of the
+    /// vector to the rows it has edges to.
+    pub fn from_edges<A: Arithmetic<Value = T>>(
+        size: usize,
+        edges: impl IntoIterator<Item = (usize, usize)>,
+        arithmetic: &A,
+    ) -> Self {
+        let mut matrix = Matrix(vec![vec![arithmetic.zero(); size]; size]);
+
+        for (row, column) in edges {
+            let entry = &mut matrix.0[row][column];
+            *entry = arithmetic.add(entry, &arithmetic.one());
+        }
+
+        matrix
+    }
+
+    fn identity<A: Arithmetic<Value = T>>(size: usize, arithmetic: &A) -> Self {
+        Matrix::from_edges(size, (0..size).map(|index| (index, index)), arithmetic)
+    }
+
+    fn mul<A: Arithmetic<Value = T>>(&self, other: &Self, arithmetic: &A) -> Self {
+        // walking the columns of the other matrix as rows keeps both sides of every product in
+        // order in memory
+        let columns = other.transpose();
+
+        Matrix(
+            self.0
+                .iter()
+                .map(|row| {
+                    columns
+                        .iter()
+                        .map(|column| arithmetic.dot(row, column))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn transpose(&self) -> Vec<Vec<T>> {
+        (0..self.0.len())
+            .map(|column| self.0.iter().map(|row| row[column].clone()).collect())
+            .collect()
+    }
+
+    /// Raises the matrix to the power of `exponent` by squaring, in about `2 log2(exponent)`
+    /// products.
+    pub fn pow<A: Arithmetic<Value = T>>(&self, mut exponent: u64, arithmetic: &A) -> Self {
+        let mut result = Matrix::identity(self.0.len(), arithmetic);
+        let mut base = self.clone();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base, arithmetic);
+            }
+
+            exponent >>= 1;
+
+            if exponent > 0 {
+                base = base.mul(&base, arithmetic);
+            }
+        }
+
+        result
+    }
+
+    pub fn apply<A: Arithmetic<Value = T>>(&self, vector: &[T], arithmetic: &A) -> Vec<T> {
+        self.0
+            .iter()
+            .map(|row| arithmetic.dot(row, vector))
+            .collect()
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Context};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Default)]
+pub struct Options {
+    input: Option<PathBuf>,
+    profile: bool,
+    trace: Option<PathBuf>,
+    format: Format,
+}
+
+impl Options {
+    /// Splits the `--flag value` options from the remaining positional arguments.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<(Options, Vec<String>)> {
+        let mut options = Options::default();
+        let mut positional = Vec::new();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" => {
+                    let path = args.next().context("Missing path after --input")?;
+                    options.input = Some(path.into());
+                }
+                "--format" => {
+                    let format = args.next().context("Missing format after --format")?;
+                    options.format = format.parse()?;
+                }
+                "--profile" => options.profile = true,
+                "--trace" => {
+                    let path = args.next().context("Missing path after --trace")?;
+                    options.trace = Some(path.into());
+                }
+                _ if arg.starts_with("--") => return Err(anyhow!("Unknown option {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        Ok((options, positional))
+    }
+
+    /// Local file to read the puzzle input from, instead of downloading it.
+    pub fn input(&self) -> Option<&Path> {
+        self.input.as_deref()
+    }
+
+    /// Whether to print how long each phase of a solution took.
+    pub fn profile(&self) -> bool {
+        self.profile
+    }
+
+    /// File to write a Chrome trace (`chrome://tracing`, Perfetto) of the solution phases to.
+    pub fn trace(&self) -> Option<&Path> {
+        self.trace.as_deref()
+    }
+
+    /// How results are printed.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+}
+
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Format {
+    /// One line of text per result.
+    #[default]
+    Human,
+    /// One JSON object per result, for other tools to consume.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            _ => Err(anyhow!(
+                "{} is not a valid format, expecting human or json",
+                string
+            )),
+        }
+    }
+}
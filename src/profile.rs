@@ -0,0 +1,148 @@
+use crate::options::{Format, Options};
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+lazy_static! {
+    static ref PHASES: Mutex<Vec<Phase>> = Mutex::new(Vec::new());
+}
+
+/// Installs the subscribers requested by `--profile` and `--trace`.
+///
+/// The returned guard has to be kept alive until the program exits, as dropping it is what writes
+/// the Chrome trace to disk.
+pub fn init(options: &Options) -> anyhow::Result<Option<tracing_chrome::FlushGuard>> {
+    let (chrome, guard) = match options.trace() {
+        Some(path) => {
+            let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+                .file(path)
+                .include_args(true)
+                .build();
+
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    if chrome.is_some() || options.profile() {
+        tracing_subscriber::registry()
+            .with(options.profile().then_some(PhaseLayer))
+            .with(chrome)
+            .try_init()?;
+    }
+
+    Ok(guard)
+}
+
+/// Prints the time spent in each phase since the last report, if profiling is enabled.
+///
+/// The breakdown goes to stderr for JSON output, to keep stdout parseable.
+pub fn report(format: Format) {
+    let phases = std::mem::take(&mut *PHASES.lock().unwrap());
+
+    let total = phases
+        .iter()
+        .filter(|phase| phase.depth == 0)
+        .map(|phase| phase.busy)
+        .sum::<Duration>();
+
+    for phase in phases {
+        let name = phase.path.rsplit(" > ").next().unwrap_or_default();
+        let share = phase.busy.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON) * 100.0;
+
+        let line = format!(
+            "  {:indent$}{:width$} {:>12?} {:>6.1}% (calls = {})",
+            "",
+            name,
+            phase.busy,
+            share,
+            phase.calls,
+            indent = phase.depth * 2,
+            width = 24usize.saturating_sub(phase.depth * 2),
+        );
+
+        match format {
+            Format::Human => println!("{}", line),
+            Format::Json => eprintln!("{}", line),
+        }
+    }
+}
+
+struct Phase {
+    path: String,
+    depth: usize,
+    busy: Duration,
+    calls: usize,
+}
+
+/// Span extension tracking where a span sits in the tree and when it was last entered.
+struct Timing {
+    path: String,
+    entered: Option<Instant>,
+}
+
+/// Adds up how long each span (identified by the names of its ancestors) was entered.
+struct PhaseLayer;
+
+impl<S> Layer<S> for PhaseLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let names = span
+            .scope()
+            .from_root()
+            .map(|span| span.name())
+            .collect::<Vec<_>>();
+        let path = names.join(" > ");
+
+        // phases are registered when they're first created, so parents are listed before children
+        let mut phases = PHASES.lock().unwrap();
+
+        if !phases.iter().any(|phase| phase.path == path) {
+            phases.push(Phase {
+                path: path.clone(),
+                depth: names.len() - 1,
+                busy: Duration::ZERO,
+                calls: 0,
+            });
+        }
+
+        span.extensions_mut().insert(Timing {
+            path,
+            entered: None,
+        });
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(timing) = ctx.span(id).unwrap().extensions_mut().get_mut::<Timing>() {
+            timing.entered = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let mut extensions = span.extensions_mut();
+
+        let timing = match extensions.get_mut::<Timing>() {
+            Some(timing) => timing,
+            None => return,
+        };
+
+        if let Some(entered) = timing.entered.take() {
+            let mut phases = PHASES.lock().unwrap();
+
+            if let Some(phase) = phases.iter_mut().find(|phase| phase.path == timing.path) {
+                phase.busy += entered.elapsed();
+                phase.calls += 1;
+            }
+        }
+    }
+}
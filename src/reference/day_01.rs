@@ -0,0 +1,37 @@
+pub const INPUT_SIZE: usize = 200;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let depths = parse(input)?;
+    let mut count = 0;
+
+    for i in 1..depths.len() {
+        if depths[i] > depths[i - 1] {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let depths = parse(input)?;
+    let mut count = 0;
+
+    for i in 3..depths.len() {
+        let previous = depths[i - 3] + depths[i - 2] + depths[i - 1];
+        let current = depths[i - 2] + depths[i - 1] + depths[i];
+
+        if current > previous {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<Vec<u32>> {
+    Ok(input
+        .iter()
+        .map(|line| line.parse())
+        .collect::<Result<_, _>>()?)
+}
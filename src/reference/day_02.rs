@@ -0,0 +1,50 @@
+use anyhow::anyhow;
+
+pub const INPUT_SIZE: usize = 200;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut position = 0i64;
+    let mut depth = 0i64;
+
+    for (direction, amount) in parse(input)? {
+        match direction {
+            "forward" => position += amount,
+            "down" => depth += amount,
+            _ => depth -= amount,
+        }
+    }
+
+    Ok(position * depth)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut position = 0i64;
+    let mut depth = 0i64;
+    let mut aim = 0i64;
+
+    for (direction, amount) in parse(input)? {
+        match direction {
+            "forward" => {
+                position += amount;
+                depth += aim * amount;
+            }
+            "down" => aim += amount,
+            _ => aim -= amount,
+        }
+    }
+
+    Ok(position * depth)
+}
+
+fn parse<'a>(input: &[&'a str]) -> anyhow::Result<Vec<(&'a str, i64)>> {
+    input
+        .iter()
+        .map(|line| {
+            let (direction, amount) = line
+                .split_once(' ')
+                .ok_or_else(|| anyhow!("Invalid command {}", line))?;
+
+            Ok((direction, amount.parse()?))
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+pub const INPUT_SIZE: usize = 101;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut gamma = String::new();
+    let mut epsilon = String::new();
+
+    for i in 0..input[0].len() {
+        let ones = input
+            .iter()
+            .filter(|line| line.as_bytes()[i] == b'1')
+            .count();
+
+        if ones * 2 >= input.len() {
+            gamma.push('1');
+            epsilon.push('0');
+        } else {
+            gamma.push('0');
+            epsilon.push('1');
+        }
+    }
+
+    Ok(u64::from_str_radix(&gamma, 2)? * u64::from_str_radix(&epsilon, 2)?)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let oxygen = find_rating(input, true)?;
+    let co2 = find_rating(input, false)?;
+
+    Ok(oxygen * co2)
+}
+
+fn find_rating(input: &[&str], most_common: bool) -> anyhow::Result<u64> {
+    let mut remaining = input.to_vec();
+    let mut i = 0;
+
+    while remaining.len() > 1 && i < input[0].len() {
+        let ones = remaining
+            .iter()
+            .filter(|line| line.as_bytes()[i] == b'1')
+            .count();
+        let zeros = remaining.len() - ones;
+
+        // when every number shares the bit, there's nothing to filter out
+        if ones > 0 && zeros > 0 {
+            let keep = if (ones >= zeros) == most_common {
+                b'1'
+            } else {
+                b'0'
+            };
+            remaining.retain(|line| line.as_bytes()[i] == keep);
+        }
+
+        i += 1;
+    }
+
+    Ok(u64::from_str_radix(remaining[0], 2)?)
+}
@@ -0,0 +1,73 @@
+use anyhow::anyhow;
+
+pub const INPUT_SIZE: usize = 20;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let scores = play(input)?;
+    scores
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("No board has won"))
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let scores = play(input)?;
+    scores
+        .last()
+        .copied()
+        .ok_or_else(|| anyhow!("No board has won"))
+}
+
+/// Returns the scores of all boards in the order they've won.
+fn play(input: &[&str]) -> anyhow::Result<Vec<u32>> {
+    let numbers = input[0]
+        .split(',')
+        .map(|number| number.parse())
+        .collect::<Result<Vec<u32>, _>>()?;
+
+    let mut boards = Vec::new();
+
+    for chunk in input[1..].chunks(6) {
+        let board = chunk
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .map(|number| number.parse())
+            .collect::<Result<Vec<u32>, _>>()?;
+
+        boards.push(board);
+    }
+
+    let mut marked = vec![vec![false; 25]; boards.len()];
+    let mut won = vec![false; boards.len()];
+    let mut scores = Vec::new();
+
+    for number in numbers {
+        for (b, board) in boards.iter().enumerate() {
+            if won[b] {
+                continue;
+            }
+
+            for (i, value) in board.iter().enumerate() {
+                if *value == number {
+                    marked[b][i] = true;
+                }
+            }
+
+            let row = (0..5).any(|y| (0..5).all(|x| marked[b][x + y * 5]));
+            let column = (0..5).any(|x| (0..5).all(|y| marked[b][x + y * 5]));
+
+            if row || column {
+                won[b] = true;
+
+                let unmarked = (0..25)
+                    .filter(|&i| !marked[b][i])
+                    .map(|i| board[i])
+                    .sum::<u32>();
+
+                scores.push(unmarked * number);
+            }
+        }
+    }
+
+    Ok(scores)
+}
@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+pub const INPUT_SIZE: usize = 100;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    count_overlaps(input, false)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    count_overlaps(input, true)
+}
+
+fn count_overlaps(input: &[&str], diagonals: bool) -> anyhow::Result<usize> {
+    let mut points = HashMap::<(i32, i32), usize>::new();
+
+    for line in input {
+        let numbers = line
+            .split(" -> ")
+            .flat_map(|point| point.split(','))
+            .map(|number| number.parse())
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        let (x1, y1, x2, y2) = (numbers[0], numbers[1], numbers[2], numbers[3]);
+
+        if x1 != x2 && y1 != y2 && !diagonals {
+            continue;
+        }
+
+        let dx = (x2 - x1).signum();
+        let dy = (y2 - y1).signum();
+        let steps = (x2 - x1).abs().max((y2 - y1).abs());
+
+        for step in 0..=steps {
+            *points.entry((x1 + dx * step, y1 + dy * step)).or_default() += 1;
+        }
+    }
+
+    Ok(points.values().filter(|&&count| count > 1).count())
+}
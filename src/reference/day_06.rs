@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+pub const INPUT_SIZE: usize = 30;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut fish = parse(input[0])?;
+
+    for _ in 0..80 {
+        let mut newborn = 0;
+
+        for timer in &mut fish {
+            if *timer == 0 {
+                *timer = 6;
+                newborn += 1;
+            } else {
+                *timer -= 1;
+            }
+        }
+
+        fish.extend(std::iter::repeat_n(8, newborn));
+    }
+
+    Ok(fish.len())
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    // a literal list of fish would need terabytes, so count the descendants of each fish instead
+    let fish = parse(input[0])?;
+    let mut cache = HashMap::new();
+
+    Ok(fish
+        .iter()
+        .map(|&timer| descendants(timer as i64, 256, &mut cache))
+        .sum::<u64>())
+}
+
+/// Counts a fish and all of its descendants after the given amount of days.
+fn descendants(timer: i64, days: i64, cache: &mut HashMap<(i64, i64), u64>) -> u64 {
+    if let Some(&count) = cache.get(&(timer, days)) {
+        return count;
+    }
+
+    let mut count = 1;
+    let mut day = timer + 1;
+
+    while day <= days {
+        count += descendants(8, days - day, cache);
+        day += 7;
+    }
+
+    cache.insert((timer, days), count);
+    count
+}
+
+fn parse(input: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(input
+        .split(',')
+        .map(|number| number.parse())
+        .collect::<Result<_, _>>()?)
+}
@@ -0,0 +1,29 @@
+pub const INPUT_SIZE: usize = 100;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    min_cost(input, |distance| distance)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    min_cost(input, |distance| distance * (distance + 1) / 2)
+}
+
+fn min_cost(input: &[&str], cost: impl Fn(i64) -> i64) -> anyhow::Result<i64> {
+    let positions = input[0]
+        .split(',')
+        .map(|position| position.parse())
+        .collect::<Result<Vec<i64>, _>>()?;
+
+    let min = *positions.iter().min().unwrap();
+    let max = *positions.iter().max().unwrap();
+
+    Ok((min..=max)
+        .map(|target| {
+            positions
+                .iter()
+                .map(|position| cost((position - target).abs()))
+                .sum::<i64>()
+        })
+        .min()
+        .unwrap())
+}
@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Context};
+
+pub const INPUT_SIZE: usize = 20;
+
+const DIGITS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut count = 0;
+
+    for line in input {
+        let (_, outputs) = line.split_once(" | ").context("Missing delimiter")?;
+
+        count += outputs
+            .split_whitespace()
+            .filter(|output| [2, 3, 4, 7].contains(&output.len()))
+            .count();
+    }
+
+    Ok(count)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let wirings = permutations(b"abcdefg".to_vec());
+    let mut sum = 0;
+
+    for line in input {
+        let (patterns, outputs) = line.split_once(" | ").context("Missing delimiter")?;
+        let patterns = patterns.split_whitespace().collect::<Vec<_>>();
+
+        let wiring = wirings
+            .iter()
+            .find(|wiring| {
+                patterns
+                    .iter()
+                    .all(|pattern| decode(wiring, pattern).is_some())
+            })
+            .ok_or_else(|| anyhow!("No wiring matches {}", line))?;
+
+        sum += outputs
+            .split_whitespace()
+            .fold(0, |acc, output| acc * 10 + decode(wiring, output).unwrap());
+    }
+
+    Ok(sum)
+}
+
+/// Decodes a scrambled pattern, where `wiring[i]` is the wire connected to segment `i`.
+fn decode(wiring: &[u8], pattern: &str) -> Option<usize> {
+    let mut segments = pattern
+        .bytes()
+        .map(|wire| b'a' + wiring.iter().position(|&w| w == wire).unwrap() as u8)
+        .collect::<Vec<_>>();
+
+    segments.sort_unstable();
+    DIGITS.iter().position(|digit| digit.as_bytes() == segments)
+}
+
+fn permutations(items: Vec<u8>) -> Vec<Vec<u8>> {
+    if items.len() <= 1 {
+        return vec![items];
+    }
+
+    let mut result = Vec::new();
+
+    for i in 0..items.len() {
+        let mut rest = items.clone();
+        let item = rest.remove(i);
+
+        for mut permutation in permutations(rest) {
+            permutation.insert(0, item);
+            result.push(permutation);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,72 @@
+pub const INPUT_SIZE: usize = 30;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let map = parse(input);
+    let mut risk = 0;
+
+    for y in 0..map.len() {
+        for x in 0..map[y].len() {
+            let lowest = neighbours(&map, x, y).all(|(nx, ny)| map[ny][nx] > map[y][x]);
+
+            if lowest {
+                risk += map[y][x] as usize + 1;
+            }
+        }
+    }
+
+    Ok(risk)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let map = parse(input);
+    let mut visited = vec![vec![false; map[0].len()]; map.len()];
+    let mut sizes = Vec::new();
+
+    for y in 0..map.len() {
+        for x in 0..map[y].len() {
+            if visited[y][x] || map[y][x] == 9 {
+                continue;
+            }
+
+            let mut size = 0;
+            let mut pending = vec![(x, y)];
+            visited[y][x] = true;
+
+            while let Some((x, y)) = pending.pop() {
+                size += 1;
+
+                for (nx, ny) in neighbours(&map, x, y) {
+                    if !visited[ny][nx] && map[ny][nx] != 9 {
+                        visited[ny][nx] = true;
+                        pending.push((nx, ny));
+                    }
+                }
+            }
+
+            sizes.push(size);
+        }
+    }
+
+    sizes.sort_unstable();
+    sizes.resize(sizes.len().max(3), 0);
+
+    Ok(sizes.iter().rev().take(3).product::<usize>())
+}
+
+fn parse(input: &[&str]) -> Vec<Vec<u8>> {
+    input
+        .iter()
+        .map(|line| line.bytes().map(|byte| byte - b'0').collect())
+        .collect()
+}
+
+fn neighbours(map: &[Vec<u8>], x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    let width = map[0].len() as isize;
+    let height = map.len() as isize;
+
+    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .into_iter()
+        .map(move |(dx, dy)| (x as isize + dx, y as isize + dy))
+        .filter(move |(x, y)| (0..width).contains(x) && (0..height).contains(y))
+        .map(|(x, y)| (x as usize, y as usize))
+}
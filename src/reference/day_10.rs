@@ -0,0 +1,50 @@
+pub const INPUT_SIZE: usize = 50;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut score = 0;
+
+    for line in input {
+        if let Err(illegal) = check(line) {
+            score += match illegal {
+                ')' => 3,
+                ']' => 57,
+                '}' => 1197,
+                _ => 25137,
+            };
+        }
+    }
+
+    Ok(score)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut scores = Vec::new();
+
+    for line in input {
+        if let Ok(missing) = check(line) {
+            let score = missing.chars().rev().fold(0u64, |acc, closing| {
+                acc * 5 + ")]}>".find(closing).unwrap() as u64 + 1
+            });
+
+            scores.push(score);
+        }
+    }
+
+    scores.sort_unstable();
+    Ok(scores[scores.len() / 2])
+}
+
+/// Returns the still open chunks, or the first illegal character.
+fn check(line: &str) -> Result<String, char> {
+    let mut stack = String::new();
+
+    for char in line.chars() {
+        if let Some(index) = "([{<".find(char) {
+            stack.push(")]}>".as_bytes()[index] as char);
+        } else if stack.pop() != Some(char) {
+            return Err(char);
+        }
+    }
+
+    Ok(stack)
+}
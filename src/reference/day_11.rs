@@ -0,0 +1,72 @@
+pub const INPUT_SIZE: usize = 10;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut grid = parse(input);
+    Ok((0..100).map(|_| step(&mut grid)).sum::<usize>())
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut grid = parse(input);
+    let mut steps = 1;
+
+    while step(&mut grid) != 100 {
+        steps += 1;
+    }
+
+    Ok(steps)
+}
+
+fn parse(input: &[&str]) -> Vec<Vec<u32>> {
+    input
+        .iter()
+        .map(|line| line.bytes().map(|byte| (byte - b'0') as u32).collect())
+        .collect()
+}
+
+fn step(grid: &mut [Vec<u32>]) -> usize {
+    let mut flashed = vec![vec![false; 10]; 10];
+
+    for row in grid.iter_mut() {
+        for energy in row.iter_mut() {
+            *energy += 1;
+        }
+    }
+
+    loop {
+        let mut changed = false;
+
+        for y in 0..10 {
+            for x in 0..10 {
+                if grid[y][x] <= 9 || flashed[y][x] {
+                    continue;
+                }
+
+                flashed[y][x] = true;
+                changed = true;
+
+                for row in &mut grid[y.saturating_sub(1)..=(y + 1).min(9)] {
+                    for energy in &mut row[x.saturating_sub(1)..=(x + 1).min(9)] {
+                        *energy += 1;
+                    }
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let mut count = 0;
+
+    for y in 0..10 {
+        for x in 0..10 {
+            if flashed[y][x] {
+                grid[y][x] = 0;
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
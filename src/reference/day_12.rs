@@ -0,0 +1,54 @@
+use anyhow::Context;
+use std::collections::HashMap;
+
+pub const INPUT_SIZE: usize = 8;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let caves = parse(input)?;
+    Ok(count_paths(&caves, "start", &mut vec!["start"], false))
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let caves = parse(input)?;
+    Ok(count_paths(&caves, "start", &mut vec!["start"], true))
+}
+
+fn parse<'a>(input: &[&'a str]) -> anyhow::Result<HashMap<&'a str, Vec<&'a str>>> {
+    let mut caves = HashMap::<_, Vec<_>>::new();
+
+    for line in input {
+        let (a, b) = line.split_once('-').context("Missing `-`")?;
+        caves.entry(a).or_default().push(b);
+        caves.entry(b).or_default().push(a);
+    }
+
+    Ok(caves)
+}
+
+fn count_paths<'a>(
+    caves: &HashMap<&'a str, Vec<&'a str>>,
+    cave: &'a str,
+    path: &mut Vec<&'a str>,
+    revisit: bool,
+) -> usize {
+    if cave == "end" {
+        return 1;
+    }
+
+    let mut count = 0;
+
+    for &next in &caves[cave] {
+        let small = next.chars().all(|char| char.is_ascii_lowercase());
+        let visited = small && path.contains(&next);
+
+        if next == "start" || (visited && !revisit) {
+            continue;
+        }
+
+        path.push(next);
+        count += count_paths(caves, next, path, revisit && !visited);
+        path.pop();
+    }
+
+    count
+}
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use std::collections::BTreeSet;
+
+pub const INPUT_SIZE: usize = 4;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let (dots, folds) = parse(input)?;
+    Ok(fold(dots, folds[0]).len())
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let (mut dots, folds) = parse(input)?;
+
+    for f in folds {
+        dots = fold(dots, f);
+    }
+
+    let width = dots.iter().map(|dot| dot.0).max().unwrap_or_default() + 1;
+    let height = dots.iter().map(|dot| dot.1).max().unwrap_or_default() + 1;
+    let mut output = String::from("\n");
+
+    for y in 0..height {
+        for x in 0..width {
+            output.push(if dots.contains(&(x, y)) { '█' } else { ' ' });
+        }
+
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+type Dots = BTreeSet<(u32, u32)>;
+
+fn fold(dots: Dots, (along_x, position): (bool, u32)) -> Dots {
+    dots.into_iter()
+        .filter(|&(x, y)| {
+            if along_x {
+                x != position
+            } else {
+                y != position
+            }
+        })
+        .map(|(x, y)| {
+            if along_x && x > position {
+                (2 * position - x, y)
+            } else if !along_x && y > position {
+                (x, 2 * position - y)
+            } else {
+                (x, y)
+            }
+        })
+        .collect()
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<(Dots, Vec<(bool, u32)>)> {
+    let mut dots = Dots::new();
+    let mut folds = Vec::new();
+
+    for line in input {
+        if let Some(fold) = line.strip_prefix("fold along ") {
+            let (axis, position) = fold.split_once('=').context("Missing `=`")?;
+            folds.push((axis == "x", position.parse()?));
+        } else if let Some((x, y)) = line.split_once(',') {
+            dots.insert((x.parse()?, y.parse()?));
+        }
+    }
+
+    Ok((dots, folds))
+}
@@ -0,0 +1,109 @@
+use anyhow::Context;
+use std::collections::HashMap;
+
+pub const INPUT_SIZE: usize = 10;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let rules = parse(input)?;
+    let mut polymer = input[0].chars().collect::<Vec<_>>();
+
+    for _ in 0..10 {
+        let mut next = vec![polymer[0]];
+
+        for pair in polymer.windows(2) {
+            if let Some(&insertion) = rules.get(&(pair[0], pair[1])) {
+                next.push(insertion);
+            }
+
+            next.push(pair[1]);
+        }
+
+        polymer = next;
+    }
+
+    let mut counts = HashMap::<char, u64>::new();
+
+    for char in polymer {
+        *counts.entry(char).or_default() += 1;
+    }
+
+    Ok(score(&counts))
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    // expanding the string 40 times would need terabytes, so count what each pair expands into
+    let rules = parse(input)?;
+    let template = input[0].chars().collect::<Vec<_>>();
+
+    let mut cache = HashMap::new();
+    let mut counts = HashMap::<char, u64>::new();
+
+    for char in &template {
+        *counts.entry(*char).or_default() += 1;
+    }
+
+    for pair in template.windows(2) {
+        for (char, count) in inserted(&rules, (pair[0], pair[1]), 40, &mut cache) {
+            *counts.entry(char).or_default() += count;
+        }
+    }
+
+    Ok(score(&counts))
+}
+
+type Rules = HashMap<(char, char), char>;
+type Cache = HashMap<((char, char), usize), HashMap<char, u64>>;
+
+/// Counts the elements inserted between a pair after the given amount of steps.
+fn inserted(
+    rules: &Rules,
+    pair: (char, char),
+    steps: usize,
+    cache: &mut Cache,
+) -> HashMap<char, u64> {
+    if steps == 0 {
+        return HashMap::new();
+    }
+
+    if let Some(counts) = cache.get(&(pair, steps)) {
+        return counts.clone();
+    }
+
+    let mut counts = HashMap::new();
+
+    if let Some(&insertion) = rules.get(&pair) {
+        counts.insert(insertion, 1);
+
+        for half in [(pair.0, insertion), (insertion, pair.1)] {
+            for (char, count) in inserted(rules, half, steps - 1, cache) {
+                *counts.entry(char).or_default() += count;
+            }
+        }
+    }
+
+    cache.insert((pair, steps), counts.clone());
+    counts
+}
+
+fn score(counts: &HashMap<char, u64>) -> u64 {
+    counts.values().max().unwrap() - counts.values().min().unwrap()
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<Rules> {
+    let mut rules = Rules::new();
+
+    for line in &input[2..] {
+        let (pair, insertion) = line.split_once(" -> ").context("Missing `->`")?;
+        let mut pair = pair.chars();
+
+        rules.insert(
+            (
+                pair.next().context("Empty pair")?,
+                pair.next().context("Short pair")?,
+            ),
+            insertion.chars().next().context("Empty insertion")?,
+        );
+    }
+
+    Ok(rules)
+}
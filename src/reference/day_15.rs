@@ -0,0 +1,66 @@
+pub const INPUT_SIZE: usize = 15;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    Ok(lowest_risk(&parse(input)))
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let tile = parse(input);
+    let size = tile.len();
+    let mut grid = vec![vec![0; size * 5]; size * 5];
+
+    for (y, row) in grid.iter_mut().enumerate() {
+        for (x, risk) in row.iter_mut().enumerate() {
+            let original = tile[y % size][x % size] + (x / size + y / size) as u32;
+            *risk = (original - 1) % 9 + 1;
+        }
+    }
+
+    Ok(lowest_risk(&grid))
+}
+
+fn parse(input: &[&str]) -> Vec<Vec<u32>> {
+    input
+        .iter()
+        .map(|line| line.bytes().map(|byte| (byte - b'0') as u32).collect())
+        .collect()
+}
+
+/// Keeps relaxing every cell until none of them improve anymore.
+fn lowest_risk(grid: &[Vec<u32>]) -> u32 {
+    let size = grid.len();
+    let mut risks = vec![vec![u32::MAX; size]; size];
+    risks[0][0] = 0;
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for y in 0..size {
+            for x in 0..size {
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+
+                for (nx, ny) in neighbours {
+                    if nx >= size || ny >= size || risks[ny][nx] == u32::MAX {
+                        continue;
+                    }
+
+                    let risk = risks[ny][nx] + grid[y][x];
+
+                    if risk < risks[y][x] {
+                        risks[y][x] = risk;
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    risks[size - 1][size - 1]
+}
@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Context};
+
+pub const INPUT_SIZE: usize = 30;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let bits = to_binary(input[0])?;
+    Ok(parse(&bits, &mut 0)?.0)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let bits = to_binary(input[0])?;
+    Ok(parse(&bits, &mut 0)?.1)
+}
+
+fn to_binary(hex: &str) -> anyhow::Result<String> {
+    hex.chars()
+        .map(|char| {
+            let value = char.to_digit(16).context("Not a hex digit")?;
+            Ok(format!("{:04b}", value))
+        })
+        .collect()
+}
+
+fn read(bits: &str, position: &mut usize, length: usize) -> anyhow::Result<u64> {
+    let slice = bits
+        .get(*position..*position + length)
+        .context("Unexpected end of transmission")?;
+
+    *position += length;
+    Ok(u64::from_str_radix(slice, 2)?)
+}
+
+/// Returns the sum of all versions and the value of the packet.
+fn parse(bits: &str, position: &mut usize) -> anyhow::Result<(u64, u64)> {
+    let mut version = read(bits, position, 3)?;
+    let type_id = read(bits, position, 3)?;
+
+    if type_id == 4 {
+        let mut value = 0;
+
+        loop {
+            let last = read(bits, position, 1)? == 0;
+            value = value << 4 | read(bits, position, 4)?;
+
+            if last {
+                return Ok((version, value));
+            }
+        }
+    }
+
+    let mut values = Vec::new();
+
+    if read(bits, position, 1)? == 0 {
+        let length = read(bits, position, 15)? as usize;
+        let end = *position + length;
+
+        while *position < end {
+            let (v, value) = parse(bits, position)?;
+            version += v;
+            values.push(value);
+        }
+    } else {
+        for _ in 0..read(bits, position, 11)? {
+            let (v, value) = parse(bits, position)?;
+            version += v;
+            values.push(value);
+        }
+    }
+
+    let value = match type_id {
+        0 => values.iter().sum(),
+        1 => values.iter().product(),
+        2 => *values.iter().min().context("Empty minimum")?,
+        3 => *values.iter().max().context("Empty maximum")?,
+        5 => (values[0] > values[1]) as u64,
+        6 => (values[0] < values[1]) as u64,
+        7 => (values[0] == values[1]) as u64,
+        _ => return Err(anyhow!("Unknown type id {}", type_id)),
+    };
+
+    Ok((version, value))
+}
@@ -0,0 +1,46 @@
+use anyhow::Context;
+
+pub const INPUT_SIZE: usize = 40;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let hits = find_hits(input)?;
+    let apex = |vy: i32| if vy > 0 { vy * (vy + 1) / 2 } else { 0 };
+    Ok(hits.iter().map(|&(_, vy)| apex(vy)).max().unwrap_or(0))
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    Ok(find_hits(input)?.len())
+}
+
+fn find_hits(input: &[&str]) -> anyhow::Result<Vec<(i32, i32)>> {
+    let numbers = input[0]
+        .split(|char: char| !char.is_ascii_digit() && char != '-')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse())
+        .collect::<Result<Vec<i32>, _>>()?;
+
+    let [min_x, max_x, min_y, max_y] = <[i32; 4]>::try_from(numbers)
+        .ok()
+        .context("Invalid target")?;
+    let mut hits = Vec::new();
+
+    for vx in 0..=max_x {
+        for vy in min_y..=-min_y {
+            let (mut x, mut y, mut dx, mut dy) = (0, 0, vx, vy);
+
+            while x <= max_x && y >= min_y {
+                if x >= min_x && y <= max_y {
+                    hits.push((vx, vy));
+                    break;
+                }
+
+                x += dx;
+                y += dy;
+                dx -= dx.signum();
+                dy -= 1;
+            }
+        }
+    }
+
+    Ok(hits)
+}
@@ -0,0 +1,129 @@
+pub const INPUT_SIZE: usize = 20;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut numbers = input.iter().map(|line| parse(&mut line.bytes()));
+    let first = numbers.next().unwrap();
+    Ok(numbers.fold(first, add).magnitude())
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let numbers = input
+        .iter()
+        .map(|line| parse(&mut line.bytes()))
+        .collect::<Vec<_>>();
+
+    let mut max = 0;
+
+    for (i, x) in numbers.iter().enumerate() {
+        for (j, y) in numbers.iter().enumerate() {
+            if i != j {
+                max = max.max(add(x.clone(), y.clone()).magnitude());
+            }
+        }
+    }
+
+    Ok(max)
+}
+
+#[derive(Clone)]
+enum Number {
+    Regular(u32),
+    Pair(Box<Number>, Box<Number>),
+}
+
+impl Number {
+    fn magnitude(&self) -> u32 {
+        match self {
+            Number::Regular(value) => *value,
+            Number::Pair(left, right) => 3 * left.magnitude() + 2 * right.magnitude(),
+        }
+    }
+
+    fn regular(&self) -> Option<u32> {
+        match self {
+            Number::Regular(value) => Some(*value),
+            Number::Pair(..) => None,
+        }
+    }
+
+    /// Explodes the leftmost pair nested inside four pairs, returning the values to spread.
+    fn explode(&mut self, depth: usize) -> Option<(Option<u32>, Option<u32>)> {
+        let (left, right) = match self {
+            Number::Regular(_) => return None,
+            Number::Pair(left, right) => (left, right),
+        };
+
+        if depth >= 4 {
+            if let (Some(a), Some(b)) = (left.regular(), right.regular()) {
+                *self = Number::Regular(0);
+                return Some((Some(a), Some(b)));
+            }
+        }
+
+        if let Some((a, b)) = left.explode(depth + 1) {
+            if let Some(b) = b {
+                right.add_leftmost(b);
+            }
+
+            return Some((a, None));
+        }
+
+        if let Some((a, b)) = right.explode(depth + 1) {
+            if let Some(a) = a {
+                left.add_rightmost(a);
+            }
+
+            return Some((None, b));
+        }
+
+        None
+    }
+
+    fn split(&mut self) -> bool {
+        match self {
+            Number::Regular(value) if *value >= 10 => {
+                let left = Number::Regular(*value / 2);
+                let right = Number::Regular(*value - *value / 2);
+                *self = Number::Pair(Box::new(left), Box::new(right));
+                true
+            }
+            Number::Regular(_) => false,
+            Number::Pair(left, right) => left.split() || right.split(),
+        }
+    }
+
+    fn add_leftmost(&mut self, amount: u32) {
+        match self {
+            Number::Regular(value) => *value += amount,
+            Number::Pair(left, _) => left.add_leftmost(amount),
+        }
+    }
+
+    fn add_rightmost(&mut self, amount: u32) {
+        match self {
+            Number::Regular(value) => *value += amount,
+            Number::Pair(_, right) => right.add_rightmost(amount),
+        }
+    }
+}
+
+fn add(left: Number, right: Number) -> Number {
+    let mut number = Number::Pair(Box::new(left), Box::new(right));
+
+    while number.explode(0).is_some() || number.split() {}
+
+    number
+}
+
+fn parse(bytes: &mut impl Iterator<Item = u8>) -> Number {
+    match bytes.next().unwrap() {
+        b'[' => {
+            let left = parse(bytes);
+            bytes.next(); // ,
+            let right = parse(bytes);
+            bytes.next(); // ]
+            Number::Pair(Box::new(left), Box::new(right))
+        }
+        digit => Number::Regular((digit - b'0') as u32),
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+pub const INPUT_SIZE: usize = 6;
+
+const MIN_OVERLAP: usize = 12;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let (beacons, _) = align(input)?;
+    Ok(beacons.len())
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let (_, scanners) = align(input)?;
+    let mut max = 0;
+
+    for a in &scanners {
+        for b in &scanners {
+            max = max.max((0..3).map(|i| (a[i] - b[i]).abs()).sum::<i32>());
+        }
+    }
+
+    Ok(max)
+}
+
+type Point = [i32; 3];
+
+/// Tries every rotation and every beacon pairing until each scanner overlaps an aligned one.
+fn align(input: &[&str]) -> anyhow::Result<(HashSet<Point>, Vec<Point>)> {
+    let reports = parse(input)?;
+    let rotations = rotations();
+
+    let mut aligned = vec![None; reports.len()];
+    aligned[0] = Some((reports[0].iter().copied().collect::<HashSet<_>>(), [0; 3]));
+
+    let mut tried = HashSet::new();
+    let mut progress = true;
+
+    while progress {
+        progress = false;
+
+        for j in 0..reports.len() {
+            if aligned[j].is_some() {
+                continue;
+            }
+
+            'search: for i in 0..reports.len() {
+                let known = match &aligned[i] {
+                    Some((beacons, _)) if tried.insert((i, j)) => beacons.clone(),
+                    _ => continue,
+                };
+
+                for rotation in &rotations {
+                    let rotated = reports[j]
+                        .iter()
+                        .map(|beacon| rotate(rotation, beacon))
+                        .collect::<Vec<_>>();
+
+                    for a in &known {
+                        for b in &rotated {
+                            let offset = [0, 1, 2].map(|k| a[k] - b[k]);
+
+                            let moved = rotated
+                                .iter()
+                                .map(|beacon| [0, 1, 2].map(|k| beacon[k] + offset[k]))
+                                .collect::<Vec<_>>();
+
+                            let overlap = moved.iter().filter(|beacon| known.contains(*beacon));
+
+                            if overlap.count() >= MIN_OVERLAP {
+                                aligned[j] = Some((moved.into_iter().collect(), offset));
+                                progress = true;
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut beacons = HashSet::new();
+    let mut scanners = Vec::new();
+
+    for alignment in aligned {
+        let (report, scanner) = alignment.ok_or_else(|| anyhow::anyhow!("Unaligned scanner"))?;
+        beacons.extend(report);
+        scanners.push(scanner);
+    }
+
+    Ok((beacons, scanners))
+}
+
+fn rotate(rotation: &[i32; 9], point: &Point) -> Point {
+    [0, 1, 2].map(|row| (0..3).map(|i| rotation[row * 3 + i] * point[i]).sum())
+}
+
+fn rotations() -> Vec<[i32; 9]> {
+    let mut rotations = Vec::new();
+
+    for x in 0..3 {
+        for y in 0..3 {
+            for z in 0..3 {
+                if x == y || y == z || x == z {
+                    continue;
+                }
+
+                for signs in 0..8 {
+                    let mut m = [0; 9];
+                    m[x] = if signs & 1 == 0 { 1 } else { -1 };
+                    m[3 + y] = if signs & 2 == 0 { 1 } else { -1 };
+                    m[6 + z] = if signs & 4 == 0 { 1 } else { -1 };
+
+                    let determinant = m[0] * (m[4] * m[8] - m[5] * m[7])
+                        - m[1] * (m[3] * m[8] - m[5] * m[6])
+                        + m[2] * (m[3] * m[7] - m[4] * m[6]);
+
+                    if determinant == 1 {
+                        rotations.push(m);
+                    }
+                }
+            }
+        }
+    }
+
+    rotations
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<Vec<Vec<Point>>> {
+    let mut reports = Vec::new();
+
+    for line in input {
+        if line.starts_with("---") {
+            reports.push(Vec::new());
+        } else if !line.is_empty() {
+            let values = line
+                .split(',')
+                .map(|value| value.parse())
+                .collect::<Result<Vec<i32>, _>>()?;
+
+            reports
+                .last_mut()
+                .unwrap()
+                .push([values[0], values[1], values[2]]);
+        }
+    }
+
+    Ok(reports)
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+pub const INPUT_SIZE: usize = 10;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    enhance(input, 2)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    enhance(input, 50)
+}
+
+fn enhance(input: &[&str], steps: usize) -> anyhow::Result<usize> {
+    let algorithm = input[0]
+        .bytes()
+        .map(|byte| byte == b'#')
+        .collect::<Vec<_>>();
+    let mut lit = HashSet::new();
+
+    for (y, line) in input[2..].iter().enumerate() {
+        for (x, byte) in line.bytes().enumerate() {
+            if byte == b'#' {
+                lit.insert((x as i32, y as i32));
+            }
+        }
+    }
+
+    let mut min = (0, 0);
+    let mut max = (input[2].len() as i32 - 1, input.len() as i32 - 3);
+
+    // every pixel outside of the bounds shares the same state
+    let mut background = false;
+
+    for _ in 0..steps {
+        let mut next = HashSet::new();
+
+        for y in min.1 - 1..=max.1 + 1 {
+            for x in min.0 - 1..=max.0 + 1 {
+                let mut index = 0;
+
+                for ny in y - 1..=y + 1 {
+                    for nx in x - 1..=x + 1 {
+                        let inside = (min.0..=max.0).contains(&nx) && (min.1..=max.1).contains(&ny);
+                        let pixel = if inside {
+                            lit.contains(&(nx, ny))
+                        } else {
+                            background
+                        };
+                        index = index << 1 | pixel as usize;
+                    }
+                }
+
+                if algorithm[index] {
+                    next.insert((x, y));
+                }
+            }
+        }
+
+        background = algorithm[if background { 511 } else { 0 }];
+        lit = next;
+        min = (min.0 - 1, min.1 - 1);
+        max = (max.0 + 1, max.1 + 1);
+    }
+
+    Ok(lit.len())
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+pub const INPUT_SIZE: usize = 2;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut positions = parse(input)?;
+    let mut scores = [0, 0];
+    let mut rolls = 0;
+    let mut player = 0;
+
+    loop {
+        let mut moves = 0;
+
+        for _ in 0..3 {
+            moves += rolls % 100 + 1;
+            rolls += 1;
+        }
+
+        positions[player] = (positions[player] + moves - 1) % 10 + 1;
+        scores[player] += positions[player];
+
+        if scores[player] >= 1000 {
+            return Ok(scores[1 - player] * rolls);
+        }
+
+        player = 1 - player;
+    }
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let positions = parse(input)?;
+    let wins = count_wins(positions[0], positions[1], 0, 0, &mut HashMap::new());
+    Ok(wins.0.max(wins.1))
+}
+
+type Cache = HashMap<(u64, u64, u64, u64), (u64, u64)>;
+
+/// Counts the universes in which the current and the other player win.
+fn count_wins(
+    position: u64,
+    other: u64,
+    score: u64,
+    other_score: u64,
+    cache: &mut Cache,
+) -> (u64, u64) {
+    if other_score >= 21 {
+        return (0, 1);
+    }
+
+    let key = (position, other, score, other_score);
+
+    if let Some(&wins) = cache.get(&key) {
+        return wins;
+    }
+
+    let mut wins = (0, 0);
+
+    for a in 1..=3 {
+        for b in 1..=3 {
+            for c in 1..=3 {
+                let position = (position + a + b + c - 1) % 10 + 1;
+                let (theirs, mine) =
+                    count_wins(other, position, other_score, score + position, cache);
+                wins.0 += mine;
+                wins.1 += theirs;
+            }
+        }
+    }
+
+    cache.insert(key, wins);
+    wins
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<[u64; 2]> {
+    let position = |line: &str| line.rsplit(' ').next().unwrap_or_default().parse::<u64>();
+    Ok([position(input[0])?, position(input[1])?])
+}
@@ -0,0 +1,88 @@
+use anyhow::Context;
+
+pub const INPUT_SIZE: usize = 30;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut cubes = vec![false; 101 * 101 * 101];
+
+    for (on, [x, y, z]) in parse(input)? {
+        let clip = |(min, max): (i64, i64)| min.max(-50)..=max.min(50);
+
+        for x in clip(x) {
+            for y in clip(y) {
+                for z in clip(z) {
+                    cubes[((x + 50) + (y + 50) * 101 + (z + 50) * 101 * 101) as usize] = on;
+                }
+            }
+        }
+    }
+
+    Ok(cubes.iter().filter(|&&on| on).count())
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    // the full region has far too many cubes, so split it along every cuboid boundary instead
+    let steps = parse(input)?;
+
+    let axes = [0, 1, 2].map(|axis| {
+        let mut bounds = steps
+            .iter()
+            .flat_map(|(_, ranges)| [ranges[axis].0, ranges[axis].1 + 1])
+            .collect::<Vec<_>>();
+
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds
+    });
+
+    let [xs, ys, zs] = &axes;
+    let mut cells = vec![false; xs.len() * ys.len() * zs.len()];
+
+    for (on, [x, y, z]) in &steps {
+        let range = |bounds: &[i64], (min, max): (i64, i64)| {
+            bounds.iter().position(|&b| b == min).unwrap()
+                ..bounds.iter().position(|&b| b == max + 1).unwrap()
+        };
+
+        for i in range(xs, *x) {
+            for j in range(ys, *y) {
+                for k in range(zs, *z) {
+                    cells[i + j * xs.len() + k * xs.len() * ys.len()] = *on;
+                }
+            }
+        }
+    }
+
+    let mut volume = 0i64;
+
+    for i in 0..xs.len() - 1 {
+        for j in 0..ys.len() - 1 {
+            for k in 0..zs.len() - 1 {
+                if cells[i + j * xs.len() + k * xs.len() * ys.len()] {
+                    volume += (xs[i + 1] - xs[i]) * (ys[j + 1] - ys[j]) * (zs[k + 1] - zs[k]);
+                }
+            }
+        }
+    }
+
+    Ok(volume)
+}
+
+type Step = (bool, [(i64, i64); 3]);
+
+fn parse(input: &[&str]) -> anyhow::Result<Vec<Step>> {
+    input
+        .iter()
+        .map(|line| {
+            let (state, ranges) = line.split_once(' ').context("Missing state")?;
+            let mut bounds = [(0, 0); 3];
+
+            for (i, range) in ranges.split(',').enumerate() {
+                let (min, max) = range[2..].split_once("..").context("Missing `..`")?;
+                bounds[i] = (min.parse()?, max.parse()?);
+            }
+
+            Ok((state == "on", bounds))
+        })
+        .collect()
+}
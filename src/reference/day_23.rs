@@ -0,0 +1,134 @@
+use anyhow::anyhow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+pub const INPUT_SIZE: usize = 8;
+
+const HALLWAY_LENGTH: usize = 11;
+const DOORS: [usize; 4] = [2, 4, 6, 8];
+const EMPTY: u8 = b'.';
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    dijkstra(parse(&input[2..4]))
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    dijkstra(parse(&[input[2], "  #D#C#B#A#", "  #D#B#A#C#", input[3]]))
+}
+
+/// The hallway, followed by every room from top to bottom.
+type Burrow = Vec<u8>;
+
+fn parse(rows: &[&str]) -> Burrow {
+    let mut burrow = vec![EMPTY; HALLWAY_LENGTH];
+
+    for room in 0..4 {
+        for row in rows {
+            burrow.push(row.as_bytes()[3 + 2 * room]);
+        }
+    }
+
+    burrow
+}
+
+fn dijkstra(start: Burrow) -> anyhow::Result<u32> {
+    let depth = (start.len() - HALLWAY_LENGTH) / 4;
+    let mut energies = HashMap::new();
+    let mut queue = BinaryHeap::new();
+
+    energies.insert(start.clone(), 0);
+    queue.push(Reverse((0, start)));
+
+    while let Some(Reverse((energy, burrow))) = queue.pop() {
+        if energies[&burrow] < energy {
+            continue;
+        }
+
+        let done = (0..4).all(|room| {
+            (0..depth).all(|d| burrow[HALLWAY_LENGTH + room * depth + d] == b'A' + room as u8)
+        });
+
+        if done {
+            return Ok(energy);
+        }
+
+        for (next, cost) in moves(&burrow, depth) {
+            let energy = energy + cost;
+
+            if energies.get(&next).is_none_or(|&known| energy < known) {
+                energies.insert(next.clone(), energy);
+                queue.push(Reverse((energy, next)));
+            }
+        }
+    }
+
+    Err(anyhow!("Cannot find solution"))
+}
+
+fn moves(burrow: &Burrow, depth: usize) -> Vec<(Burrow, u32)> {
+    let mut moves = Vec::new();
+    let slot = |room: usize, d: usize| HALLWAY_LENGTH + room * depth + d;
+    let clear = |from: usize, to: usize| (from.min(to)..=from.max(to)).all(|i| burrow[i] == EMPTY);
+
+    for room in 0..4 {
+        let top = match (0..depth).find(|&d| burrow[slot(room, d)] != EMPTY) {
+            Some(top) => top,
+            None => continue,
+        };
+
+        let kind = burrow[slot(room, top)];
+        let settled = (top..depth).all(|d| burrow[slot(room, d)] == b'A' + room as u8);
+
+        if settled {
+            continue;
+        }
+
+        for spot in (0..HALLWAY_LENGTH).filter(|spot| !DOORS.contains(spot)) {
+            if clear(DOORS[room], spot) {
+                let mut next = burrow.clone();
+                next.swap(slot(room, top), spot);
+                let steps = top + 1 + DOORS[room].abs_diff(spot);
+                moves.push((next, steps as u32 * energy(kind)));
+            }
+        }
+    }
+
+    for spot in 0..HALLWAY_LENGTH {
+        let kind = burrow[spot];
+
+        if kind == EMPTY {
+            continue;
+        }
+
+        let room = (kind - b'A') as usize;
+        let door = DOORS[room];
+
+        let path_clear = if spot < door {
+            clear(spot + 1, door)
+        } else {
+            clear(door, spot - 1)
+        };
+
+        let only_own_kind = (0..depth)
+            .all(|d| matches!(burrow[slot(room, d)], EMPTY) || burrow[slot(room, d)] == kind);
+
+        if !path_clear || !only_own_kind {
+            continue;
+        }
+
+        let target = (0..depth)
+            .rev()
+            .find(|&d| burrow[slot(room, d)] == EMPTY)
+            .unwrap();
+        let mut next = burrow.clone();
+        next.swap(spot, slot(room, target));
+        let steps = spot.abs_diff(door) + target + 1;
+        moves.push((next, steps as u32 * energy(kind)));
+    }
+
+    moves
+}
+
+fn energy(kind: u8) -> u32 {
+    10u32.pow((kind - b'A') as u32)
+}
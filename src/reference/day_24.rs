@@ -0,0 +1,236 @@
+use anyhow::{anyhow, Context};
+use std::collections::HashSet;
+
+pub const INPUT_SIZE: usize = 14;
+
+/// Most digits worth also trying every model number for, at `9^7` runs of the program.
+const MAX_DIGITS: usize = 7;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    search(input, true)
+}
+
+pub fn part_b(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    search(input, false)
+}
+
+enum Operand {
+    Register(usize),
+    Literal(i64),
+}
+
+struct Instruction {
+    op: String,
+    register: usize,
+    operand: Operand,
+}
+
+/// Searches digit by digit, and for programs short enough also tries every model number in turn
+/// to check that both agree.
+fn search(input: &[&str], largest: bool) -> anyhow::Result<u64> {
+    let program = parse(input)?;
+    let digits = program
+        .iter()
+        .filter(|instruction| instruction.op == "inp")
+        .count();
+    let found = find_by_digits(&program, largest);
+
+    if digits > MAX_DIGITS {
+        return found;
+    }
+
+    match (found, try_every_number(&program, largest)) {
+        (Ok(found), Ok(tried)) if found == tried => Ok(found),
+        (Err(error), Err(_)) => Err(error),
+        (found, tried) => Err(anyhow!(
+            "Searching digit by digit found {:?}, trying every number found {:?}",
+            found.ok(),
+            tried.ok()
+        )),
+    }
+}
+
+/// Tries every digit in order, running the program up to the next input after each one, and
+/// remembers the registers that can no longer lead to a valid number.
+fn find_by_digits(program: &[Instruction], largest: bool) -> anyhow::Result<u64> {
+    let digits = match largest {
+        true => [9, 8, 7, 6, 5, 4, 3, 2, 1],
+        false => [1, 2, 3, 4, 5, 6, 7, 8, 9],
+    };
+
+    // whatever comes before the first input doesn't depend on any digit
+    let first = program
+        .iter()
+        .position(|instruction| instruction.op == "inp")
+        .unwrap_or(program.len());
+    let registers = run_from(&program[..first], [0; 4], &[]);
+
+    let mut search = Search {
+        blocks: program[first..]
+            .chunk_by(|_, next| next.op != "inp")
+            .collect(),
+        limits: Vec::new(),
+        digits,
+        dead_ends: HashSet::new(),
+        number: Vec::new(),
+    };
+
+    // `z` only ever shrinks through `div z` in MONAD, so it can't get back to 0 when it's larger
+    // than all the divisors left put together. Other programs may break that, which is why short
+    // ones are also checked by trying every number.
+    let mut limit = 1i64;
+
+    for block in search.blocks.iter().rev() {
+        for instruction in block.iter() {
+            if let ("div", 3, Operand::Literal(divisor)) = (
+                instruction.op.as_str(),
+                instruction.register,
+                &instruction.operand,
+            ) {
+                limit = limit.saturating_mul(divisor.abs());
+            }
+        }
+
+        search.limits.push(limit);
+    }
+
+    search.limits.reverse();
+
+    if search.find(0, registers) {
+        Ok(search
+            .number
+            .iter()
+            .fold(0, |acc, &digit| acc * 10 + digit as u64))
+    } else {
+        Err(anyhow!("No model number is accepted"))
+    }
+}
+
+/// Depth-first search over the digits, one block of the program from an input to the next at a
+/// time.
+struct Search<'a> {
+    blocks: Vec<&'a [Instruction]>,
+    /// Largest `z` entering each block that the remaining blocks can still divide down to 0.
+    limits: Vec<i64>,
+    digits: [i64; 9],
+    dead_ends: HashSet<(usize, [i64; 4])>,
+    number: Vec<i64>,
+}
+
+impl Search<'_> {
+    /// Whether the blocks from `block` on accept some digits when starting from the registers,
+    /// leaving those digits in `number` if so.
+    fn find(&mut self, block: usize, registers: [i64; 4]) -> bool {
+        if block == self.blocks.len() {
+            return registers[3] == 0;
+        }
+
+        if registers[3].abs() > self.limits[block] || self.dead_ends.contains(&(block, registers)) {
+            return false;
+        }
+
+        for digit in self.digits {
+            self.number.push(digit);
+
+            let next = run_from(self.blocks[block], registers, &[digit]);
+
+            if self.find(block + 1, next) {
+                return true;
+            }
+
+            self.number.pop();
+        }
+
+        self.dead_ends.insert((block, registers));
+        false
+    }
+}
+
+/// Runs the program on every model number in turn, from the largest or the smallest one on, until
+/// one of them leaves 0 in `z`.
+fn try_every_number(program: &[Instruction], largest: bool) -> anyhow::Result<u64> {
+    let digits = program
+        .iter()
+        .filter(|instruction| instruction.op == "inp")
+        .count();
+    let count = 9u64.pow(digits as u32);
+
+    for index in 0..count {
+        let index = if largest { count - 1 - index } else { index };
+
+        // the model number is the index written in base 9, with every digit one higher
+        let number = (0..digits)
+            .rev()
+            .map(|position| (index / 9u64.pow(position as u32) % 9 + 1) as i64)
+            .collect::<Vec<_>>();
+
+        if run_from(program, [0; 4], &number)[3] == 0 {
+            return Ok(number.iter().fold(0, |acc, &digit| acc * 10 + digit as u64));
+        }
+    }
+
+    Err(anyhow!("No model number is accepted"))
+}
+
+/// Registers after running the program from the given ones on, reading the digits.
+fn run_from(program: &[Instruction], mut registers: [i64; 4], digits: &[i64]) -> [i64; 4] {
+    let mut digits = digits.iter();
+
+    for instruction in program {
+        let value = match instruction.operand {
+            Operand::Register(index) => registers[index],
+            Operand::Literal(value) => value,
+        };
+
+        let register = &mut registers[instruction.register];
+
+        *register = match instruction.op.as_str() {
+            "inp" => *digits.next().unwrap(),
+            "add" => *register + value,
+            "mul" => *register * value,
+            "div" => *register / value,
+            "mod" => *register % value,
+            _ => (*register == value) as i64,
+        };
+    }
+
+    registers
+}
+
+fn parse(input: &[&str]) -> anyhow::Result<Vec<Instruction>> {
+    input
+        .iter()
+        .map(|line| {
+            let parts = line.split(' ').collect::<Vec<_>>();
+            let target = register(parts.get(1).context("Missing register")?)?;
+
+            let operand = match parts.get(2) {
+                None => Operand::Literal(0),
+                Some(operand) => match register(operand) {
+                    Ok(index) => Operand::Register(index),
+                    Err(_) => Operand::Literal(operand.parse()?),
+                },
+            };
+
+            if !matches!(parts[0], "inp" | "add" | "mul" | "div" | "mod" | "eql") {
+                return Err(anyhow!("Unknown instruction {}", parts[0]));
+            }
+
+            Ok(Instruction {
+                op: parts[0].to_string(),
+                register: target,
+                operand,
+            })
+        })
+        .collect()
+}
+
+fn register(name: &str) -> anyhow::Result<usize> {
+    match name {
+        "w" => Ok(0),
+        "x" => Ok(1),
+        "y" => Ok(2),
+        "z" => Ok(3),
+        _ => Err(anyhow!("{} is not a register", name)),
+    }
+}
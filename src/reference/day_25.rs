@@ -0,0 +1,37 @@
+pub const INPUT_SIZE: usize = 30;
+
+pub fn part_a(input: &[&str]) -> anyhow::Result<impl std::fmt::Display> {
+    let mut grid = input
+        .iter()
+        .map(|line| line.bytes().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let height = grid.len();
+    let width = grid[0].len();
+    let mut steps = 0;
+
+    loop {
+        steps += 1;
+        let mut moved = false;
+
+        for (herd, dx, dy) in [(b'>', 1, 0), (b'v', 0, 1)] {
+            let current = grid.clone();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let (nx, ny) = ((x + dx) % width, (y + dy) % height);
+
+                    if current[y][x] == herd && current[ny][nx] == b'.' {
+                        grid[y][x] = b'.';
+                        grid[ny][nx] = herd;
+                        moved = true;
+                    }
+                }
+            }
+        }
+
+        if !moved {
+            return Ok(steps);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Deliberately simple solutions, used to check the optimised ones on generated inputs.
+//!
+//! These favour obviously correct code over speed, so they're only fed small inputs.
+
+use crate::challenge::{self, Challenge, Day, Part};
+use anyhow::{anyhow, Context};
+
+pub mod day_01;
+pub mod day_02;
+pub mod day_03;
+pub mod day_04;
+pub mod day_05;
+pub mod day_06;
+pub mod day_07;
+pub mod day_08;
+pub mod day_09;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
+pub mod day_16;
+pub mod day_17;
+pub mod day_18;
+pub mod day_19;
+pub mod day_20;
+pub mod day_21;
+pub mod day_22;
+pub mod day_23;
+pub mod day_24;
+pub mod day_25;
+
+const DEFAULT_COUNT: u64 = 10;
+
+/// Size of the generated inputs, small enough for the reference solution to handle.
+fn input_size(day: Day) -> usize {
+    use Day::*;
+
+    match day {
+        Day01 => day_01::INPUT_SIZE,
+        Day02 => day_02::INPUT_SIZE,
+        Day03 => day_03::INPUT_SIZE,
+        Day04 => day_04::INPUT_SIZE,
+        Day05 => day_05::INPUT_SIZE,
+        Day06 => day_06::INPUT_SIZE,
+        Day07 => day_07::INPUT_SIZE,
+        Day08 => day_08::INPUT_SIZE,
+        Day09 => day_09::INPUT_SIZE,
+        Day10 => day_10::INPUT_SIZE,
+        Day11 => day_11::INPUT_SIZE,
+        Day12 => day_12::INPUT_SIZE,
+        Day13 => day_13::INPUT_SIZE,
+        Day14 => day_14::INPUT_SIZE,
+        Day15 => day_15::INPUT_SIZE,
+        Day16 => day_16::INPUT_SIZE,
+        Day17 => day_17::INPUT_SIZE,
+        Day18 => day_18::INPUT_SIZE,
+        Day19 => day_19::INPUT_SIZE,
+        Day20 => day_20::INPUT_SIZE,
+        Day21 => day_21::INPUT_SIZE,
+        Day22 => day_22::INPUT_SIZE,
+        Day23 => day_23::INPUT_SIZE,
+        Day24 => day_24::INPUT_SIZE,
+        Day25 => day_25::INPUT_SIZE,
+    }
+}
+
+/// Handles `verify <day|all> [count]`, comparing both solutions on `count` generated inputs.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let days = match args.first().map(String::as_str) {
+        Some("all") | None => (1..=25).map(Day::try_from).collect::<Result<Vec<_>, _>>()?,
+        Some(day) => vec![day.parse()?],
+    };
+
+    let count = match args.get(1) {
+        Some(count) => count.parse().context("Count is not a number")?,
+        None => DEFAULT_COUNT,
+    };
+
+    // panics are reported as mismatches, there's no need to also print them
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut mismatches = 0;
+    let mut result = Ok(());
+
+    for day in days {
+        let mut checked = 0;
+
+        for seed in 0..count {
+            match compare(day, seed, input_size(day)) {
+                Ok((compared, found)) => {
+                    checked += compared;
+                    mismatches += found.len();
+                    found.iter().for_each(|mismatch| println!("{}", mismatch));
+                }
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+
+        println!("{}: {} results compared", day, checked);
+    }
+
+    std::panic::set_hook(hook);
+    result?;
+
+    if mismatches > 0 {
+        Err(anyhow!("Found {} mismatching results", mismatches))
+    } else {
+        Ok(())
+    }
+}
+
+/// Runs both solutions of both parts on the generated input, returning how many results were
+/// compared and a description of every one that didn't match.
+fn compare(day: Day, seed: u64, size: usize) -> anyhow::Result<(usize, Vec<String>)> {
+    let input = crate::generator::generate(day, seed, Some(size))?;
+    let input = input.lines().collect::<Vec<_>>();
+    let mut compared = 0;
+    let mut mismatches = Vec::new();
+
+    for part in [Part::PartA, Part::PartB] {
+        let challenge = Challenge::new(day, part);
+
+        if !challenge::has_solution(&challenge) {
+            continue;
+        }
+
+        let expected = challenge::evaluate_reference(&challenge, &input);
+        let actual = challenge::evaluate(&challenge, &input);
+        compared += 1;
+
+        match (expected, actual) {
+            (Ok(expected), Ok(actual)) if expected == actual => {}
+            // neither of them accepts the input, which is just as consistent
+            (Err(_), Err(_)) => {}
+            (expected, actual) => mismatches.push(format!(
+                "{} (seed = {}): expected {}, got {}",
+                challenge,
+                seed,
+                describe(expected),
+                describe(actual)
+            )),
+        }
+    }
+
+    Ok((compared, mismatches))
+}
+
+fn describe(result: anyhow::Result<String>) -> String {
+    match result {
+        Ok(value) => format!("`{}`", value.trim()),
+        Err(error) => format!("error `{}`", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solutions_match_references_on_small_inputs() {
+        for day in (1..=25).map(|day| Day::try_from(day).unwrap()) {
+            // brute forcing the beacons takes a while without optimisations
+            let size = match day {
+                Day::Day19 => 3,
+                day => input_size(day),
+            };
+
+            for seed in 0..2 {
+                let (_, mismatches) = compare(day, seed, size).unwrap();
+                assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+            }
+        }
+    }
+
+    #[test]
+    fn monad_pairing_matches_reference() {
+        // 14 digits is the only size the push/pop pairing handles, and 4 is few enough for the
+        // reference to also try every model number
+        for (size, seed) in [14, 4]
+            .into_iter()
+            .flat_map(|size| (0..4).map(move |seed| (size, seed)))
+        {
+            let (compared, mismatches) = compare(Day::Day24, seed, size).unwrap();
+            assert_eq!(compared, 2);
+            assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+        }
+    }
+}